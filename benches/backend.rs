@@ -0,0 +1,92 @@
+#![feature(test)]
+
+extern crate test;
+extern crate viola;
+
+use std::string::String as StdString;
+
+use test::Bencher;
+use viola::gap;
+use viola::piece;
+use viola::text::TextBuffer;
+
+const LINE: &'static str = "The quick brown fox jumps over the lazy dog.\n";
+
+// Returns a text of about `len` bytes.
+fn text(len: usize) -> StdString {
+    LINE.repeat(len / LINE.len() + 1)
+}
+
+// Returns pseudo-random offsets within `len`, aligned to line starts.
+fn offsets(len: usize, count: usize) -> Vec<usize> {
+    let mut state = 0x2545f491u64;
+    (0..count).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state as usize % (len / LINE.len())) * LINE.len()
+    }).collect()
+}
+
+// Inserts a word at each offset.
+fn scattered<T: TextBuffer<Unit = str>>(buf: &mut T, offsets: &[usize]) {
+    for &offset in offsets {
+        buf.splice(offset..offset, "word ");
+    }
+}
+
+// Types a line one char at a time in the middle of the buffer.
+fn typing<T: TextBuffer<Unit = str>>(buf: &mut T) {
+    let mut offset = buf.len() / LINE.len() / 2 * LINE.len();
+    for ch in LINE.chars() {
+        let src = ch.to_string();
+        offset = buf.splice(offset..offset, &src).end;
+    }
+}
+
+// Iterates over every char of the buffer.
+fn chars<T: TextBuffer<Unit = str>>(buf: &T) -> usize {
+    buf.chunks(..).chars().count()
+}
+
+#[bench]
+fn gap_scattered_1m(b: &mut Bencher) {
+    let init = text(1 << 20);
+    let offsets = offsets(init.len(), 100);
+    b.iter(|| scattered(&mut gap::String::from(&init[..]), &offsets));
+}
+
+#[bench]
+fn piece_scattered_1m(b: &mut Bencher) {
+    let init = text(1 << 20);
+    let offsets = offsets(init.len(), 100);
+    b.iter(|| scattered(&mut piece::String::from(&init[..]), &offsets));
+}
+
+#[bench]
+fn gap_typing_1m(b: &mut Bencher) {
+    let init = text(1 << 20);
+    b.iter(|| typing(&mut gap::String::from(&init[..])));
+}
+
+#[bench]
+fn piece_typing_1m(b: &mut Bencher) {
+    let init = text(1 << 20);
+    b.iter(|| typing(&mut piece::String::from(&init[..])));
+}
+
+#[bench]
+fn gap_chars_1m(b: &mut Bencher) {
+    let init = text(1 << 20);
+    let mut buf = gap::String::from(&init[..]);
+    scattered(&mut buf, &offsets(init.len(), 100));
+    b.iter(|| chars(&buf));
+}
+
+#[bench]
+fn piece_chars_1m(b: &mut Bencher) {
+    let init = text(1 << 20);
+    let mut buf = piece::String::from(&init[..]);
+    scattered(&mut buf, &offsets(init.len(), 100));
+    b.iter(|| chars(&buf));
+}
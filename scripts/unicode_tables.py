@@ -0,0 +1,182 @@
+#!/usr/bin/env python3
+"""Generates src/unicode/tables.rs from the Unicode character database.
+
+Usage: scripts/unicode_tables.py [UCD_DIR]
+
+Reads the UCD files from UCD_DIR, laid out as in the published ucd directory, or downloads them
+for the pinned version if no directory is given.
+"""
+
+import os
+import sys
+import urllib.request
+
+UNICODE_VERSION = '16.0.0'
+
+URL = 'https://www.unicode.org/Public/%s/ucd/' % UNICODE_VERSION
+
+OUTPUT = os.path.join(os.path.dirname(os.path.abspath(__file__)),
+                      '..', 'src', 'unicode', 'tables.rs')
+
+# Grapheme_Cluster_Break values and their GraphemeBreak variants.
+GRAPHEME_BREAK = {
+    'CR': 'Cr',
+    'LF': 'Lf',
+    'Control': 'Control',
+    'Extend': 'Extend',
+    'L': 'L',
+    'LV': 'Lv',
+    'LVT': 'Lvt',
+    'Prepend': 'Prepend',
+    'Regional_Indicator': 'RegionalIndicator',
+    'SpacingMark': 'SpacingMark',
+    'T': 'T',
+    'V': 'V',
+    'ZWJ': 'Zwj',
+}
+
+# Hangul medial vowels and final consonants, which combine with a leading consonant.
+HANGUL_JAMO = [(0x1160, 0x11ff), (0xd7b0, 0xd7ff)]
+
+SOFT_HYPHEN = 0xad
+
+
+def read(ucd, path):
+    if ucd is None:
+        with urllib.request.urlopen(URL + path) as response:
+            return response.read().decode('utf-8')
+    with open(os.path.join(ucd, path), encoding='utf-8') as f:
+        return f.read()
+
+
+def parse_range(field):
+    bounds = field.strip().split('..')
+    return int(bounds[0], 16), int(bounds[-1], 16)
+
+
+# Returns (first, last, value) for each entry of a UCD data file.
+def entries(text):
+    for line in text.splitlines():
+        line = line.split('#', 1)[0].strip()
+        if line:
+            fields = [field.strip() for field in line.split(';')]
+            first, last = parse_range(fields[0])
+            yield first, last, fields[1]
+
+
+# Returns (first, last, value) for each default value given by an @missing line, in order.
+def missing(text):
+    for line in text.splitlines():
+        if line.startswith('# @missing:'):
+            fields = [field.strip() for field in line[len('# @missing:'):].split(';')]
+            first, last = parse_range(fields[0])
+            yield first, last, fields[1]
+
+
+def chars(ranges):
+    return set(c for first, last in ranges for c in range(first, last + 1))
+
+
+def grapheme_break(ucd):
+    values = {}
+    for first, last, value in entries(read(ucd, 'auxiliary/GraphemeBreakProperty.txt')):
+        for c in range(first, last + 1):
+            values[c] = GRAPHEME_BREAK[value]
+    for first, last, value in entries(read(ucd, 'emoji/emoji-data.txt')):
+        if value == 'Extended_Pictographic':
+            for c in range(first, last + 1):
+                if c in values:
+                    sys.exit('U+%04X is Extended_Pictographic and %s' % (c, values[c]))
+                values[c] = 'ExtendedPictographic'
+
+    table = []
+    for c in sorted(values):
+        if table and table[-1][1] + 1 == c and table[-1][2] == values[c]:
+            table[-1][1] = c
+        else:
+            table.append([c, c, values[c]])
+    return table
+
+
+def zero_width(ucd):
+    categories = ('Mn', 'Me', 'Cf')
+    ranges = [(first, last) for first, last, category
+              in entries(read(ucd, 'extracted/DerivedGeneralCategory.txt'))
+              if category in categories]
+    return (chars(ranges) | chars(HANGUL_JAMO)) - {SOFT_HYPHEN}
+
+
+def wide(ucd, zero):
+    text = read(ucd, 'EastAsianWidth.txt')
+
+    # Unlisted code points take the value of the last @missing line covering them.
+    widths = {}
+    for first, last, width in missing(text):
+        for c in range(first, last + 1):
+            widths[c] = width
+    for first, last, width in entries(text):
+        for c in range(first, last + 1):
+            widths[c] = width
+
+    emoji = [(first, last) for first, last, value in entries(read(ucd, 'emoji/emoji-data.txt'))
+             if value == 'Emoji_Presentation']
+    wide = set(c for c, width in widths.items() if width in ('W', 'F'))
+    return (wide | chars(emoji)) - zero
+
+
+def merge(chars):
+    table = []
+    for c in sorted(chars):
+        if table and table[-1][1] + 1 == c:
+            table[-1][1] = c
+        else:
+            table.append([c, c])
+    return table
+
+
+def main():
+    ucd = sys.argv[1] if len(sys.argv) > 1 else None
+    zero = zero_width(ucd)
+
+    lines = [
+        '// Generated by scripts/unicode_tables.py from the Unicode character database. Adjacent',
+        '// ranges are merged.',
+        '',
+        'use super::GraphemeBreak;',
+        'use super::GraphemeBreak::*;',
+        '',
+        '// Grapheme_Cluster_Break property values from Unicode %s, with Extended_Pictographic'
+        ' chars as a' % UNICODE_VERSION,
+        '// separate value.',
+        "pub const GRAPHEME_BREAK: &'static [(char, char, GraphemeBreak)] = &[",
+    ]
+    for first, last, value in grapheme_break(ucd):
+        lines.append("    ('\\u{%x}', '\\u{%x}', %s)," % (first, last, value))
+    lines += [
+        '];',
+        '',
+        '// Nonspacing marks, enclosing marks and format chars other than soft hyphen, from'
+        ' Unicode %s,' % UNICODE_VERSION,
+        '// and Hangul medial vowels and final consonants.',
+        "pub const ZERO_WIDTH: &'static [(char, char)] = &[",
+    ]
+    for first, last in merge(zero):
+        lines.append("    ('\\u{%x}', '\\u{%x}')," % (first, last))
+    lines += [
+        '];',
+        '',
+        '// East_Asian_Width Wide and Fullwidth chars and Emoji_Presentation chars from Unicode'
+        ' %s,' % UNICODE_VERSION,
+        '// excluding zero width chars.',
+        "pub const WIDE: &'static [(char, char)] = &[",
+    ]
+    for first, last in merge(wide(ucd, zero)):
+        lines.append("    ('\\u{%x}', '\\u{%x}')," % (first, last))
+    lines.append('];')
+
+    with open(OUTPUT, 'w') as f:
+        f.write('\n'.join(lines) + '\n')
+
+
+if __name__ == '__main__':
+    main()
@@ -0,0 +1,300 @@
+//! Blockwise selections.
+//!
+//! A block selects the same display columns in a range of lines, as in visual block mode. Lines
+//! which end before the left edge of a block are short, and are left as they are by most edits.
+//! Wide chars such as tabs may be cut by the edges of a block, in which case an edit replaces
+//! them with spaces for their cells outside the block.
+//!
+//! Blocks are yanked, deleted and changed like other regions with the `operator` module. The
+//! edits here work on the lines of a block as a whole, and return their changes as a single
+//! batch of operations.
+
+use std::cmp;
+use std::iter;
+use std::ops::Range;
+use std::string::String as StdString;
+use std::usize;
+
+use gap::String;
+use operation::Operation;
+use operator::Edit;
+use register::{Kind, Value};
+use width;
+
+/// Block of display columns in a range of lines.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Block {
+    /// Range of lines, which must not be empty.
+    pub lines: Range<usize>,
+
+    /// Range of columns, which must not be empty. Ending at `usize::MAX` extends the block to the
+    /// end of each line, as after `$`.
+    pub cols: Range<usize>,
+}
+
+impl Block {
+    /// Returns the block with the chars at `anchor` and `cursor` in opposite corners, as selected
+    /// in visual block mode, or extending to the end of each line if `to_end` is set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `anchor` or `cursor` is out of bounds.
+    pub fn new(text: &String, anchor: usize, cursor: usize, to_end: bool, tabstop: usize) -> Self {
+        // Returns the columns of the grapheme cluster at an offset, or one column at a line end.
+        let cells = |offset: usize| {
+            let start = text.virtcol(offset, tabstop);
+            let line_end = text.line_range(text.line_of(offset)).end;
+            let next = cmp::min(text.next_grapheme_boundary(offset), line_end);
+            (start, cmp::max(text.virtcol(next, tabstop), start + 1))
+        };
+        let (anchor_cells, cursor_cells) = (cells(anchor), cells(cursor));
+        let (anchor_line, cursor_line) = (text.line_of(anchor), text.line_of(cursor));
+        let end = if to_end { usize::MAX } else { cmp::max(anchor_cells.1, cursor_cells.1) };
+        Block {
+            lines: cmp::min(anchor_line, cursor_line)..(cmp::max(anchor_line, cursor_line) + 1),
+            cols: cmp::min(anchor_cells.0, cursor_cells.0)..end,
+        }
+    }
+
+    /// Returns `true` if the block extends to the end of each line.
+    pub fn is_to_end(&self) -> bool {
+        self.cols.end == usize::MAX
+    }
+
+    /// Returns the text in the block, with a line break between lines.
+    ///
+    /// Wide chars cut by the edges of the block are replaced with spaces for their cells in the
+    /// block, and short lines are empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the block is out of bounds.
+    pub fn text(&self, text: &String, tabstop: usize) -> StdString {
+        let insides: Vec<_> = self.cuts(text, tabstop).into_iter().map(|cut| cut.inside).collect();
+        insides.join("\n")
+    }
+
+    // Returns the part of each line in the block.
+    pub(crate) fn cuts(&self, text: &String, tabstop: usize) -> Vec<Cut> {
+        let cols = self.cols.clone();
+        assert!(self.lines.start < self.lines.end && cols.start < cols.end, "empty block");
+        self.lines.clone().map(|line| {
+            let range = text.line_range(line);
+            let mut cut = Cut {
+                range: range.end..range.end,
+                col: 0,
+                before: 0,
+                inside: StdString::new(),
+                outside: StdString::new(),
+            };
+            let mut after = 0;
+            let mut started = false;
+            let mut col = 0;
+            for (index, cluster) in text.slice(range.clone()).grapheme_indices() {
+                let end = cluster.chars()
+                    .fold(col, |col, ch| col + width::char_width(ch, col, tabstop));
+                if col >= cols.end {
+                    break;
+                }
+                // Clusters occupying no cells are in the block if they are displayed in it.
+                if cmp::max(end, col + 1) > cols.start {
+                    if !started {
+                        started = true;
+                        cut.range.start = range.start + index;
+                        cut.before = cols.start.saturating_sub(col);
+                    }
+                    cut.range.end = range.start + index + cluster.len();
+                    after = end.saturating_sub(cols.end);
+                    if col >= cols.start && end <= cols.end {
+                        cut.inside.extend(cluster.chars());
+                    } else {
+                        let cells = cmp::min(end, cols.end) - cmp::max(col, cols.start);
+                        cut.inside.push_str(&spaces(cells));
+                    }
+                }
+                col = end;
+                if !started {
+                    cut.col = col;
+                }
+            }
+            if started {
+                cut.col = text.virtcol(cut.range.start, tabstop);
+            }
+            cut.outside = spaces(cut.before + after);
+            cut
+        }).collect()
+    }
+}
+
+// Part of a line in a block.
+pub(crate) struct Cut {
+    // Bytes of the grapheme clusters in the block, or the end of the line if it is short.
+    pub(crate) range: Range<usize>,
+
+    // Display column of the start of the range.
+    pub(crate) col: usize,
+
+    // Cells of the first cluster before the block.
+    pub(crate) before: usize,
+
+    // Text in the block, with spaces for the cells of wide clusters cut by its edges.
+    pub(crate) inside: StdString,
+
+    // Spaces for the cells of the clusters outside the block.
+    pub(crate) outside: StdString,
+}
+
+// Where text is inserted at a column of a line.
+pub(crate) struct Insertion {
+    // Bytes replaced, which are a wide char cut by the column or else empty.
+    dest: Range<usize>,
+
+    // Spaces before and after the text, for the cells of a cut wide char or to pad a short line.
+    lead: usize,
+    trail: usize,
+
+    // Whether the line ends at or before the column.
+    pub(crate) short: bool,
+}
+
+impl Insertion {
+    pub(crate) fn new(text: &String, line: usize, col: usize, tabstop: usize) -> Self {
+        let block = Block { lines: line..(line + 1), cols: col..(col + 1) };
+        let cut = block.cuts(text, tabstop).remove(0);
+        let (dest, lead, trail) = if cut.range.is_empty() {
+            (cut.range.clone(), col - cut.col, 0)
+        } else if cut.before > 0 {
+            (cut.range.clone(), cut.before, cut.outside.len() + 1 - cut.before)
+        } else {
+            (cut.range.start..cut.range.start, 0, 0)
+        };
+        Insertion {
+            short: cut.range.is_empty(),
+            dest: dest,
+            lead: lead,
+            trail: trail,
+        }
+    }
+
+    // Returns where the text starts after inserting it.
+    pub(crate) fn start(&self) -> usize {
+        self.dest.start + self.lead
+    }
+
+    pub(crate) fn operation(&self, src: &str) -> Operation<StdString> {
+        let mut spaced = spaces(self.lead);
+        spaced.push_str(src);
+        spaced.push_str(&spaces(self.trail));
+        Operation { dest: self.dest.clone(), src: spaced }
+    }
+}
+
+/// Inserts `s` before the block in each line, as with `I` in visual block mode.
+///
+/// Short lines are left as they are, except the first line, where `s` is inserted at the end. As
+/// in vi, text with a line break is only inserted in the first line. The cursor moves to the start
+/// of the text in the first line.
+///
+/// # Panics
+///
+/// Panics if the block is out of bounds.
+pub fn insert(text: &String, block: &Block, s: &str, tabstop: usize) -> Edit {
+    let first = block.lines.start;
+    let mut operations = Vec::new();
+    let mut cursor = 0;
+    for line in replicated_lines(block, s) {
+        let insertion = Insertion::new(text, line, block.cols.start, tabstop);
+        let (operation, start) = if insertion.short {
+            if line > first {
+                continue;
+            }
+            let end = text.line_range(line).end;
+            (Operation { dest: end..end, src: s.to_owned() }, end)
+        } else {
+            (insertion.operation(s), insertion.start())
+        };
+        if line == first {
+            cursor = start;
+        }
+        operations.push(operation);
+    }
+    edit(operations, None, cursor)
+}
+
+/// Appends `s` after the block in each line, as with `A` in visual block mode.
+///
+/// Short lines are padded with spaces to the end of the block. If the block extends to the end of
+/// each line, `s` is appended to each line as it is. As in vi, text with a line break is only
+/// appended in the first line. The cursor moves to the start of the text in the first line.
+///
+/// # Panics
+///
+/// Panics if the block is out of bounds.
+pub fn append(text: &String, block: &Block, s: &str, tabstop: usize) -> Edit {
+    let mut operations = Vec::new();
+    let mut cursor = 0;
+    for line in replicated_lines(block, s) {
+        let (operation, start) = if block.is_to_end() {
+            let end = text.line_range(line).end;
+            (Operation { dest: end..end, src: s.to_owned() }, end)
+        } else {
+            let insertion = Insertion::new(text, line, block.cols.end, tabstop);
+            (insertion.operation(s), insertion.start())
+        };
+        if line == block.lines.start {
+            cursor = start;
+        }
+        operations.push(operation);
+    }
+    edit(operations, None, cursor)
+}
+
+/// Replaces the block with `s` in each line, as with `c` in visual block mode.
+///
+/// Short lines are left as they are, except the first line, where `s` is inserted at the end. As
+/// in vi, text with a line break only replaces the block in the first line, and the block is
+/// deleted from the other lines. The cursor moves to the start of the text in the first line.
+///
+/// # Panics
+///
+/// Panics if the block is out of bounds.
+pub fn change(text: &String, block: &Block, s: &str, tabstop: usize) -> Edit {
+    let replicated = !s.contains('\n');
+    let mut operations = Vec::new();
+    let mut cursor = 0;
+    for (index, cut) in block.cuts(text, tabstop).into_iter().enumerate() {
+        if index == 0 {
+            cursor = cut.range.start + cut.before;
+        } else if cut.range.is_empty() {
+            continue;
+        }
+        let mut src = spaces(cut.before);
+        if index == 0 || replicated {
+            src.push_str(s);
+        }
+        src.push_str(&spaces(cut.outside.len() - cut.before));
+        operations.push(Operation { dest: cut.range, src: src });
+    }
+    let yanked = Value { text: block.text(text, tabstop), kind: Kind::Blockwise };
+    edit(operations, Some(yanked), cursor)
+}
+
+fn replicated_lines(block: &Block, s: &str) -> Range<usize> {
+    if s.contains('\n') {
+        block.lines.start..(block.lines.start + 1)
+    } else {
+        block.lines.clone()
+    }
+}
+
+fn edit(operations: Vec<Operation<StdString>>, yanked: Option<Value>, cursor: usize) -> Edit {
+    Edit {
+        operations: operations,
+        yanked: yanked,
+        cursor: cursor,
+    }
+}
+
+fn spaces(cells: usize) -> StdString {
+    iter::repeat(' ').take(cells).collect()
+}
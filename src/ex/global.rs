@@ -0,0 +1,131 @@
+use std::ops::Range;
+
+use gap::{Anchor, Gravity, String};
+use regex::Regex;
+use super::Target;
+
+// Line marked to run the command on.
+//
+// The start anchor moves after text inserted before the line, such as lines added by earlier
+// commands. The end anchor follows the line break, so a line is deleted when the two meet, and
+// joined to the line before when its start is no longer at the start of a line. The last line has
+// no line break, so can only be joined.
+#[derive(Debug)]
+struct Mark {
+    start: Anchor,
+    end: Option<Anchor>,
+}
+
+/// Runs vi's `:g` command, or `:v` if `invert` is set, over `lines` of the target.
+///
+/// Lines in which `regex` has a match starting, or has none if inverted, are marked first. Then
+/// `command` is run with the current line of each marked line in turn, as earlier commands may
+/// have moved it. Marked lines which an earlier command deleted or joined to another line are
+/// skipped, as in vi.
+///
+/// All edits are made in a single undo transaction which begins with the cursor at `cursor`. The
+/// transaction is committed with the cursor at the start of the last line run, or at `cursor` if
+/// none was. If `command` returns an error, the remaining lines are skipped, the transaction is
+/// committed and the error is returned.
+///
+/// Returns the number of lines the command was run on.
+///
+/// # Panics
+///
+/// Panics if `lines` is out of bounds, or if a transaction has already begun.
+pub fn global<T, F, E>(
+    target: &mut T,
+    lines: Range<usize>,
+    regex: &Regex,
+    invert: bool,
+    cursor: usize,
+    mut command: F,
+) -> Result<usize, E>
+where T: Target, F: FnMut(&mut T, usize) -> Result<(), E> {
+    let marks: Vec<_> = matching(target.text(), lines, regex, invert).into_iter()
+        .map(|line| mark(target.text_mut(), line))
+        .collect();
+
+    target.begin(cursor);
+    let mut count = 0;
+    let mut after = cursor;
+    let mut result = Ok(());
+    for mark in marks {
+        let line = resolve(target.text(), &mark);
+        target.text_mut().remove_anchor(mark.start);
+        if let Some(end) = mark.end {
+            target.text_mut().remove_anchor(end);
+        }
+
+        let line = match line {
+            Some(line) if result.is_ok() => line,
+            _ => continue,
+        };
+        result = command(target, line);
+        count += 1;
+        let text = target.text();
+        after = text.line_range(line.min(text.line_count() - 1)).start;
+    }
+    target.commit(after);
+    result.map(|()| count)
+}
+
+// Returns the lines in which a match starts, or the other lines if inverted.
+fn matching(text: &String, lines: Range<usize>, regex: &Regex, invert: bool) -> Vec<usize> {
+    assert!(lines.start <= lines.end && lines.end <= text.line_count(), "lines out of bounds");
+    let mut matched = Vec::new();
+    let mut line = lines.start;
+    while line < lines.end {
+        let found = match regex.find_at(text.as_str(), text.line_range(line).start) {
+            Some(found) => text.line_of(found.start),
+            None => break,
+        };
+        if found >= lines.end {
+            break;
+        }
+        matched.push(found);
+        line = found + 1;
+    }
+
+    if invert {
+        let mut matched = matched.into_iter().peekable();
+        lines.filter(|&line| {
+            if matched.peek() == Some(&line) {
+                matched.next();
+                false
+            } else {
+                true
+            }
+        }).collect()
+    } else {
+        matched
+    }
+}
+
+fn mark(text: &mut String, line: usize) -> Mark {
+    let range = text.line_range(line);
+    Mark {
+        start: text.anchor(range.start, Gravity::Right),
+        end: if range.end < text.len() {
+            Some(text.anchor(range.end + 1, Gravity::Left))
+        } else {
+            None
+        },
+    }
+}
+
+// Returns the current line of a mark, or `None` if it was deleted or joined.
+fn resolve(text: &String, mark: &Mark) -> Option<usize> {
+    let start = text.anchor_offset(mark.start)?;
+    if let Some(end) = mark.end {
+        if text.anchor_offset(end)? <= start {
+            return None;
+        }
+    }
+    let line = text.line_of(start);
+    if text.line_range(line).start == start {
+        Some(line)
+    } else {
+        None
+    }
+}
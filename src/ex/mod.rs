@@ -0,0 +1,73 @@
+//! Ex commands.
+//!
+//! Engines for the ex commands which edit many places of a buffer at once. Each produces a batch
+//! of operations or runs within a single transaction, so that the whole command is undone as a
+//! unit. Command lines are parsed into `Command`s by `parse`.
+
+use std::string::String as StdString;
+
+use gap::String;
+use history::{History, Tree};
+use operation::Operate;
+
+mod global;
+mod parse;
+mod substitute;
+
+pub use self::global::global;
+pub use self::parse::{Address, Arg, Base, Command, ErrorKind, LineRange, Name, ParseError};
+pub use self::parse::{Substitute, parse};
+pub use self::substitute::{Confirm, Flags, ParseFlagsError, Substitution, Template, substitute};
+
+/// Text with an undo history, which ex commands edit.
+pub trait Target: Operate<Owned = StdString> {
+    /// Returns the text.
+    fn text(&self) -> &String;
+
+    /// Returns the text to add and remove anchors.
+    ///
+    /// The contents must only be changed by applying operations to the target.
+    fn text_mut(&mut self) -> &mut String;
+
+    /// Begins an undo transaction with the cursor at `cursor`.
+    fn begin(&mut self, cursor: usize);
+
+    /// Commits the undo transaction with the cursor at `cursor`.
+    fn commit(&mut self, cursor: usize);
+}
+
+impl Target for History<String> {
+    fn text(&self) -> &String {
+        self.get_ref()
+    }
+
+    fn text_mut(&mut self) -> &mut String {
+        self.get_mut()
+    }
+
+    fn begin(&mut self, cursor: usize) {
+        History::begin(self, cursor);
+    }
+
+    fn commit(&mut self, cursor: usize) {
+        History::commit(self, cursor);
+    }
+}
+
+impl Target for Tree<String> {
+    fn text(&self) -> &String {
+        self.get_ref()
+    }
+
+    fn text_mut(&mut self) -> &mut String {
+        self.get_mut()
+    }
+
+    fn begin(&mut self, cursor: usize) {
+        Tree::begin(self, cursor);
+    }
+
+    fn commit(&mut self, cursor: usize) {
+        Tree::commit(self, cursor);
+    }
+}
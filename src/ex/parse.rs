@@ -0,0 +1,805 @@
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::string::String as StdString;
+
+use search::Direction;
+use super::Flags;
+
+/// Base of a line address.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Base {
+    /// The current line, as with `.` or an address which is only an offset.
+    Current,
+
+    /// The last line, as with `$`.
+    Last,
+
+    /// A line number from one, or zero for before the first line.
+    Line(usize),
+
+    /// The line of a mark, as with `'a`.
+    Mark(char),
+
+    /// The next line matching a pattern, as with `/pat/`, or the previous one, as with `?pat?`.
+    ///
+    /// An empty pattern repeats the last search.
+    Search(StdString, Direction),
+}
+
+/// Line address, such as `.+1` or `/pat/-2`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Address {
+    /// Base line.
+    pub base: Base,
+
+    /// Number of lines added to the base.
+    pub offset: isize,
+}
+
+/// Range of lines.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LineRange {
+    /// First line, or the only line.
+    pub start: Address,
+
+    /// Last line, if two addresses were given.
+    pub end: Option<Address>,
+
+    /// Whether the current line is set to the start before the end is found, as with `;`.
+    pub set_current: bool,
+}
+
+/// Name of an ex command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Name {
+    /// Only a range, which moves to its last line.
+    Goto,
+
+    /// `:copy` or `:t`.
+    Copy,
+
+    /// `:delete`.
+    Delete,
+
+    /// `:edit`.
+    Edit,
+
+    /// `:global`, or `:vglobal` with a bang.
+    Global,
+
+    /// `:join`.
+    Join,
+
+    /// `:mark` or `:k`.
+    Mark,
+
+    /// `:move`.
+    Move,
+
+    /// `:normal`.
+    Normal,
+
+    /// `:print`.
+    Print,
+
+    /// `:put`.
+    Put,
+
+    /// `:quit`.
+    Quit,
+
+    /// `:read`.
+    Read,
+
+    /// `:redo`.
+    Redo,
+
+    /// `:set`.
+    Set,
+
+    /// `:substitute`, or `:&` to repeat it.
+    Substitute,
+
+    /// `:undo`.
+    Undo,
+
+    /// `:vglobal`.
+    VGlobal,
+
+    /// `:write`.
+    Write,
+
+    /// `:wq`.
+    WriteQuit,
+
+    /// `:xit`.
+    Xit,
+
+    /// `:yank`.
+    Yank,
+
+    /// `:<`.
+    ShiftLeft,
+
+    /// `:>`.
+    ShiftRight,
+
+    /// `:!`.
+    Shell,
+
+    /// `:=`.
+    LineNumber,
+}
+
+/// Argument of an ex command.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Arg {
+    /// No argument.
+    None,
+
+    /// Text such as a file name, the keys of `:normal` or a shell command.
+    Text(StdString),
+
+    /// Destination of `:move` and `:copy`.
+    Address(Address),
+
+    /// Register and count of `:delete`, `:yank` and `:put`.
+    Register {
+        /// Register name.
+        register: Option<char>,
+
+        /// Number of lines from the last line of the range.
+        count: Option<usize>,
+    },
+
+    /// Count of lines of `:join` and `:print`.
+    Count(usize),
+
+    /// Shifts and count of `:<` and `:>`.
+    Shift {
+        /// Number of shifts, given by repeating the command char.
+        shifts: usize,
+
+        /// Number of lines from the last line of the range.
+        count: Option<usize>,
+    },
+
+    /// Pattern, replacement and flags of `:s`.
+    Substitute(Substitute),
+
+    /// Pattern and command of `:g` and `:v`.
+    Global {
+        /// Pattern to match lines.
+        pattern: StdString,
+
+        /// Command to run on each line, which is `p` if none was given.
+        command: StdString,
+    },
+
+    /// Mark name of `:mark`.
+    Mark(char),
+}
+
+/// Argument of `:s`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Substitute {
+    /// Pattern, or `None` to repeat the last substitution. An empty pattern repeats the last
+    /// search.
+    pub pattern: Option<StdString>,
+
+    /// Replacement, or `None` to repeat the last substitution.
+    pub replacement: Option<StdString>,
+
+    /// Flags.
+    pub flags: Flags,
+}
+
+/// Parsed ex command.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Command {
+    /// Range of lines, or `None` for the command's default.
+    pub range: Option<LineRange>,
+
+    /// Command name.
+    pub name: Name,
+
+    /// Whether the name was followed by `!`.
+    pub bang: bool,
+
+    /// Argument.
+    pub arg: Arg,
+}
+
+/// Error parsing an ex command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Column of the offending char, counted in chars from zero.
+    pub column: usize,
+
+    /// Kind of error.
+    pub kind: ErrorKind,
+}
+
+/// Kind of ex command line error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Name which is not a command.
+    UnknownCommand(StdString),
+
+    /// Invalid mark name.
+    InvalidMark,
+
+    /// Line number or count too large.
+    NumberTooLarge,
+
+    /// Missing address where one is required.
+    MissingAddress,
+
+    /// Missing argument where one is required.
+    MissingArgument,
+
+    /// Pattern delimiter which is a letter, digit, `\`, `"` or `|`.
+    InvalidDelimiter,
+
+    /// Invalid `:s` flag.
+    InvalidFlag(char),
+
+    /// Range given to a command which takes none.
+    NoRangeAllowed,
+
+    /// Bang given to a command which takes none.
+    NoBangAllowed,
+
+    /// Unexpected chars after a command.
+    TrailingCharacters,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.kind {
+            ErrorKind::UnknownCommand(ref name) => write!(f, "not an editor command: {}", name)?,
+            ErrorKind::InvalidMark => f.write_str("invalid mark")?,
+            ErrorKind::NumberTooLarge => f.write_str("number too large")?,
+            ErrorKind::MissingAddress => f.write_str("missing address")?,
+            ErrorKind::MissingArgument => f.write_str("argument required")?,
+            ErrorKind::InvalidDelimiter => f.write_str("invalid pattern delimiter")?,
+            ErrorKind::InvalidFlag(flag) => write!(f, "invalid flag {:?}", flag)?,
+            ErrorKind::NoRangeAllowed => f.write_str("no range allowed")?,
+            ErrorKind::NoBangAllowed => f.write_str("no ! allowed")?,
+            ErrorKind::TrailingCharacters => f.write_str("trailing characters")?,
+        }
+        write!(f, " at column {}", self.column + 1)
+    }
+}
+
+impl error::Error for ParseError {}
+
+// Kinds of argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    None,
+    File,
+    Rest,
+    Address,
+    Register,
+    Count,
+    Shift,
+    Substitute,
+    Global,
+    Mark,
+}
+
+// Command table entry.
+struct Entry {
+    name: &'static str,
+    abbrev: usize,
+    command: Name,
+    range: bool,
+    bang: bool,
+    kind: Kind,
+}
+
+const fn entry(
+    name: &'static str,
+    abbrev: usize,
+    command: Name,
+    range: bool,
+    bang: bool,
+    kind: Kind,
+) -> Entry {
+    Entry { name: name, abbrev: abbrev, command: command, range: range, bang: bang, kind: kind }
+}
+
+// Commands with their shortest abbreviations.
+static COMMANDS: &'static [Entry] = &[
+    entry("copy", 2, Name::Copy, true, false, Kind::Address),
+    entry("delete", 1, Name::Delete, true, false, Kind::Register),
+    entry("edit", 1, Name::Edit, false, true, Kind::File),
+    entry("global", 1, Name::Global, true, true, Kind::Global),
+    entry("join", 1, Name::Join, true, true, Kind::Count),
+    entry("k", 1, Name::Mark, true, false, Kind::Mark),
+    entry("mark", 2, Name::Mark, true, false, Kind::Mark),
+    entry("move", 1, Name::Move, true, false, Kind::Address),
+    entry("normal", 4, Name::Normal, true, true, Kind::Rest),
+    entry("print", 1, Name::Print, true, false, Kind::Count),
+    entry("put", 2, Name::Put, true, true, Kind::Register),
+    entry("quit", 1, Name::Quit, false, true, Kind::None),
+    entry("read", 1, Name::Read, true, false, Kind::File),
+    entry("redo", 3, Name::Redo, false, false, Kind::None),
+    entry("set", 2, Name::Set, false, true, Kind::File),
+    entry("substitute", 1, Name::Substitute, true, false, Kind::Substitute),
+    entry("t", 1, Name::Copy, true, false, Kind::Address),
+    entry("undo", 1, Name::Undo, false, true, Kind::None),
+    entry("vglobal", 1, Name::VGlobal, true, false, Kind::Global),
+    entry("wq", 2, Name::WriteQuit, true, true, Kind::File),
+    entry("write", 1, Name::Write, true, true, Kind::File),
+    entry("xit", 1, Name::Xit, true, true, Kind::File),
+    entry("yank", 1, Name::Yank, true, false, Kind::Register),
+    entry("&", 1, Name::Substitute, true, false, Kind::Substitute),
+    entry("<", 1, Name::ShiftLeft, true, false, Kind::Shift),
+    entry(">", 1, Name::ShiftRight, true, false, Kind::Shift),
+    entry("!", 1, Name::Shell, true, false, Kind::Rest),
+    entry("=", 1, Name::LineNumber, true, false, Kind::None),
+];
+
+/// Parses an ex command line into commands separated by `|`.
+///
+/// A leading `:` and blanks are skipped, and empty commands are ignored. Commands whose argument
+/// is another command line or keys, such as `:g` and `:normal`, take the rest of the line
+/// including any `|`. Elsewhere `\|` is a literal `|`.
+pub fn parse(line: &str) -> Result<Vec<Command>, ParseError> {
+    let mut parser = Parser {
+        line: line,
+        pos: 0,
+    };
+    let mut commands = Vec::new();
+    loop {
+        parser.skip(|ch| ch == ':' || is_blank(ch));
+        if parser.peek().is_none() {
+            break;
+        }
+        if parser.eat('|') {
+            continue;
+        }
+        commands.push(parser.command()?);
+        parser.skip(is_blank);
+        match parser.peek() {
+            None => break,
+            Some('|') => parser.pos += 1,
+            Some(_) => return Err(parser.error(ErrorKind::TrailingCharacters)),
+        }
+    }
+    Ok(commands)
+}
+
+fn is_blank(ch: char) -> bool {
+    ch == ' ' || ch == '\t'
+}
+
+struct Parser<'a> {
+    line: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.line[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.pos += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        if self.peek() == Some(ch) {
+            self.pos += ch.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip<F: Fn(char) -> bool>(&mut self, f: F) -> &'a str {
+        let start = self.pos;
+        while self.peek().map_or(false, &f) {
+            self.bump();
+        }
+        &self.line[start..self.pos]
+    }
+
+    fn error(&self, kind: ErrorKind) -> ParseError {
+        self.error_at(self.pos, kind)
+    }
+
+    fn error_at(&self, pos: usize, kind: ErrorKind) -> ParseError {
+        ParseError {
+            column: self.line[..pos].chars().count(),
+            kind: kind,
+        }
+    }
+
+    fn command(&mut self) -> Result<Command, ParseError> {
+        let range = self.range()?;
+        self.skip(is_blank);
+
+        let start = self.pos;
+        let entry = match self.peek() {
+            None | Some('|') => {
+                return match range {
+                    Some(range) => Ok(Command {
+                        range: Some(range),
+                        name: Name::Goto,
+                        bang: false,
+                        arg: Arg::None,
+                    }),
+                    None => Err(self.error(ErrorKind::MissingAddress)),
+                };
+            },
+            Some(ch) if ch.is_alphabetic() => {
+                let word = self.skip(char::is_alphabetic);
+                match lookup(word) {
+                    Some(entry) => entry,
+                    // Marks may directly follow `k`, as in `:ka`.
+                    None if word.starts_with('k') => {
+                        self.pos = start + 1;
+                        lookup("k").unwrap()
+                    },
+                    None => {
+                        let kind = ErrorKind::UnknownCommand(word.to_owned());
+                        return Err(self.error_at(start, kind));
+                    },
+                }
+            },
+            Some(ch) => {
+                self.bump();
+                match lookup(&ch.to_string()) {
+                    Some(entry) => entry,
+                    None => {
+                        return Err(self.error_at(start, ErrorKind::UnknownCommand(ch.to_string())));
+                    },
+                }
+            },
+        };
+
+        if range.is_some() && !entry.range {
+            return Err(self.error_at(start, ErrorKind::NoRangeAllowed));
+        }
+        let bang = entry.command != Name::Shell && self.peek() == Some('!');
+        if bang {
+            if !entry.bang {
+                return Err(self.error(ErrorKind::NoBangAllowed));
+            }
+            self.bump();
+        }
+
+        let arg = self.arg(entry)?;
+        Ok(Command {
+            range: range,
+            name: entry.command,
+            bang: bang,
+            arg: arg,
+        })
+    }
+
+    fn range(&mut self) -> Result<Option<LineRange>, ParseError> {
+        self.skip(is_blank);
+        if self.eat('%') {
+            return Ok(Some(LineRange {
+                start: Address { base: Base::Line(1), offset: 0 },
+                end: Some(Address { base: Base::Last, offset: 0 }),
+                set_current: false,
+            }));
+        }
+
+        let mut range: Option<LineRange> = None;
+        let mut address = self.address()?;
+        loop {
+            self.skip(is_blank);
+            let set_current = match self.peek() {
+                Some(',') => false,
+                Some(';') => true,
+                _ => break,
+            };
+            self.bump();
+
+            // A missing address is the current line. Only the last two addresses are used.
+            let start = address.take().unwrap_or(Address { base: Base::Current, offset: 0 });
+            self.skip(is_blank);
+            let end = self.address()?.unwrap_or(Address { base: Base::Current, offset: 0 });
+            range = Some(LineRange {
+                start: start,
+                end: Some(end.clone()),
+                set_current: set_current,
+            });
+            address = Some(end);
+        }
+
+        Ok(match range {
+            Some(range) => Some(range),
+            None => address.map(|address| LineRange {
+                start: address,
+                end: None,
+                set_current: false,
+            }),
+        })
+    }
+
+    fn address(&mut self) -> Result<Option<Address>, ParseError> {
+        let base = match self.peek() {
+            Some('.') => {
+                self.bump();
+                Some(Base::Current)
+            },
+            Some('$') => {
+                self.bump();
+                Some(Base::Last)
+            },
+            Some(ch) if ch.is_ascii_digit() => Some(Base::Line(self.number()?)),
+            Some('\'') => {
+                self.bump();
+                match self.peek() {
+                    Some(ch) if is_mark(ch) => {
+                        self.bump();
+                        Some(Base::Mark(ch))
+                    },
+                    _ => return Err(self.error(ErrorKind::InvalidMark)),
+                }
+            },
+            Some(delimiter) if delimiter == '/' || delimiter == '?' => {
+                self.bump();
+                let pattern = self.pattern(delimiter);
+                let direction = if delimiter == '/' {
+                    Direction::Forward
+                } else {
+                    Direction::Backward
+                };
+                Some(Base::Search(pattern, direction))
+            },
+            _ => None,
+        };
+
+        let mut offset = 0isize;
+        let mut any = false;
+        loop {
+            let sign = match self.peek() {
+                Some('+') => 1,
+                Some('-') => -1,
+                // A number after an address is added to it.
+                Some(ch) if ch.is_ascii_digit() && base.is_some() => {
+                    offset = self.add(offset, 1)?;
+                    continue;
+                },
+                _ => break,
+            };
+            self.bump();
+            any = true;
+            let start = self.pos;
+            let n = if self.peek().map_or(false, |ch| ch.is_ascii_digit()) {
+                self.number()?
+            } else {
+                1
+            };
+            if n > isize::max_value() as usize {
+                return Err(self.error_at(start, ErrorKind::NumberTooLarge));
+            }
+            offset = self.add(offset, sign * n as isize)?;
+        }
+
+        Ok(match base {
+            Some(base) => Some(Address { base: base, offset: offset }),
+            None if any => Some(Address { base: Base::Current, offset: offset }),
+            None => None,
+        })
+    }
+
+    // Adds a number following an address to the offset.
+    fn add(&mut self, offset: isize, sign: isize) -> Result<isize, ParseError> {
+        let start = self.pos;
+        let n = if sign == 1 && self.peek().map_or(false, |ch| ch.is_ascii_digit()) {
+            self.number()? as isize
+        } else {
+            sign
+        };
+        offset.checked_add(n).ok_or_else(|| self.error_at(start, ErrorKind::NumberTooLarge))
+    }
+
+    fn number(&mut self) -> Result<usize, ParseError> {
+        let start = self.pos;
+        let digits = self.skip(|ch| ch.is_ascii_digit());
+        match digits.parse::<usize>() {
+            Ok(n) if n <= isize::max_value() as usize => Ok(n),
+            _ => Err(self.error_at(start, ErrorKind::NumberTooLarge)),
+        }
+    }
+
+    // Reads a pattern up to an unescaped delimiter or the end of the line, consuming the
+    // delimiter.
+    fn pattern(&mut self, delimiter: char) -> StdString {
+        let mut pattern = StdString::new();
+        while let Some(ch) = self.bump() {
+            if ch == delimiter {
+                break;
+            }
+            pattern.push(ch);
+            if ch == '\\' {
+                if let Some(escaped) = self.bump() {
+                    pattern.push(escaped);
+                }
+            }
+        }
+        pattern
+    }
+
+    fn arg(&mut self, entry: &Entry) -> Result<Arg, ParseError> {
+        let arg = match entry.kind {
+            Kind::None => Arg::None,
+            Kind::File => {
+                self.skip(is_blank);
+                let text = self.text();
+                if text.is_empty() { Arg::None } else { Arg::Text(text) }
+            },
+            Kind::Rest => {
+                if entry.command != Name::Shell {
+                    self.skip(is_blank);
+                }
+                let rest = self.rest();
+                if rest.is_empty() {
+                    return Err(self.error(ErrorKind::MissingArgument));
+                }
+                self.pos = self.line.len();
+                Arg::Text(rest.to_owned())
+            },
+            Kind::Address => {
+                self.skip(is_blank);
+                match self.address()? {
+                    Some(address) => Arg::Address(address),
+                    None => return Err(self.error(ErrorKind::MissingAddress)),
+                }
+            },
+            Kind::Register => {
+                self.skip(is_blank);
+                let register = match self.peek() {
+                    Some(ch) if !ch.is_ascii_digit() && ch != '|' && !is_blank(ch) => {
+                        self.bump();
+                        Some(ch)
+                    },
+                    _ => None,
+                };
+                self.skip(is_blank);
+                let count = self.count()?;
+                if register.is_none() && count.is_none() {
+                    Arg::None
+                } else {
+                    Arg::Register { register: register, count: count }
+                }
+            },
+            Kind::Count => {
+                self.skip(is_blank);
+                match self.count()? {
+                    Some(count) => Arg::Count(count),
+                    None => Arg::None,
+                }
+            },
+            Kind::Shift => {
+                // Shifts are counted by repeating the command char.
+                let shifts = 1 + self.skip(|ch| ch.to_string() == entry.name).len();
+                self.skip(is_blank);
+                Arg::Shift { shifts: shifts, count: self.count()? }
+            },
+            Kind::Substitute => Arg::Substitute(self.substitute(entry)?),
+            Kind::Global => {
+                let delimiter = self.delimiter()?.ok_or_else(|| {
+                    self.error(ErrorKind::MissingArgument)
+                })?;
+                let pattern = self.pattern(delimiter);
+                let command = self.rest().trim_start_matches(is_blank);
+                self.pos = self.line.len();
+                Arg::Global {
+                    pattern: pattern,
+                    command: if command.is_empty() { "p".to_owned() } else { command.to_owned() },
+                }
+            },
+            Kind::Mark => {
+                self.skip(is_blank);
+                match self.peek() {
+                    Some(ch) if is_mark(ch) => {
+                        self.bump();
+                        Arg::Mark(ch)
+                    },
+                    Some(_) => return Err(self.error(ErrorKind::InvalidMark)),
+                    None => return Err(self.error(ErrorKind::MissingArgument)),
+                }
+            },
+        };
+        Ok(arg)
+    }
+
+    fn count(&mut self) -> Result<Option<usize>, ParseError> {
+        if self.peek().map_or(false, |ch| ch.is_ascii_digit()) {
+            self.number().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Reads text up to an unescaped `|`, unescaping `\|` and trimming trailing blanks.
+    fn text(&mut self) -> StdString {
+        let mut text = StdString::new();
+        while let Some(ch) = self.peek() {
+            if ch == '|' {
+                break;
+            }
+            self.bump();
+            if ch == '\\' && self.eat('|') {
+                text.push('|');
+            } else {
+                text.push(ch);
+            }
+        }
+        let len = text.trim_end_matches(is_blank).len();
+        text.truncate(len);
+        text
+    }
+
+    // Reads a pattern delimiter, or returns `None` at the end of the command.
+    fn delimiter(&mut self) -> Result<Option<char>, ParseError> {
+        match self.peek() {
+            None | Some('|') => Ok(None),
+            Some(ch) if ch.is_alphanumeric() || ch == '\\' || ch == '"' || is_blank(ch) => {
+                Err(self.error(ErrorKind::InvalidDelimiter))
+            },
+            Some(ch) => {
+                self.bump();
+                Ok(Some(ch))
+            },
+        }
+    }
+
+    fn substitute(&mut self, entry: &Entry) -> Result<Substitute, ParseError> {
+        let (pattern, replacement) = if entry.name == "&" {
+            (None, None)
+        } else {
+            match self.peek() {
+                Some(ch) if is_blank(ch) || ch.is_alphanumeric() => (None, None),
+                _ => match self.delimiter()? {
+                    Some(delimiter) => {
+                        let pattern = self.pattern(delimiter);
+                        let replacement = self.pattern(delimiter);
+                        (Some(pattern), Some(replacement))
+                    },
+                    None => (None, None),
+                },
+            }
+        };
+
+        self.skip(is_blank);
+        let start = self.pos;
+        let flags = self.skip(char::is_alphabetic);
+        let flags = flags.parse().map_err(|_| {
+            let (index, flag) = flags.char_indices()
+                .find(|&(_, ch)| ch.to_string().parse::<Flags>().is_err())
+                .unwrap();
+            self.error_at(start + index, ErrorKind::InvalidFlag(flag))
+        })?;
+        Ok(Substitute {
+            pattern: pattern,
+            replacement: replacement,
+            flags: flags,
+        })
+    }
+}
+
+// Returns the command a name abbreviates.
+fn lookup(name: &str) -> Option<&'static Entry> {
+    COMMANDS.iter().find(|entry| name.len() >= entry.abbrev && entry.name.starts_with(name))
+}
+
+fn is_mark(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || "<>[]'`^.\"".contains(ch)
+}
@@ -0,0 +1,348 @@
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+use std::str::FromStr;
+use std::string::String as StdString;
+
+use gap::{Str, String};
+use operation::Operation;
+use regex::{self, Captures, Regex};
+
+/// Flags of the `:s` command.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Flags {
+    /// Replace every match in a line rather than the first, as with `g`.
+    pub global: bool,
+
+    /// Confirm each replacement, as with `c`.
+    pub confirm: bool,
+
+    /// Only count the matches, as with `n`.
+    pub count_only: bool,
+
+    /// Ignore case, as with `i`, or match case if `Some(false)`, as with `I`.
+    pub ignore_case: Option<bool>,
+}
+
+impl Flags {
+    /// Returns the options to compile the pattern with.
+    pub fn regex_options(&self, options: &regex::Options) -> regex::Options {
+        match self.ignore_case {
+            Some(ignore_case) => regex::Options {
+                ignore_case: ignore_case,
+                smart_case: false,
+                ..*options
+            },
+            None => *options,
+        }
+    }
+}
+
+/// Error parsing `:s` flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseFlagsError {
+    flag: char,
+}
+
+impl Display for ParseFlagsError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "invalid substitute flag {:?}", self.flag)
+    }
+}
+
+impl error::Error for ParseFlagsError {}
+
+/// Parses flags such as `gc`.
+impl FromStr for Flags {
+    type Err = ParseFlagsError;
+
+    fn from_str(s: &str) -> Result<Self, ParseFlagsError> {
+        let mut flags = Flags::default();
+        for flag in s.chars() {
+            match flag {
+                'g' => flags.global = !flags.global,
+                'c' => flags.confirm = true,
+                'n' => flags.count_only = true,
+                'i' => flags.ignore_case = Some(true),
+                'I' => flags.ignore_case = Some(false),
+                _ => return Err(ParseFlagsError { flag: flag }),
+            }
+        }
+        Ok(flags)
+    }
+}
+
+/// Answer to a confirmation prompt, as with the `c` flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Confirm {
+    /// Replace this match.
+    Yes,
+
+    /// Skip this match.
+    No,
+
+    /// Replace this and all remaining matches.
+    All,
+
+    /// Replace this match and stop.
+    Last,
+
+    /// Stop without replacing this match.
+    Quit,
+}
+
+/// Replacement string of the `:s` command.
+///
+/// As in vi, `&` and `\0` insert the whole match, `\1` to `\9` insert capture groups, `\r`
+/// inserts a line break, `\n` a NUL char and `\t` a tab. `\u` and `\l` change the case of the
+/// next char, and `\U` and `\L` change the case of the following chars until `\e` or `\E`. Any
+/// other char after a backslash is inserted as is. Without magic, `\&` inserts the match and `&`
+/// is literal. `~` is always literal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(StdString),
+    Group(usize),
+    Case(Case),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Case {
+    UpperNext,
+    LowerNext,
+    Upper,
+    Lower,
+    End,
+}
+
+impl Template {
+    /// Parses a replacement string, with `magic` as in the `magic` option.
+    pub fn new(replacement: &str, magic: bool) -> Self {
+        let mut parts = Vec::new();
+        let mut literal = StdString::new();
+        let mut chars = replacement.chars();
+        while let Some(ch) = chars.next() {
+            let part = match (ch, chars.clone().next()) {
+                ('&', _) if magic => Part::Group(0),
+                ('\\', Some(escaped)) => {
+                    chars.next();
+                    match escaped {
+                        '0'..='9' => Part::Group(escaped as usize - '0' as usize),
+                        '&' if !magic => Part::Group(0),
+                        'u' => Part::Case(Case::UpperNext),
+                        'l' => Part::Case(Case::LowerNext),
+                        'U' => Part::Case(Case::Upper),
+                        'L' => Part::Case(Case::Lower),
+                        'e' | 'E' => Part::Case(Case::End),
+                        'r' | '\r' | '\n' => {
+                            literal.push('\n');
+                            continue;
+                        },
+                        'n' => {
+                            literal.push('\0');
+                            continue;
+                        },
+                        't' => {
+                            literal.push('\t');
+                            continue;
+                        },
+                        _ => {
+                            literal.push(escaped);
+                            continue;
+                        },
+                    }
+                },
+                _ => {
+                    literal.push(ch);
+                    continue;
+                },
+            };
+            if !literal.is_empty() {
+                parts.push(Part::Literal(literal.split_off(0)));
+            }
+            parts.push(part);
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Template { parts: parts }
+    }
+
+    /// Returns the replacement for a match in `haystack`.
+    ///
+    /// Groups which did not participate in the match insert nothing.
+    pub fn expand(&self, haystack: Str, captures: &Captures) -> StdString {
+        let mut expansion = Expansion {
+            string: StdString::new(),
+            next: None,
+            rest: None,
+        };
+        for part in &self.parts {
+            match *part {
+                Part::Literal(ref literal) => literal.chars().for_each(|ch| expansion.push(ch)),
+                Part::Group(index) => if let Some(range) = captures.get(index) {
+                    haystack.slice(range).chars().for_each(|ch| expansion.push(ch));
+                },
+                Part::Case(case) if case == Case::UpperNext || case == Case::LowerNext => {
+                    expansion.next = Some(case);
+                },
+                Part::Case(Case::End) => expansion.rest = None,
+                Part::Case(case) => expansion.rest = Some(case),
+            }
+        }
+        expansion.string
+    }
+}
+
+// Expanded replacement, with the pending case changes.
+struct Expansion {
+    string: StdString,
+    next: Option<Case>,
+    rest: Option<Case>,
+}
+
+impl Expansion {
+    fn push(&mut self, ch: char) {
+        match self.next.take().or(self.rest) {
+            Some(Case::UpperNext) | Some(Case::Upper) => self.string.extend(ch.to_uppercase()),
+            Some(Case::LowerNext) | Some(Case::Lower) => self.string.extend(ch.to_lowercase()),
+            _ => self.string.push(ch),
+        }
+    }
+}
+
+/// Result of the `:s` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Substitution {
+    /// Replacements, as a batch of operations relative to the text before any is applied.
+    ///
+    /// Applying the batch with `Operate::apply_many` through an undo history records a single
+    /// transaction. Empty if only counting.
+    pub operations: Vec<Operation<StdString>>,
+
+    /// Number of matches replaced, or counted if only counting.
+    pub count: usize,
+
+    /// Number of lines with replaced or counted matches.
+    pub lines: usize,
+
+    /// Last line with a replaced or counted match, where vi leaves the cursor.
+    pub last_line: Option<usize>,
+}
+
+/// Finds the replacements of vi's `:s` command on `lines` of `text`.
+///
+/// Matches start in one of the lines, but may continue past its end. Only the first match in
+/// each line is replaced unless the `g` flag is set. An empty match directly after another match
+/// is skipped, so `:s/x*/-/g` turns `abxc` into `-a-b-c-`.
+///
+/// If the `c` flag is set, `confirm` is called with the range of each match in the original text,
+/// and the text is not changed while confirming. The `i` and `I` flags only have an effect
+/// through `Flags::regex_options`.
+///
+/// # Panics
+///
+/// Panics if `lines` is out of bounds of `text`.
+pub fn substitute<F>(
+    text: &String,
+    lines: Range<usize>,
+    regex: &Regex,
+    template: &Template,
+    flags: &Flags,
+    mut confirm: F,
+) -> Substitution
+where F: FnMut(Range<usize>) -> Confirm {
+    assert!(lines.start <= lines.end && lines.end <= text.line_count(), "lines out of bounds");
+    let haystack = text.as_str();
+    let mut substitution = Substitution {
+        operations: Vec::new(),
+        count: 0,
+        lines: 0,
+        last_line: None,
+    };
+    let mut confirming = flags.confirm;
+
+    // End of the last match, which later matches must not overlap.
+    let mut last_end = None;
+    let mut done = false;
+    // Match found past the end of a line, which is the next match in the text.
+    let mut ahead = None;
+    let mut line = lines.start;
+    while line < lines.end {
+        let range = text.line_range(line);
+        let mut position = match last_end {
+            Some(end) if end > range.end => {
+                line += 1;
+                continue;
+            },
+            Some(end) if end > range.start => end,
+            _ => range.start,
+        };
+        let mut matched = false;
+
+        while position <= range.end {
+            let captures = match ahead.take().or_else(|| regex.captures_at(haystack, position)) {
+                Some(captures) => captures,
+                None => {
+                    done = true;
+                    break;
+                },
+            };
+            let found = captures.get(0).unwrap();
+            if found.start > range.end {
+                ahead = Some(captures);
+                break;
+            }
+            position = if found.is_empty() { next_char(haystack, found.end) } else { found.end };
+            if found.is_empty() && last_end == Some(found.start) {
+                continue;
+            }
+
+            let answer = if confirming { confirm(found.clone()) } else { Confirm::Yes };
+            if answer == Confirm::All {
+                confirming = false;
+            }
+            if answer != Confirm::No && answer != Confirm::Quit {
+                last_end = Some(found.end);
+                matched = true;
+                substitution.count += 1;
+                if !flags.count_only {
+                    substitution.operations.push(Operation {
+                        src: template.expand(haystack, &captures),
+                        dest: found,
+                    });
+                }
+            }
+            if answer == Confirm::Last || answer == Confirm::Quit {
+                done = true;
+                break;
+            }
+            if !flags.global {
+                break;
+            }
+        }
+
+        if matched {
+            substitution.lines += 1;
+            substitution.last_line = Some(line);
+        }
+        if done {
+            break;
+        }
+        // Skips the lines without a match.
+        line = match ahead {
+            Some(ref captures) => text.line_of(captures.get(0).unwrap().start),
+            None => line + 1,
+        };
+    }
+    substitution
+}
+
+// Returns the offset after the char at `offset`, or after `offset` at the end of the haystack.
+fn next_char(haystack: Str, offset: usize) -> usize {
+    offset + haystack.slice(offset..).chars().next().map_or(1, char::len_utf8)
+}
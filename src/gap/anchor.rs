@@ -0,0 +1,170 @@
+use std::ops::Range;
+
+/// Handle to a position which tracks splices.
+///
+/// Handles are never reused, so a handle to a removed anchor remains invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Anchor(usize);
+
+/// Direction an anchor moves when text is inserted at its position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Gravity {
+    /// Stays before inserted text, and moves to the start of a splice which deletes its position.
+    Left,
+
+    /// Moves after inserted text, and to the end of a splice which deletes its position.
+    Right,
+}
+
+/// Set of anchors in a gap buffer.
+///
+/// Stored split in two like the gap buffer itself. Anchors before the split store their offset
+/// from the start of the buffer, while anchors after the split store their offset from the end,
+/// so a splice only touches anchors between the old and new split points, or within its
+/// destination.
+///
+/// Removed anchors are only marked as such, and stay in place until they make up half of the
+/// anchors, so that removal does not search for the anchor.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Anchors {
+    slots: Vec<Slot>,
+    front: Vec<usize>,
+    back: Vec<usize>,
+    len: usize,
+    removed: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    gravity: Gravity,
+    side: Side,
+    offset: usize,
+    removed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Front,
+    Back,
+}
+
+impl Anchors {
+    /// Creates an anchor set for a buffer of length `len`.
+    pub fn new(len: usize) -> Self {
+        Anchors {
+            len: len,
+            ..Anchors::default()
+        }
+    }
+
+    /// Adds an anchor at `offset`.
+    pub fn insert(&mut self, offset: usize, gravity: Gravity) -> Anchor {
+        let id = self.slots.len();
+        let split = self.front.last().map_or(0, |&id| self.offset(id));
+        let slot = if offset >= split {
+            let index = self.back.partition_point(|&id| self.offset(id) >= offset);
+            self.back.insert(index, id);
+            Slot {
+                gravity: gravity,
+                side: Side::Back,
+                offset: self.len - offset,
+                removed: false,
+            }
+        } else {
+            let index = self.front.partition_point(|&id| self.offset(id) <= offset);
+            self.front.insert(index, id);
+            Slot {
+                gravity: gravity,
+                side: Side::Front,
+                offset: offset,
+                removed: false,
+            }
+        };
+        self.slots.push(slot);
+        Anchor(id)
+    }
+
+    /// Removes an anchor, returning its offset.
+    pub fn remove(&mut self, anchor: Anchor) -> Option<usize> {
+        let offset = self.get(anchor)?;
+        self.slots[anchor.0].removed = true;
+        self.removed += 1;
+        if self.removed * 2 > self.front.len() + self.back.len() {
+            let slots = &self.slots;
+            self.front.retain(|&id| !slots[id].removed);
+            self.back.retain(|&id| !slots[id].removed);
+            self.removed = 0;
+        }
+        Some(offset)
+    }
+
+    /// Returns the offset of an anchor.
+    pub fn get(&self, anchor: Anchor) -> Option<usize> {
+        match self.slots.get(anchor.0) {
+            Some(slot) if !slot.removed => Some(self.offset(anchor.0)),
+            _ => None,
+        }
+    }
+
+    /// Updates anchors for a splice of `src_len` bytes into `dest`.
+    pub fn splice(&mut self, dest: Range<usize>, src_len: usize) {
+        self.move_split(dest.start);
+
+        let mut right = Vec::new();
+        while let Some(&id) = self.back.last() {
+            let offset = self.offset(id);
+            let slot = &mut self.slots[id];
+            if offset > dest.end || (offset == dest.end && dest.start < dest.end) {
+                break;
+            }
+            self.back.pop();
+            match slot.gravity {
+                Gravity::Left => {
+                    slot.side = Side::Front;
+                    slot.offset = dest.start;
+                    self.front.push(id);
+                },
+                Gravity::Right => {
+                    slot.offset = self.len - dest.end;
+                    right.push(id);
+                },
+            }
+        }
+        self.back.extend(right.into_iter().rev());
+
+        self.len = self.len - dest.len() + src_len;
+    }
+
+    // Returns the offset of an anchor by ID.
+    fn offset(&self, id: usize) -> usize {
+        let slot = &self.slots[id];
+        match slot.side {
+            Side::Front => slot.offset,
+            Side::Back => self.len - slot.offset,
+        }
+    }
+
+    // Moves the split so that all anchors before `offset` are in front.
+    fn move_split(&mut self, offset: usize) {
+        while let Some(&id) = self.front.last() {
+            if self.offset(id) < offset {
+                break;
+            }
+            self.front.pop();
+            let slot = &mut self.slots[id];
+            slot.side = Side::Back;
+            slot.offset = self.len - slot.offset;
+            self.back.push(id);
+        }
+        while let Some(&id) = self.back.last() {
+            if self.offset(id) >= offset {
+                break;
+            }
+            self.back.pop();
+            let slot = &mut self.slots[id];
+            slot.side = Side::Front;
+            slot.offset = self.len - slot.offset;
+            self.front.push(id);
+        }
+    }
+}
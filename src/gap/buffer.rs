@@ -1,13 +1,17 @@
 use std::ops::Range;
 use std::ptr;
 
-use range::{IntoRange, RangeExt};
-use super::Slice;
+use range::{self, IntoRange, RangeExt};
+use super::{Anchor, Gravity, Slice};
+use super::anchor::Anchors;
 
 /// Gap buffer.
+///
+/// Maintains a set of anchors, updated by each splice.
 pub struct Buffer {
     pub(super) buf: Vec<u8>,
     pub(super) gap: Range<usize>,
+    pub(super) anchors: Anchors,
 }
 
 impl Buffer {
@@ -18,6 +22,7 @@ impl Buffer {
         Buffer {
             buf: Vec::new(),
             gap: 0..0,
+            anchors: Anchors::default(),
         }
     }
 
@@ -82,9 +87,50 @@ impl Buffer {
         }
         self.copy_into_gap(src);
 
+        self.anchors.splice(dest.clone(), src.len());
         dest.with_len(src.len())
     }
 
+    /// Replaces several non-overlapping slices of bytes in one pass.
+    ///
+    /// Destinations are relative to the buffer before any replacement, and may be given in any
+    /// order. Replacements are performed in order of position, so the gap only moves forward.
+    ///
+    /// Returns the ranges of written bytes in the resulting buffer, in the order given.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any destinations overlap, or if any point is out of bounds.
+    pub fn splice_many(&mut self, splices: &[(Range<usize>, &[u8])]) -> Vec<Range<usize>> {
+        let len = self.len();
+        assert!(splices.iter().all(|&(ref dest, _)| dest.end <= len), "dest end out of bounds");
+        range::splice_disjoint(splices, |dest, src| self.splice(dest, src))
+    }
+
+    /// Adds an anchor at `offset`, which tracks the position through splices.
+    ///
+    /// Text inserted at the anchor's position is placed before or after it according to its
+    /// gravity. If a splice deletes the anchor's position, it moves to the start or end of the
+    /// written bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is out of bounds.
+    pub fn anchor(&mut self, offset: usize, gravity: Gravity) -> Anchor {
+        assert!(offset <= self.len(), "offset out of bounds");
+        self.anchors.insert(offset, gravity)
+    }
+
+    /// Returns the offset of an anchor, or `None` if it has been removed.
+    pub fn anchor_offset(&self, anchor: Anchor) -> Option<usize> {
+        self.anchors.get(anchor)
+    }
+
+    /// Removes an anchor, returning its offset, or `None` if it has already been removed.
+    pub fn remove_anchor(&mut self, anchor: Anchor) -> Option<usize> {
+        self.anchors.remove(anchor)
+    }
+
     // Reallocates the buffer and makes all bytes available.
     fn resize_buf(&mut self, additional: usize) {
         let new_len = self.buf.len() + additional;
@@ -1,6 +1,8 @@
 use std::string::String as StdString;
 
 use super::{Buffer, Slice, Str, String};
+use super::anchor::Anchors;
+use super::lines::Lines;
 
 /// Uses the extra capacity as the gap.
 impl From<Vec<u8>> for Buffer {
@@ -11,6 +13,7 @@ impl From<Vec<u8>> for Buffer {
         Buffer {
             buf: buf,
             gap: len..cap,
+            anchors: Anchors::new(len),
         }
     }
 }
@@ -49,7 +52,10 @@ impl<'a> Into<Vec<u8>> for Slice<'a> {
 /// Uses the extra capacity as the gap.
 impl From<StdString> for String {
     fn from(string: StdString) -> Self {
-        String { buf: Buffer::from(string.into_bytes()) }
+        String {
+            lines: Lines::new(&string),
+            buf: Buffer::from(string.into_bytes()),
+        }
     }
 }
 
@@ -0,0 +1,125 @@
+use unicode::is_grapheme_break;
+use super::{Str, String};
+
+impl<'a> Str<'a> {
+    /// Returns `true` if `offset` is a grapheme cluster boundary.
+    ///
+    /// The start and end of the slice are always boundaries. Offsets beyond the end are not.
+    pub fn is_grapheme_boundary(&self, offset: usize) -> bool {
+        if offset == 0 || offset == self.len() {
+            return true;
+        }
+        if offset > self.len() || !self.is_char_boundary(offset) {
+            return false;
+        }
+        let after = self.slice(offset..).chars().next().unwrap();
+        is_grapheme_break(self.slice(..offset).chars().rev(), after)
+    }
+
+    /// Returns the last grapheme cluster boundary before `offset`, or zero.
+    pub fn prev_grapheme_boundary(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.len());
+        while offset > 0 {
+            offset -= 1;
+            if self.is_grapheme_boundary(offset) {
+                break;
+            }
+        }
+        offset
+    }
+
+    /// Returns the first grapheme cluster boundary after `offset`, or the length of the slice.
+    pub fn next_grapheme_boundary(&self, offset: usize) -> usize {
+        let mut offset = offset;
+        while offset < self.len() {
+            offset += 1;
+            if self.is_grapheme_boundary(offset) {
+                break;
+            }
+        }
+        offset.min(self.len())
+    }
+
+    /// Returns an iterator over the grapheme clusters of the string slice.
+    pub fn graphemes(&self) -> Graphemes<'a> {
+        Graphemes(self.grapheme_indices())
+    }
+
+    /// Returns an iterator over the grapheme clusters of the string slice, and their positions.
+    pub fn grapheme_indices(&self) -> GraphemeIndices<'a> {
+        GraphemeIndices {
+            slice: *self,
+            front: 0,
+            back: self.len(),
+        }
+    }
+}
+
+impl String {
+    /// Returns `true` if `offset` is a grapheme cluster boundary.
+    pub fn is_grapheme_boundary(&self, offset: usize) -> bool {
+        self.as_str().is_grapheme_boundary(offset)
+    }
+
+    /// Returns the last grapheme cluster boundary before `offset`, or zero.
+    pub fn prev_grapheme_boundary(&self, offset: usize) -> usize {
+        self.as_str().prev_grapheme_boundary(offset)
+    }
+
+    /// Returns the first grapheme cluster boundary after `offset`, or the length of the string.
+    pub fn next_grapheme_boundary(&self, offset: usize) -> usize {
+        self.as_str().next_grapheme_boundary(offset)
+    }
+}
+
+/// Iterator over the grapheme clusters of a gap buffer string.
+///
+/// Clusters which straddle the gap are yielded as fragmented slices.
+#[derive(Debug, Clone)]
+pub struct Graphemes<'a>(GraphemeIndices<'a>);
+
+impl<'a> Iterator for Graphemes<'a> {
+    type Item = Str<'a>;
+
+    fn next(&mut self) -> Option<Str<'a>> {
+        self.0.next().map(|(_, cluster)| cluster)
+    }
+}
+
+impl<'a> DoubleEndedIterator for Graphemes<'a> {
+    fn next_back(&mut self) -> Option<Str<'a>> {
+        self.0.next_back().map(|(_, cluster)| cluster)
+    }
+}
+
+/// Iterator over the grapheme clusters of a gap buffer string and their byte offsets.
+#[derive(Debug, Clone)]
+pub struct GraphemeIndices<'a> {
+    slice: Str<'a>,
+    front: usize,
+    back: usize,
+}
+
+impl<'a> Iterator for GraphemeIndices<'a> {
+    type Item = (usize, Str<'a>);
+
+    fn next(&mut self) -> Option<(usize, Str<'a>)> {
+        if self.front == self.back {
+            return None;
+        }
+        let start = self.front;
+        self.front = self.slice.next_grapheme_boundary(start);
+        Some((start, self.slice.slice(start..self.front)))
+    }
+}
+
+impl<'a> DoubleEndedIterator for GraphemeIndices<'a> {
+    fn next_back(&mut self) -> Option<(usize, Str<'a>)> {
+        if self.front == self.back {
+            return None;
+        }
+        let end = self.back;
+        self.back = self.slice.prev_grapheme_boundary(end);
+        Some((self.back, self.slice.slice(self.back..end)))
+    }
+}
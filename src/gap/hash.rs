@@ -0,0 +1,45 @@
+use std::hash::{Hash, Hasher};
+
+use super::{Buffer, Slice, Str, String};
+
+/// Hashes the same as the equivalent `[u8]`.
+impl<'a> Hash for Slice<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        match *self {
+            Slice::Contiguous(back) => state.write(back),
+            Slice::Fragmented(front, back) => {
+                state.write(front);
+                state.write(back);
+            },
+        }
+    }
+}
+
+/// Hashes the same as the equivalent `[u8]`.
+impl Hash for Buffer {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state);
+    }
+}
+
+/// Hashes the same as the equivalent `str`.
+impl<'a> Hash for Str<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match *self {
+            Str::Contiguous(back) => state.write(back.as_bytes()),
+            Str::Fragmented(front, back) => {
+                state.write(front.as_bytes());
+                state.write(back.as_bytes());
+            },
+        }
+        state.write_u8(0xff);
+    }
+}
+
+/// Hashes the same as the equivalent `str`.
+impl Hash for String {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
@@ -0,0 +1,79 @@
+use std::ops::Range;
+
+/// Line index of a gap buffer string.
+///
+/// Stores the offsets of newlines split in two like the gap buffer itself. Newlines before the
+/// split are stored as offsets from the start of the string, while newlines after the split are
+/// stored as offsets from the end of the string, nearest the split last. Splicing moves the split
+/// to the destination, so only newlines between the old and new split points are touched, and
+/// offsets after the splice remain valid without being shifted.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Lines {
+    front: Vec<usize>,
+    back: Vec<usize>,
+    len: usize,
+}
+
+impl Lines {
+    /// Creates a line index for a string.
+    pub fn new(s: &str) -> Self {
+        Lines {
+            front: newlines(s, 0).collect(),
+            back: Vec::new(),
+            len: s.len(),
+        }
+    }
+
+    /// Returns the number of newlines.
+    pub fn newlines(&self) -> usize {
+        self.front.len() + self.back.len()
+    }
+
+    /// Returns the offset of the `n`-th newline.
+    pub fn newline(&self, n: usize) -> usize {
+        if n < self.front.len() {
+            self.front[n]
+        } else {
+            self.len - self.back[self.back.len() - 1 - (n - self.front.len())]
+        }
+    }
+
+    /// Returns the number of newlines before `offset`.
+    pub fn newlines_before(&self, offset: usize) -> usize {
+        let front = self.front.partition_point(|&nl| nl < offset);
+        if front < self.front.len() {
+            return front;
+        }
+        let back = self.back.partition_point(|&nl| nl <= self.len - offset);
+        front + self.back.len() - back
+    }
+
+    /// Updates the index for a splice of `src` into `dest`.
+    pub fn splice(&mut self, dest: Range<usize>, src: &str) {
+        self.move_split(dest.start);
+
+        while self.back.last().map_or(false, |&nl| self.len - nl < dest.end) {
+            self.back.pop();
+        }
+
+        self.front.extend(newlines(src, dest.start));
+        self.len = self.len - dest.len() + src.len();
+    }
+
+    // Moves the split so that all newlines before `offset` are in front.
+    fn move_split(&mut self, offset: usize) {
+        while self.front.last().map_or(false, |&nl| nl >= offset) {
+            let nl = self.front.pop().unwrap();
+            self.back.push(self.len - nl);
+        }
+        while self.back.last().map_or(false, |&nl| self.len - nl < offset) {
+            let nl = self.back.pop().unwrap();
+            self.front.push(self.len - nl);
+        }
+    }
+}
+
+// Returns an iterator over the offsets of newlines in `s`, added to `base`.
+fn newlines<'a>(s: &'a str, base: usize) -> impl Iterator<Item = usize> + 'a {
+    s.bytes().enumerate().filter(|&(_, b)| b == b'\n').map(move |(i, _)| base + i)
+}
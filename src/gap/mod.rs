@@ -10,21 +10,36 @@
 //! The gap buffer offers a single operation, splice, which both deletes and inserts data. These
 //! operations are performed by moving, expanding, and shrinking the gap.
 //!
+//! Anchors are positions in a gap buffer which track splices, such as marks and cursors.
+//!
+//! String slices can be iterated by grapheme cluster, vi word and sentence, including spans which
+//! straddle the gap.
+//!
 //! # Examples
 //!
 //! TODO
 
+mod anchor;
 mod buffer;
 mod cmp;
 mod convert;
 mod fmt;
+mod grapheme;
+mod hash;
 mod iter;
+mod lines;
+mod search;
 mod slice;
 mod str;
 mod string;
+mod word;
 
+pub use self::anchor::{Anchor, Gravity};
 pub use self::buffer::Buffer;
+pub use self::grapheme::{GraphemeIndices, Graphemes};
 pub use self::iter::{CharIndices, Iter};
+pub use self::search::{MatchIndices, RMatchIndices};
 pub use self::slice::Slice;
 pub use self::str::Str;
 pub use self::string::String;
+pub use self::word::{Sentences, Words};
@@ -0,0 +1,401 @@
+use std::cmp;
+use std::ops::Range;
+
+use range::IntoRange;
+use super::{Buffer, Slice, Str, String};
+
+impl Buffer {
+    /// Returns the offset in the buffer of the first match of `needle` within a range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point, or if either point is out of
+    /// bounds.
+    pub fn find<R: IntoRange>(&self, range: R, needle: &[u8]) -> Option<usize> {
+        self.match_indices(range, needle).next().map(|range| range.start)
+    }
+
+    /// Returns the offset in the buffer of the last match of `needle` within a range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point, or if either point is out of
+    /// bounds.
+    pub fn rfind<R: IntoRange>(&self, range: R, needle: &[u8]) -> Option<usize> {
+        self.rmatch_indices(range, needle).next().map(|range| range.start)
+    }
+
+    /// Returns an iterator over the non-overlapping matches of `needle` within a range, from the
+    /// start.
+    ///
+    /// Unlike `Slice::match_indices`, matches are byte ranges in the buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point, or if either point is out of
+    /// bounds.
+    pub fn match_indices<R: IntoRange>(&self, range: R, needle: &[u8]) -> MatchIndices {
+        let range = range.into_range(self.len());
+        MatchIndices {
+            offset: range.start,
+            ..self.slice(range).match_indices(needle)
+        }
+    }
+
+    /// Returns an iterator over the non-overlapping matches of `needle` within a range, from the
+    /// end.
+    ///
+    /// Unlike `Slice::rmatch_indices`, matches are byte ranges in the buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point, or if either point is out of
+    /// bounds.
+    pub fn rmatch_indices<R: IntoRange>(&self, range: R, needle: &[u8]) -> RMatchIndices {
+        let range = range.into_range(self.len());
+        RMatchIndices {
+            offset: range.start,
+            ..self.slice(range).rmatch_indices(needle)
+        }
+    }
+}
+
+impl String {
+    /// Returns the offset in the string of the first match of `needle` within a range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point, or if either point is not a
+    /// char boundary.
+    pub fn find<R: IntoRange>(&self, range: R, needle: &str) -> Option<usize> {
+        self.match_indices(range, needle).next().map(|range| range.start)
+    }
+
+    /// Returns the offset in the string of the last match of `needle` within a range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point, or if either point is not a
+    /// char boundary.
+    pub fn rfind<R: IntoRange>(&self, range: R, needle: &str) -> Option<usize> {
+        self.rmatch_indices(range, needle).next().map(|range| range.start)
+    }
+
+    /// Returns an iterator over the non-overlapping matches of `needle` within a range, from the
+    /// start.
+    ///
+    /// Unlike `Str::match_indices`, matches are byte ranges in the string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point, or if either point is not a
+    /// char boundary.
+    pub fn match_indices<R: IntoRange>(&self, range: R, needle: &str) -> MatchIndices {
+        let range = range.into_range(self.len());
+        MatchIndices {
+            offset: range.start,
+            ..self.slice(range).match_indices(needle)
+        }
+    }
+
+    /// Returns an iterator over the non-overlapping matches of `needle` within a range, from the
+    /// end.
+    ///
+    /// Unlike `Str::rmatch_indices`, matches are byte ranges in the string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point, or if either point is not a
+    /// char boundary.
+    pub fn rmatch_indices<R: IntoRange>(&self, range: R, needle: &str) -> RMatchIndices {
+        let range = range.into_range(self.len());
+        RMatchIndices {
+            offset: range.start,
+            ..self.slice(range).rmatch_indices(needle)
+        }
+    }
+}
+
+impl<'a> Slice<'a> {
+    /// Returns the offset of the first match of `needle`, relative to the start of the slice.
+    pub fn find(&self, needle: &[u8]) -> Option<usize> {
+        self.match_indices(needle).next().map(|range| range.start)
+    }
+
+    /// Returns the offset of the last match of `needle`, relative to the start of the slice.
+    pub fn rfind(&self, needle: &[u8]) -> Option<usize> {
+        self.rmatch_indices(needle).next().map(|range| range.start)
+    }
+
+    /// Returns an iterator over the non-overlapping matches of `needle`, from the start.
+    ///
+    /// Matches are byte ranges relative to the start of the slice, including matches which span
+    /// the gap. `Buffer::match_indices` gives offsets in the buffer instead.
+    pub fn match_indices(&self, needle: &[u8]) -> MatchIndices<'a> {
+        MatchIndices {
+            haystack: *self,
+            searcher: TwoWay::new(needle.to_vec()),
+            position: 0,
+            offset: 0,
+            utf8: false,
+        }
+    }
+
+    /// Returns an iterator over the non-overlapping matches of `needle`, from the end.
+    ///
+    /// Matches are relative to the start of the slice, as with `match_indices`.
+    pub fn rmatch_indices(&self, needle: &[u8]) -> RMatchIndices<'a> {
+        RMatchIndices {
+            haystack: *self,
+            searcher: TwoWay::new(needle.iter().rev().cloned().collect()),
+            position: 0,
+            offset: 0,
+            utf8: false,
+        }
+    }
+}
+
+impl<'a> Str<'a> {
+    /// Returns the offset of the first match of `needle`, relative to the start of the slice.
+    pub fn find(&self, needle: &str) -> Option<usize> {
+        self.match_indices(needle).next().map(|range| range.start)
+    }
+
+    /// Returns the offset of the last match of `needle`, relative to the start of the slice.
+    pub fn rfind(&self, needle: &str) -> Option<usize> {
+        self.rmatch_indices(needle).next().map(|range| range.start)
+    }
+
+    /// Returns an iterator over the non-overlapping matches of `needle`, from the start.
+    ///
+    /// Matches are byte ranges relative to the start of the slice, including matches which span
+    /// the gap. `String::match_indices` gives offsets in the string instead.
+    pub fn match_indices(&self, needle: &str) -> MatchIndices<'a> {
+        MatchIndices {
+            utf8: true,
+            ..self.as_bytes().match_indices(needle.as_bytes())
+        }
+    }
+
+    /// Returns an iterator over the non-overlapping matches of `needle`, from the end.
+    ///
+    /// Matches are relative to the start of the slice, as with `match_indices`.
+    pub fn rmatch_indices(&self, needle: &str) -> RMatchIndices<'a> {
+        RMatchIndices {
+            utf8: true,
+            ..self.as_bytes().rmatch_indices(needle.as_bytes())
+        }
+    }
+}
+
+/// Iterator over the matches of a needle in a gap buffer slice.
+#[derive(Debug, Clone)]
+pub struct MatchIndices<'a> {
+    haystack: Slice<'a>,
+    searcher: TwoWay,
+    position: usize,
+
+    // Start of the haystack in the buffer, added to each match.
+    offset: usize,
+
+    // Only yield empty matches on char boundaries.
+    utf8: bool,
+}
+
+impl<'a> Iterator for MatchIndices<'a> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        let len = self.searcher.needle.len();
+        loop {
+            let start = self.searcher.find(&self.haystack, self.position)?;
+            self.position = start + cmp::max(len, 1);
+            if len > 0 || !self.utf8 || is_char_boundary(&self.haystack, start) {
+                return Some((self.offset + start)..(self.offset + start + len));
+            }
+        }
+    }
+}
+
+/// Iterator over the matches of a needle in a gap buffer slice, from the end.
+#[derive(Debug, Clone)]
+pub struct RMatchIndices<'a> {
+    haystack: Slice<'a>,
+    searcher: TwoWay,
+    position: usize,
+    offset: usize,
+    utf8: bool,
+}
+
+impl<'a> Iterator for RMatchIndices<'a> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        let len = self.searcher.needle.len();
+        let haystack = Rev(self.haystack);
+        loop {
+            let start = self.searcher.find(&haystack, self.position)?;
+            self.position = start + cmp::max(len, 1);
+            let end = self.haystack.len() - start;
+            if len > 0 || !self.utf8 || is_char_boundary(&self.haystack, end) {
+                return Some((self.offset + end - len)..(self.offset + end));
+            }
+        }
+    }
+}
+
+// Returns `true` if `offset` is not within a UTF-8 sequence.
+fn is_char_boundary<H: Haystack>(haystack: &H, offset: usize) -> bool {
+    offset == haystack.len() || haystack.at(offset) & 0xc0 != 0x80
+}
+
+// Random access to bytes of a possibly fragmented haystack.
+trait Haystack {
+    fn len(&self) -> usize;
+    fn at(&self, index: usize) -> u8;
+}
+
+impl<'a> Haystack for Slice<'a> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn at(&self, index: usize) -> u8 {
+        match *self {
+            Slice::Contiguous(back) => back[index],
+            Slice::Fragmented(front, _) if index < front.len() => front[index],
+            Slice::Fragmented(front, back) => back[index - front.len()],
+        }
+    }
+}
+
+// Haystack indexed from the end.
+struct Rev<H>(H);
+
+impl<H: Haystack> Haystack for Rev<H> {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    fn at(&self, index: usize) -> u8 {
+        self.0.at(self.0.len() - 1 - index)
+    }
+}
+
+// Two-Way string matching, as described by Crochemore and Perrin. Runs in linear time and
+// constant space, and only requires random access to the haystack.
+#[derive(Debug, Clone)]
+struct TwoWay {
+    needle: Vec<u8>,
+
+    // Critical factorization of the needle into left and right parts.
+    crit_pos: usize,
+
+    // Period of the needle, or a lower bound if `long_period`.
+    period: usize,
+
+    // Whether the period is too long to remember how much of the left part matched after a shift.
+    long_period: bool,
+}
+
+impl TwoWay {
+    fn new(needle: Vec<u8>) -> Self {
+        if needle.is_empty() {
+            return TwoWay {
+                needle: needle,
+                crit_pos: 0,
+                period: 1,
+                long_period: true,
+            };
+        }
+
+        let (crit_pos_less, period_less) = maximal_suffix(&needle, false);
+        let (crit_pos_greater, period_greater) = maximal_suffix(&needle, true);
+        let (crit_pos, period) = if crit_pos_less > crit_pos_greater {
+            (crit_pos_less, period_less)
+        } else {
+            (crit_pos_greater, period_greater)
+        };
+
+        if needle[..crit_pos] == needle[period..(period + crit_pos)] {
+            TwoWay {
+                needle: needle,
+                crit_pos: crit_pos,
+                period: period,
+                long_period: false,
+            }
+        } else {
+            let period = cmp::max(crit_pos, needle.len() - crit_pos) + 1;
+            TwoWay {
+                needle: needle,
+                crit_pos: crit_pos,
+                period: period,
+                long_period: true,
+            }
+        }
+    }
+
+    // Returns the position of the first match at or after `position`.
+    fn find<H: Haystack>(&self, haystack: &H, mut position: usize) -> Option<usize> {
+        let needle = &self.needle;
+        let mut memory = 0;
+        'search: while position + needle.len() <= haystack.len() {
+            // Match the right part, then the left part.
+            let start = cmp::max(self.crit_pos, memory);
+            for i in start..needle.len() {
+                if needle[i] != haystack.at(position + i) {
+                    position += i - self.crit_pos + 1;
+                    memory = 0;
+                    continue 'search;
+                }
+            }
+
+            let start = if self.long_period { 0 } else { memory };
+            for i in (start..self.crit_pos).rev() {
+                if needle[i] != haystack.at(position + i) {
+                    position += self.period;
+                    if !self.long_period {
+                        memory = needle.len() - self.period;
+                    }
+                    continue 'search;
+                }
+            }
+
+            return Some(position);
+        }
+        None
+    }
+}
+
+// Returns the start and period of the maximal suffix of `bytes` in lexical order, or in reverse
+// lexical order if `greater`.
+fn maximal_suffix(bytes: &[u8], greater: bool) -> (usize, usize) {
+    let mut left = 0;
+    let mut right = 1;
+    let mut offset = 0;
+    let mut period = 1;
+
+    while let Some(&a) = bytes.get(right + offset) {
+        let b = bytes[left + offset];
+        if (a < b && !greater) || (a > b && greater) {
+            right += offset + 1;
+            offset = 0;
+            period = right - left;
+        } else if a == b {
+            if offset + 1 == period {
+                right += offset + 1;
+                offset = 0;
+            } else {
+                offset += 1;
+            }
+        } else {
+            left = right;
+            right += 1;
+            offset = 0;
+            period = 1;
+        }
+    }
+    (left, period)
+}
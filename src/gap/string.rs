@@ -1,24 +1,35 @@
 use std::ops::Range;
 use std::str;
 
-use range::IntoRange;
-use super::{Buffer, Slice, Str};
+use range::{self, IntoRange};
+use width;
+use super::{Anchor, Buffer, Gravity, Slice, Str};
+use super::lines::Lines;
 
 /// Gap buffer UTF-8 string.
+///
+/// Maintains an index of line endings, updated by each splice.
 #[derive(Default)]
 pub struct String {
     pub(super) buf: Buffer,
+    pub(super) lines: Lines,
 }
 
 impl String {
     /// Creates an empty string without allocating.
     pub fn new() -> Self {
-        String { buf: Buffer::new() }
+        String {
+            buf: Buffer::new(),
+            lines: Lines::default(),
+        }
     }
 
     /// Creates a string with a pre-allocated gap.
     pub fn with_gap(gap: usize) -> Self {
-        String { buf: Buffer::with_gap(gap) }
+        String {
+            buf: Buffer::with_gap(gap),
+            lines: Lines::default(),
+        }
     }
 
     /// Returns the length of the string, excluding the gap.
@@ -63,6 +74,122 @@ impl String {
         let dest = dest.into_range(self.len());
         assert!(self.as_str().is_char_boundary(dest.start), "dest start not char boundary");
         assert!(self.as_str().is_char_boundary(dest.end), "dest end not char boundary");
+        self.lines.splice(dest.clone(), src);
         self.buf.splice(dest, src.as_bytes())
     }
+
+    /// Replaces several non-overlapping slices of the string in one pass.
+    ///
+    /// See `Buffer::splice_many`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any destinations overlap, or if any point is not a char boundary.
+    pub fn splice_many(&mut self, splices: &[(Range<usize>, &str)]) -> Vec<Range<usize>> {
+        for &(ref dest, _) in splices {
+            assert!(self.as_str().is_char_boundary(dest.start), "dest start not char boundary");
+            assert!(self.as_str().is_char_boundary(dest.end), "dest end not char boundary");
+        }
+        range::splice_disjoint(splices, |dest, src| self.splice(dest, src))
+    }
+
+    /// Adds an anchor at `offset`, which tracks the position through splices.
+    ///
+    /// See `Buffer::anchor`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is not a char boundary.
+    pub fn anchor(&mut self, offset: usize, gravity: Gravity) -> Anchor {
+        assert!(self.as_str().is_char_boundary(offset), "offset not char boundary");
+        self.buf.anchor(offset, gravity)
+    }
+
+    /// Returns the offset of an anchor, or `None` if it has been removed.
+    pub fn anchor_offset(&self, anchor: Anchor) -> Option<usize> {
+        self.buf.anchor_offset(anchor)
+    }
+
+    /// Removes an anchor, returning its offset, or `None` if it has already been removed.
+    pub fn remove_anchor(&mut self, anchor: Anchor) -> Option<usize> {
+        self.buf.remove_anchor(anchor)
+    }
+
+    /// Returns the number of lines.
+    ///
+    /// A trailing newline is followed by an empty line, so this is always one more than the
+    /// number of newlines.
+    pub fn line_count(&self) -> usize {
+        self.lines.newlines() + 1
+    }
+
+    /// Returns the line containing the byte at `offset`.
+    ///
+    /// A newline belongs to the line it ends.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is out of bounds.
+    pub fn line_of(&self, offset: usize) -> usize {
+        assert!(offset <= self.len(), "offset out of bounds");
+        self.lines.newlines_before(offset)
+    }
+
+    /// Returns the byte range of a line, excluding its newline.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line` is out of bounds.
+    pub fn line_range(&self, line: usize) -> Range<usize> {
+        assert!(line < self.line_count(), "line out of bounds");
+        let start = if line == 0 { 0 } else { self.lines.newline(line - 1) + 1 };
+        let end = if line < self.lines.newlines() { self.lines.newline(line) } else { self.len() };
+        start..end
+    }
+
+    /// Converts a byte offset to a line and a byte column within that line.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is out of bounds.
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_of(offset);
+        (line, offset - self.line_range(line).start)
+    }
+
+    /// Converts a line and a byte column within that line to a byte offset.
+    ///
+    /// The column may point at the end of the line, i.e. at its newline.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line` is out of bounds or `col` is past the end of the line.
+    pub fn offset(&self, line: usize, col: usize) -> usize {
+        let range = self.line_range(line);
+        assert!(col <= range.len(), "col out of bounds");
+        range.start + col
+    }
+
+    /// Returns the display column of `offset` within its line.
+    ///
+    /// See the `width` module.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is out of bounds or not a char boundary.
+    pub fn virtcol(&self, offset: usize, tabstop: usize) -> usize {
+        let start = self.line_range(self.line_of(offset)).start;
+        width::width(self.slice(start..offset), tabstop)
+    }
+
+    /// Returns the offset of the char displayed at column `col` of a line, or the end of the line
+    /// if it is shorter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line` is out of bounds.
+    pub fn virtcol_offset(&self, line: usize, col: usize, tabstop: usize) -> usize {
+        let range = self.line_range(line);
+        range.start + width::offset_at(self.slice(range), col, tabstop)
+    }
 }
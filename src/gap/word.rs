@@ -0,0 +1,224 @@
+use std::ops::Range;
+
+use word::{Class, Keyword};
+use super::Str;
+
+impl<'a> Str<'a> {
+    /// Returns an iterator over the spans of vi words, as separated by `keyword`.
+    ///
+    /// A word is a run of keyword chars or a run of other non-blank chars. Empty lines are also
+    /// words, and are yielded as empty spans at the start of the line.
+    pub fn words(&self, keyword: &Keyword) -> Words<'a> {
+        Words {
+            slice: *self,
+            front: 0,
+            back: self.len(),
+            keyword: Some(*keyword),
+        }
+    }
+
+    /// Returns an iterator over the spans of vi WORDs.
+    ///
+    /// A WORD is a run of non-blank chars. Empty lines are also WORDs, and are yielded as empty
+    /// spans at the start of the line.
+    pub fn big_words(&self) -> Words<'a> {
+        Words {
+            slice: *self,
+            front: 0,
+            back: self.len(),
+            keyword: None,
+        }
+    }
+
+    /// Returns an iterator over the spans of sentences.
+    ///
+    /// As in vi, a sentence ends at a `.`, `!` or `?` followed by the end of a line or by a blank,
+    /// with any number of closing `)`, `]`, `"` and `'` chars in between. Sentences also end at
+    /// empty lines, which are yielded as empty spans at the start of the line. Spans exclude
+    /// leading and trailing whitespace.
+    pub fn sentences(&self) -> Sentences<'a> {
+        Sentences {
+            slice: *self,
+            front: 0,
+            back: self.len(),
+        }
+    }
+
+    // Returns the char starting at `offset`.
+    fn char_at(&self, offset: usize) -> Option<char> {
+        self.slice(offset..).chars().next()
+    }
+
+    // Returns the char ending at `offset`.
+    fn char_before(&self, offset: usize) -> Option<char> {
+        self.slice(..offset).chars().next_back()
+    }
+
+    // Returns `true` if an empty line starts at `offset`.
+    fn is_empty_line(&self, offset: usize) -> bool {
+        self.char_at(offset) == Some('\n')
+            && (offset == 0 || self.char_before(offset) == Some('\n'))
+    }
+
+    // Returns `true` if a sentence ends at `offset`, i.e. it follows a terminator and closing
+    // chars, and precedes whitespace or the end of the slice.
+    fn is_sentence_end(&self, offset: usize) -> bool {
+        if !self.char_at(offset).map_or(true, char::is_whitespace) {
+            return false;
+        }
+        let mut before = self.slice(..offset).chars().rev();
+        let terminator = before.find(|&ch| !is_sentence_close(ch));
+        match terminator {
+            Some('.') | Some('!') | Some('?') => true,
+            _ => false,
+        }
+    }
+}
+
+fn is_sentence_close(ch: char) -> bool {
+    match ch {
+        ')' | ']' | '"' | '\'' => true,
+        _ => false,
+    }
+}
+
+/// Iterator over the spans of vi words or WORDs in a gap buffer string.
+#[derive(Debug, Clone)]
+pub struct Words<'a> {
+    slice: Str<'a>,
+    front: usize,
+    back: usize,
+    keyword: Option<Keyword>,
+}
+
+impl<'a> Words<'a> {
+    fn class(&self, ch: char) -> Class {
+        match self.keyword {
+            Some(ref keyword) => keyword.class(ch),
+            None if ch.is_whitespace() => Class::Blank,
+            None => Class::Punctuation,
+        }
+    }
+}
+
+impl<'a> Iterator for Words<'a> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        let slice = self.slice.slice(self.front..self.back);
+        let mut chars = slice.char_indices().map(|(index, ch)| (self.front + index, ch));
+
+        let (start, class) = loop {
+            let (offset, ch) = chars.next()?;
+            if self.slice.is_empty_line(offset) {
+                self.front = offset + 1;
+                return Some(offset..offset);
+            }
+            let class = self.class(ch);
+            if class != Class::Blank {
+                break (offset, class);
+            }
+        };
+        let end = chars.find(|&(_, ch)| self.class(ch) != class).map_or(self.back, |(i, _)| i);
+
+        self.front = end;
+        Some(start..end)
+    }
+}
+
+impl<'a> DoubleEndedIterator for Words<'a> {
+    fn next_back(&mut self) -> Option<Range<usize>> {
+        let slice = self.slice.slice(self.front..self.back);
+        let mut chars = slice.char_indices().rev().map(|(index, ch)| (self.front + index, ch));
+
+        let (end, class) = loop {
+            let (offset, ch) = chars.next()?;
+            if self.slice.is_empty_line(offset) {
+                self.back = offset;
+                return Some(offset..offset);
+            }
+            let class = self.class(ch);
+            if class != Class::Blank {
+                break (offset + ch.len_utf8(), class);
+            }
+        };
+        let start = chars.find(|&(_, ch)| self.class(ch) != class)
+            .map_or(self.front, |(i, ch)| i + ch.len_utf8());
+
+        self.back = start;
+        Some(start..end)
+    }
+}
+
+/// Iterator over the spans of sentences in a gap buffer string.
+#[derive(Debug, Clone)]
+pub struct Sentences<'a> {
+    slice: Str<'a>,
+    front: usize,
+    back: usize,
+}
+
+impl<'a> Iterator for Sentences<'a> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        let slice = self.slice.slice(self.front..self.back);
+        let mut chars = slice.char_indices().map(|(index, ch)| (self.front + index, ch));
+
+        let (start, mut end) = loop {
+            let (offset, ch) = chars.next()?;
+            if self.slice.is_empty_line(offset) {
+                self.front = offset + 1;
+                return Some(offset..offset);
+            }
+            if !ch.is_whitespace() {
+                break (offset, offset + ch.len_utf8());
+            }
+        };
+
+        // End at a sentence end, or after the last non-blank before an empty line.
+        for (offset, ch) in chars {
+            if self.slice.is_sentence_end(offset) || self.slice.is_empty_line(offset) {
+                break;
+            }
+            if !ch.is_whitespace() {
+                end = offset + ch.len_utf8();
+            }
+        }
+
+        self.front = end;
+        Some(start..end)
+    }
+}
+
+impl<'a> DoubleEndedIterator for Sentences<'a> {
+    fn next_back(&mut self) -> Option<Range<usize>> {
+        let slice = self.slice.slice(self.front..self.back);
+        let mut chars = slice.char_indices().rev().map(|(index, ch)| (self.front + index, ch));
+
+        let (mut start, end) = loop {
+            let (offset, ch) = chars.next()?;
+            if self.slice.is_empty_line(offset) {
+                self.back = offset;
+                return Some(offset..offset);
+            }
+            if !ch.is_whitespace() {
+                break (offset, offset + ch.len_utf8());
+            }
+        };
+
+        // Start at the first non-blank after a sentence end or an empty line.
+        for (offset, ch) in chars {
+            let after = offset + ch.len_utf8();
+            if self.slice.is_sentence_end(after) || self.slice.is_empty_line(offset) {
+                break;
+            }
+            if !ch.is_whitespace() {
+                start = offset;
+            }
+        }
+
+        self.back = start;
+        Some(start..end)
+    }
+}
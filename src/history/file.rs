@@ -0,0 +1,394 @@
+//! Undo files.
+//!
+//! An undo file stores the complete tree of an undo history, keyed by a hash of the buffer
+//! contents in its current state. Reading an undo file into a history for a buffer whose
+//! contents differ is rejected, since the recorded operations would not apply.
+//!
+//! # Format
+//!
+//! All integers are little-endian `u64` unless noted.
+//!
+//! - Magic bytes `VIOUNDO\0`.
+//! - Format version, `u32`.
+//! - FNV-1a hash of the buffer contents.
+//! - Number of states, followed by the sequence number of the current state.
+//! - For each state, starting with the original: parent, most recently visited child or zero,
+//!   seconds and nanoseconds (`u32`) since the Unix epoch, cursor before and after, number of
+//!   operations, then each operation as destination start, destination end, source length and
+//!   source bytes.
+
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read, Write};
+use std::string::String as StdString;
+use std::time::{Duration, UNIX_EPOCH};
+
+use operation::{Data, Operate, Operation};
+use text::{Chunk, TextBuffer};
+use super::{Transaction, Tree};
+use super::tree::Node;
+
+const MAGIC: &'static [u8; 8] = b"VIOUNDO\0";
+
+/// Current format version.
+pub const VERSION: u32 = 1;
+
+/// Owned operation data which can be stored in an undo file.
+pub trait Encode: Sized {
+    /// Returns the data as bytes.
+    fn as_bytes(&self) -> &[u8];
+
+    /// Converts bytes back to data, returning `None` if they are invalid.
+    fn from_bytes(bytes: Vec<u8>) -> Option<Self>;
+
+    /// Returns `true` if the data can be spliced at a byte offset.
+    fn is_boundary(&self, offset: usize) -> bool;
+}
+
+impl Encode for Vec<u8> {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn from_bytes(bytes: Vec<u8>) -> Option<Self> {
+        Some(bytes)
+    }
+
+    fn is_boundary(&self, offset: usize) -> bool {
+        offset <= self.len()
+    }
+}
+
+impl Encode for StdString {
+    fn as_bytes(&self) -> &[u8] {
+        self.as_bytes()
+    }
+
+    fn from_bytes(bytes: Vec<u8>) -> Option<Self> {
+        StdString::from_utf8(bytes).ok()
+    }
+
+    fn is_boundary(&self, offset: usize) -> bool {
+        self.is_char_boundary(offset)
+    }
+}
+
+/// Undo file error.
+#[derive(Debug)]
+pub enum Error {
+    /// I/O error.
+    Io(io::Error),
+
+    /// Not an undo file.
+    Magic,
+
+    /// Unsupported format version.
+    Version(u32),
+
+    /// Buffer contents do not match the hash in the undo file.
+    Hash,
+
+    /// Invalid undo file contents.
+    Corrupt,
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref error) => write!(f, "{}", error),
+            Error::Magic => f.write_str("not an undo file"),
+            Error::Version(version) => write!(f, "unsupported undo file version {}", version),
+            Error::Hash => f.write_str("file contents changed, undo file not applicable"),
+            Error::Corrupt => f.write_str("corrupt undo file"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// 64-bit FNV-1a hasher.
+///
+/// Unlike the standard library's default hasher, its output is stable across releases and
+/// platforms, so it is suitable for hashes stored on disk. Integers are hashed as little-endian
+/// `u64`.
+#[derive(Debug, Clone, Copy)]
+pub struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Fnv(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+}
+
+/// Returns the content hash of a buffer as stored in undo files.
+pub fn content_hash<T: Hash + ?Sized>(content: &T) -> u64 {
+    let mut hasher = Fnv::default();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl<T: Operate + Hash> Tree<T> where T::Owned: Encode {
+    /// Writes the history to an undo file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a transaction has begun, since the content hash would not match the state
+    /// written.
+    pub fn write_undo<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        assert!(self.pending.is_none(), "transaction begun");
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        write_u64(&mut writer, content_hash(&self.inner))?;
+        write_usize(&mut writer, self.nodes.len())?;
+        write_usize(&mut writer, self.current)?;
+
+        for node in &self.nodes {
+            let time = node.time.duration_since(UNIX_EPOCH).unwrap_or(Duration::new(0, 0));
+            write_usize(&mut writer, node.parent)?;
+            write_usize(&mut writer, node.next.unwrap_or(0))?;
+            write_u64(&mut writer, time.as_secs())?;
+            writer.write_all(&time.subsec_nanos().to_le_bytes())?;
+            write_usize(&mut writer, node.transaction.before)?;
+            write_usize(&mut writer, node.transaction.after)?;
+            write_usize(&mut writer, node.transaction.operations.len())?;
+            for operation in &node.transaction.operations {
+                let src = operation.src.as_bytes();
+                write_usize(&mut writer, operation.dest.start)?;
+                write_usize(&mut writer, operation.dest.end)?;
+                write_usize(&mut writer, src.len())?;
+                writer.write_all(src)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: TextBuffer + Hash> Tree<T>
+where <T::Unit as Chunk>::Owned: Encode + Operate<Owned = <T::Unit as Chunk>::Owned> {
+    /// Reads the history from an undo file, replacing the current history.
+    ///
+    /// Every recorded operation is checked to apply to the text of its state, so that moving
+    /// through the history cannot panic. The history is unchanged if an error is returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a transaction has begun.
+    pub fn read_undo<R: Read>(&mut self, mut reader: R) -> Result<(), Error> {
+        assert!(self.pending.is_none(), "transaction begun");
+
+        let mut magic = [0; 8];
+        read_exact(&mut reader, &mut magic)?;
+        if magic != *MAGIC {
+            return Err(Error::Magic);
+        }
+        let version = read_u32(&mut reader)?;
+        if version != VERSION {
+            return Err(Error::Version(version));
+        }
+        if read_u64(&mut reader)? != content_hash(&self.inner) {
+            return Err(Error::Hash);
+        }
+
+        let len = read_usize(&mut reader)?;
+        let current = read_usize(&mut reader)?;
+        if len == 0 || current >= len {
+            return Err(Error::Corrupt);
+        }
+
+        let mut nodes: Vec<Node<<T::Unit as Chunk>::Owned>> = Vec::new();
+        for seq in 0..len {
+            let parent = read_usize(&mut reader)?;
+            let next = read_usize(&mut reader)?;
+            let secs = read_u64(&mut reader)?;
+            let nanos = read_u32(&mut reader)?;
+            let before = read_usize(&mut reader)?;
+            let after = read_usize(&mut reader)?;
+            let next_valid = next == 0 || (next > seq && next < len);
+            if (seq > 0 && parent >= seq) || !next_valid || nanos >= 1_000_000_000 {
+                return Err(Error::Corrupt);
+            }
+
+            let count = read_usize(&mut reader)?;
+            let mut operations = Vec::new();
+            for _ in 0..count {
+                let start = read_usize(&mut reader)?;
+                let end = read_usize(&mut reader)?;
+                let src_len = read_u64(&mut reader)?;
+                let mut src = Vec::new();
+                (&mut reader).take(src_len).read_to_end(&mut src)?;
+                if start > end || src.len() as u64 != src_len {
+                    return Err(Error::Corrupt);
+                }
+                let src = <T::Unit as Chunk>::Owned::from_bytes(src).ok_or(Error::Corrupt)?;
+                operations.push(Operation {
+                    dest: start..end,
+                    src: src,
+                });
+            }
+
+            nodes.push(Node {
+                parent: parent,
+                depth: if seq == 0 { 0 } else { nodes[parent].depth + 1 },
+                time: UNIX_EPOCH.checked_add(Duration::new(secs, nanos)).ok_or(Error::Corrupt)?,
+                next: if next == 0 { None } else { Some(next) },
+                transaction: Transaction {
+                    operations: operations,
+                    before: before,
+                    after: after,
+                },
+            });
+        }
+
+        // Each state on the current branch must be a child of the state before it.
+        let branched = nodes.iter().enumerate()
+            .all(|(seq, node)| node.next.map_or(true, |next| nodes[next].parent == seq));
+        if !branched || !replays(&nodes, current, self.inner.chunks(..).concat()) {
+            return Err(Error::Corrupt);
+        }
+
+        self.nodes = nodes;
+        self.current = current;
+        Ok(())
+    }
+}
+
+// Returns `true` if every transaction applies to the text of the state it leads from, starting
+// with `text` in the current state. Transactions of the current branch are replayed back to the
+// original state, and each other branch is replayed from where it leaves the current one.
+fn replays<O>(nodes: &[Node<O>], current: usize, mut text: O) -> bool
+where O: Encode + Data + Operate<Owned = O> {
+    let mut children = vec![Vec::new(); nodes.len()];
+    for (seq, node) in nodes.iter().enumerate().skip(1) {
+        children[node.parent].push(seq);
+    }
+    let mut branch = vec![false; nodes.len()];
+    let mut seq = current;
+    while seq != 0 {
+        branch[seq] = true;
+        seq = nodes[seq].parent;
+    }
+
+    let mut seq = current;
+    loop {
+        // Each step either enters a state, or leaves one by applying the inverse transaction.
+        let mut steps: Vec<_> = children[seq].iter()
+            .filter(|&&child| !branch[child])
+            .map(|&child| Ok(child))
+            .collect();
+        while let Some(step) = steps.pop() {
+            match step {
+                Ok(child) => {
+                    match checked_apply(&nodes[child].transaction, &mut text) {
+                        Some(inverse) => steps.push(Err(inverse)),
+                        None => return false,
+                    }
+                    steps.extend(children[child].iter().map(|&child| Ok(child)));
+                },
+                Err(inverse) => {
+                    inverse.apply(&mut text);
+                },
+            }
+        }
+        if seq == 0 {
+            return true;
+        }
+        if checked_apply(&nodes[seq].transaction, &mut text).is_none() {
+            return false;
+        }
+        seq = nodes[seq].parent;
+    }
+}
+
+// Applies a transaction like `Transaction::apply`, returning `None` without applying the rest if
+// an operation's destination is out of range or does not fall on boundaries of the text.
+fn checked_apply<O>(transaction: &Transaction<O>, text: &mut O) -> Option<Transaction<O>>
+where O: Encode + Data + Operate<Owned = O> {
+    let mut operations = Vec::new();
+    for op in transaction.operations.iter().rev() {
+        let dest = op.dest.clone();
+        if dest.end > text.len() || !text.is_boundary(dest.start) || !text.is_boundary(dest.end) {
+            return None;
+        }
+        operations.push(text.apply(op));
+    }
+    Some(Transaction {
+        operations: operations,
+        before: transaction.after,
+        after: transaction.before,
+    })
+}
+
+fn write_u64<W: Write>(writer: &mut W, n: u64) -> io::Result<()> {
+    writer.write_all(&n.to_le_bytes())
+}
+
+fn write_usize<W: Write>(writer: &mut W, n: usize) -> io::Result<()> {
+    write_u64(writer, n as u64)
+}
+
+// Reads exactly enough bytes to fill `buf`, treating a truncated file as corrupt.
+fn read_exact<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<(), Error> {
+    reader.read_exact(buf).map_err(|error| {
+        if error.kind() == io::ErrorKind::UnexpectedEof {
+            Error::Corrupt
+        } else {
+            Error::Io(error)
+        }
+    })
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, Error> {
+    let mut bytes = [0; 4];
+    read_exact(reader, &mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64, Error> {
+    let mut bytes = [0; 8];
+    read_exact(reader, &mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_usize<R: Read>(reader: &mut R) -> Result<usize, Error> {
+    let n = read_u64(reader)?;
+    if n > usize::max_value() as u64 {
+        return Err(Error::Corrupt);
+    }
+    Ok(n as usize)
+}
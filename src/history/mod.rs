@@ -0,0 +1,202 @@
+//! Undo history.
+//!
+//! `History` records a linear undo stack, while `Tree` records every state of the buffer as a
+//! branching tree which can be navigated by sequence number and time. A `Tree` can be persisted
+//! to an undo file.
+
+use std::mem;
+
+use operation::{self, Data, Operate, Operation};
+
+pub mod file;
+mod tree;
+
+pub use self::tree::Tree;
+
+/// Linear undo history.
+///
+/// Wraps an `Operate` implementor and records the revert operation of each operation applied
+/// through it. Operations applied between `begin` and `commit` are grouped into a single
+/// transaction, which is undone and redone as a unit. Applying an operation after undoing
+/// discards the redo stack.
+#[derive(Debug)]
+pub struct History<T: Operate> {
+    inner: T,
+    undo: Vec<Transaction<T::Owned>>,
+    redo: Vec<Transaction<T::Owned>>,
+    pending: Option<Transaction<T::Owned>>,
+}
+
+// Group of operations undone and redone as a unit.
+#[derive(Debug, Clone)]
+struct Transaction<O> {
+    // Operations, applied in reverse order.
+    operations: Vec<Operation<O>>,
+
+    // Cursor position before the transaction was applied.
+    before: usize,
+
+    // Cursor position after the transaction was applied.
+    after: usize,
+}
+
+impl<O> Transaction<O> {
+    // Creates an empty transaction.
+    fn new(cursor: usize) -> Self {
+        Transaction {
+            operations: Vec::new(),
+            before: cursor,
+            after: cursor,
+        }
+    }
+
+    // Squashes the operations so that each logical edit is a single operation.
+    fn squash(&mut self) where O: Data {
+        let mut operations = mem::replace(&mut self.operations, Vec::new());
+        operations.reverse();
+        self.operations = operation::squash(&operations);
+        self.operations.reverse();
+    }
+
+    // Applies the operations in reverse order, returning the inverse transaction.
+    fn apply<T: Operate<Owned = O>>(&self, target: &mut T) -> Self {
+        let operations = self.operations.iter().rev().map(|op| target.apply(op)).collect();
+        Transaction {
+            operations: operations,
+            before: self.after,
+            after: self.before,
+        }
+    }
+}
+
+impl<T: Operate> History<T> {
+    /// Creates an empty history.
+    pub fn new(inner: T) -> Self {
+        History {
+            inner: inner,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            pending: None,
+        }
+    }
+
+    /// Returns a reference to the wrapped value.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped value.
+    ///
+    /// Changes made through the reference are not recorded, so it must not be used to change the
+    /// contents, only other state such as anchors.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Unwraps the value, discarding the history.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Returns `true` if there is a transaction to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Returns `true` if there is a transaction to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Begins a transaction with the cursor at `cursor`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a transaction has already begun.
+    pub fn begin(&mut self, cursor: usize) {
+        assert!(self.pending.is_none(), "transaction already begun");
+        self.pending = Some(Transaction::new(cursor));
+    }
+
+    /// Commits the current transaction with the cursor at `cursor`.
+    ///
+    /// Operations are squashed where possible and empty transactions are discarded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no transaction has begun.
+    pub fn commit(&mut self, cursor: usize) where T::Owned: Data {
+        let mut transaction = self.pending.take().expect("no transaction begun");
+        transaction.after = cursor;
+        transaction.squash();
+        if !transaction.operations.is_empty() {
+            self.undo.push(transaction);
+        }
+    }
+
+    /// Undoes the last transaction, returning the cursor position before it was applied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a transaction has begun.
+    pub fn undo(&mut self) -> Option<usize> {
+        assert!(self.pending.is_none(), "transaction begun");
+        let transaction = self.undo.pop()?;
+        let inverse = transaction.apply(&mut self.inner);
+        let cursor = inverse.after;
+        self.redo.push(inverse);
+        Some(cursor)
+    }
+
+    /// Redoes the last undone transaction, returning the cursor position after it was applied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a transaction has begun.
+    pub fn redo(&mut self) -> Option<usize> {
+        assert!(self.pending.is_none(), "transaction begun");
+        let transaction = self.redo.pop()?;
+        let inverse = transaction.apply(&mut self.inner);
+        let cursor = inverse.after;
+        self.undo.push(inverse);
+        Some(cursor)
+    }
+}
+
+/// Records the revert operations.
+///
+/// Outside of a transaction, each operation or batch is its own transaction with the cursor at the
+/// start of its destination.
+impl<T: Operate> Operate for History<T> where T::Owned: Clone {
+    type Owned = T::Owned;
+
+    fn apply(&mut self, operation: &Operation<T::Owned>) -> Operation<T::Owned> {
+        let revert = self.inner.apply(operation);
+        self.redo.clear();
+        match self.pending {
+            Some(ref mut transaction) => transaction.operations.push(revert.clone()),
+            None => {
+                let mut transaction = Transaction::new(operation.dest.start);
+                transaction.operations.push(revert.clone());
+                self.undo.push(transaction);
+            },
+        }
+        revert
+    }
+
+    fn apply_many(&mut self, operations: &[Operation<T::Owned>]) -> Vec<Operation<T::Owned>>
+    where T::Owned: Data {
+        let reverts = self.inner.apply_many(operations);
+        self.redo.clear();
+        match self.pending {
+            Some(ref mut transaction) => transaction.operations.extend(reverts.iter().cloned()),
+            None => {
+                let cursor = operations.iter().map(|op| op.dest.start).min().unwrap_or(0);
+                let mut transaction = Transaction::new(cursor);
+                transaction.operations = reverts.clone();
+                self.undo.push(transaction);
+            },
+        }
+        reverts
+    }
+}
@@ -0,0 +1,301 @@
+use std::time::{Duration, SystemTime};
+
+use operation::{Data, Operate, Operation};
+use super::Transaction;
+
+/// Branching undo history.
+///
+/// Wraps an `Operate` implementor and records each transaction as a node in a tree of buffer
+/// states. Applying an operation after undoing creates a new branch rather than discarding the
+/// undone transactions, so every state remains reachable.
+///
+/// Each state has a sequence number in the order it was created, with the original state as
+/// zero. States can be reached by undo and redo along the current branch, by sequence number
+/// across branches, or by the time they were created.
+#[derive(Debug)]
+pub struct Tree<T: Operate> {
+    pub(super) inner: T,
+    pub(super) nodes: Vec<Node<T::Owned>>,
+    pub(super) current: usize,
+    pub(super) pending: Option<Transaction<T::Owned>>,
+}
+
+// State of the buffer, indexed by sequence number.
+#[derive(Debug)]
+pub(super) struct Node<O> {
+    pub(super) parent: usize,
+    pub(super) depth: usize,
+    pub(super) time: SystemTime,
+
+    // Child most recently created or redone.
+    pub(super) next: Option<usize>,
+
+    // Transaction leading to the parent if this state is on the current branch, otherwise to this
+    // state from the parent.
+    pub(super) transaction: Transaction<O>,
+}
+
+impl<T: Operate> Tree<T> {
+    /// Creates an empty history.
+    pub fn new(inner: T) -> Self {
+        Tree {
+            inner: inner,
+            nodes: vec![Node {
+                parent: 0,
+                depth: 0,
+                time: SystemTime::now(),
+                next: None,
+                transaction: Transaction::new(0),
+            }],
+            current: 0,
+            pending: None,
+        }
+    }
+
+    /// Returns a reference to the wrapped value.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped value.
+    ///
+    /// Changes made through the reference are not recorded, so it must not be used to change the
+    /// contents, only other state such as anchors.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Unwraps the value, discarding the history.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Returns the sequence number of the current state.
+    pub fn seq(&self) -> usize {
+        self.current
+    }
+
+    /// Returns the sequence number of the most recently created state.
+    pub fn seq_last(&self) -> usize {
+        self.nodes.len() - 1
+    }
+
+    /// Returns the time the current state was created.
+    pub fn time(&self) -> SystemTime {
+        self.nodes[self.current].time
+    }
+
+    /// Returns `true` if there is a transaction to undo.
+    pub fn can_undo(&self) -> bool {
+        self.current != 0
+    }
+
+    /// Returns `true` if there is a transaction to redo on the current branch.
+    pub fn can_redo(&self) -> bool {
+        self.nodes[self.current].next.is_some()
+    }
+
+    /// Begins a transaction with the cursor at `cursor`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a transaction has already begun.
+    pub fn begin(&mut self, cursor: usize) {
+        assert!(self.pending.is_none(), "transaction already begun");
+        self.pending = Some(Transaction::new(cursor));
+    }
+
+    /// Commits the current transaction with the cursor at `cursor`, creating a new state.
+    ///
+    /// Operations are squashed where possible and empty transactions are discarded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no transaction has begun.
+    pub fn commit(&mut self, cursor: usize) where T::Owned: Data {
+        self.commit_at(cursor, SystemTime::now());
+    }
+
+    /// Commits the current transaction as though it was created at `time`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no transaction has begun.
+    pub fn commit_at(&mut self, cursor: usize, time: SystemTime) where T::Owned: Data {
+        let mut transaction = self.pending.take().expect("no transaction begun");
+        transaction.after = cursor;
+        transaction.squash();
+        if !transaction.operations.is_empty() {
+            self.push(transaction, time);
+        }
+    }
+
+    /// Undoes the transaction leading to the current state, returning the cursor position before
+    /// it was applied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a transaction has begun.
+    pub fn undo(&mut self) -> Option<usize> {
+        assert!(self.pending.is_none(), "transaction begun");
+        if self.current == 0 {
+            return None;
+        }
+        Some(self.up())
+    }
+
+    /// Redoes the most recently undone transaction on the current branch, returning the cursor
+    /// position after it was applied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a transaction has begun.
+    pub fn redo(&mut self) -> Option<usize> {
+        assert!(self.pending.is_none(), "transaction begun");
+        let next = self.nodes[self.current].next?;
+        Some(self.down(next))
+    }
+
+    /// Moves to the state with sequence number `seq`, across branches if necessary.
+    ///
+    /// Returns the resulting cursor position, or `None` if already in that state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a transaction has begun or `seq` is out of bounds.
+    pub fn goto(&mut self, seq: usize) -> Option<usize> {
+        assert!(self.pending.is_none(), "transaction begun");
+        assert!(seq < self.nodes.len(), "seq out of bounds");
+
+        let mut up = self.current;
+        let mut down = seq;
+        let mut path = Vec::new();
+        while self.nodes[down].depth > self.nodes[up].depth {
+            path.push(down);
+            down = self.nodes[down].parent;
+        }
+        while up != down {
+            if self.nodes[up].depth > self.nodes[down].depth {
+                up = self.nodes[up].parent;
+            } else {
+                path.push(down);
+                up = self.nodes[up].parent;
+                down = self.nodes[down].parent;
+            }
+        }
+
+        let mut cursor = None;
+        while self.current != up {
+            cursor = Some(self.up());
+        }
+        for &node in path.iter().rev() {
+            cursor = Some(self.down(node));
+        }
+        cursor
+    }
+
+    /// Moves `count` states back in sequence, like `g-`.
+    pub fn earlier(&mut self, count: usize) -> Option<usize> {
+        let seq = self.current.saturating_sub(count);
+        self.goto(seq)
+    }
+
+    /// Moves `count` states forward in sequence, like `g+`.
+    pub fn later(&mut self, count: usize) -> Option<usize> {
+        let seq = self.current.saturating_add(count).min(self.seq_last());
+        self.goto(seq)
+    }
+
+    /// Moves to the last state created at least `duration` before the current state, like
+    /// `:earlier 5m`.
+    pub fn earlier_time(&mut self, duration: Duration) -> Option<usize> {
+        let seq = match self.time().checked_sub(duration) {
+            Some(time) => self.seq_at(time),
+            None => 0,
+        };
+        self.goto(seq)
+    }
+
+    /// Moves to the last state created at most `duration` after the current state, like
+    /// `:later 5m`.
+    pub fn later_time(&mut self, duration: Duration) -> Option<usize> {
+        let seq = match self.time().checked_add(duration) {
+            Some(time) => self.seq_at(time),
+            None => self.seq_last(),
+        };
+        self.goto(seq)
+    }
+
+    // Returns the sequence number of the last state created at or before `time`.
+    fn seq_at(&self, time: SystemTime) -> usize {
+        self.nodes.iter().rposition(|node| node.time <= time).unwrap_or(0)
+    }
+
+    // Adds a state as a child of the current state and moves to it.
+    fn push(&mut self, transaction: Transaction<T::Owned>, time: SystemTime) {
+        let seq = self.nodes.len();
+        let node = Node {
+            parent: self.current,
+            depth: self.nodes[self.current].depth + 1,
+            time: time,
+            next: None,
+            transaction: transaction,
+        };
+        self.nodes.push(node);
+        self.nodes[self.current].next = Some(seq);
+        self.current = seq;
+    }
+
+    // Moves to the parent state, returning the cursor position.
+    fn up(&mut self) -> usize {
+        let node = &mut self.nodes[self.current];
+        node.transaction = node.transaction.apply(&mut self.inner);
+        self.current = node.parent;
+        node.transaction.after
+    }
+
+    // Moves to a child state, returning the cursor position.
+    fn down(&mut self, child: usize) -> usize {
+        self.nodes[self.current].next = Some(child);
+        let node = &mut self.nodes[child];
+        node.transaction = node.transaction.apply(&mut self.inner);
+        self.current = child;
+        node.transaction.after
+    }
+}
+
+/// Records the revert operations.
+///
+/// Outside of a transaction, each operation or batch creates its own state with the cursor at the
+/// start of its destination.
+impl<T: Operate> Operate for Tree<T> where T::Owned: Clone {
+    type Owned = T::Owned;
+
+    fn apply(&mut self, operation: &Operation<T::Owned>) -> Operation<T::Owned> {
+        let revert = self.inner.apply(operation);
+        match self.pending {
+            Some(ref mut transaction) => transaction.operations.push(revert.clone()),
+            None => {
+                let mut transaction = Transaction::new(operation.dest.start);
+                transaction.operations.push(revert.clone());
+                self.push(transaction, SystemTime::now());
+            },
+        }
+        revert
+    }
+
+    fn apply_many(&mut self, operations: &[Operation<T::Owned>]) -> Vec<Operation<T::Owned>>
+    where T::Owned: Data {
+        let reverts = self.inner.apply_many(operations);
+        match self.pending {
+            Some(ref mut transaction) => transaction.operations.extend(reverts.iter().cloned()),
+            None => {
+                let cursor = operations.iter().map(|op| op.dest.start).min().unwrap_or(0);
+                let mut transaction = Transaction::new(cursor);
+                transaction.operations = reverts.clone();
+                self.push(transaction, SystemTime::now());
+            },
+        }
+        reverts
+    }
+}
@@ -17,6 +17,21 @@
     type_ascription,
 )]
 
+pub mod block;
+pub mod ex;
 pub mod gap;
+pub mod history;
+pub mod motion;
+pub mod normal;
+pub mod object;
 pub mod operation;
+pub mod operator;
+pub mod piece;
 pub mod range;
+pub mod register;
+pub mod regex;
+pub mod search;
+pub mod text;
+pub mod unicode;
+pub mod width;
+pub mod word;
@@ -0,0 +1,578 @@
+//! Cursor motions.
+//!
+//! Each motion is a function of the text, the cursor offset and a count, which returns where the
+//! cursor moves and how an operator treats the text moved over, or `None` if the motion fails,
+//! where vi would beep.
+//!
+//! Targets of charwise motions may be at the end of a line, after its last char, which is where
+//! exclusive motions stop for operators. In normal mode, the editor moves the cursor back onto the
+//! last char.
+
+use std::ops::Range;
+
+use gap::{Str, String, Words};
+use normal::Motion;
+use width::Curswant;
+use word::Keyword;
+
+/// Target of a motion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Target {
+    /// Offset the cursor moves to.
+    pub offset: usize,
+
+    /// Whether an operator includes the char at the offset. Only used for charwise motions.
+    pub inclusive: bool,
+
+    /// Whether an operator works on whole lines.
+    pub linewise: bool,
+}
+
+impl Target {
+    fn exclusive(offset: usize) -> Self {
+        Target { offset: offset, inclusive: false, linewise: false }
+    }
+
+    fn inclusive(offset: usize) -> Self {
+        Target { offset: offset, inclusive: true, linewise: false }
+    }
+
+    fn linewise(offset: usize) -> Self {
+        Target { offset: offset, inclusive: false, linewise: true }
+    }
+}
+
+/// Editor state which some motions depend on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Context {
+    /// Keyword chars for word motions.
+    pub keyword: Keyword,
+
+    /// Width of a tab.
+    pub tabstop: usize,
+
+    /// Column vertical motions keep.
+    pub curswant: Curswant,
+
+    /// Lines shown on the screen.
+    pub screen: Range<usize>,
+
+    /// Last `f`, `F`, `t` or `T` motion.
+    pub last_find: Option<Motion>,
+}
+
+/// Returns the target of a normal mode motion.
+///
+/// Without a count, `%` moves to the matching bracket, and with one to that percentage of the
+/// lines. Returns `None` for marks and searches, whose targets depend on more of the editor.
+///
+/// # Panics
+///
+/// Panics if `cursor` is out of bounds.
+pub fn target(
+    text: &String,
+    cursor: usize,
+    motion: Motion,
+    count: Option<usize>,
+    context: &Context,
+) -> Option<Target> {
+    let n = count.unwrap_or(1);
+    let keyword = Some(&context.keyword);
+    match motion {
+        Motion::Left => left(text, cursor, n),
+        Motion::Right => right(text, cursor, n),
+        Motion::Up => up(text, cursor, n, context.curswant, context.tabstop),
+        Motion::Down => down(text, cursor, n, context.curswant, context.tabstop),
+        Motion::NextLine => next_line(text, cursor, n),
+        Motion::PrevLine => prev_line(text, cursor, n),
+        Motion::WordStart { big } => word_start(text, cursor, n, if big { None } else { keyword }),
+        Motion::WordBack { big } => word_back(text, cursor, n, if big { None } else { keyword }),
+        Motion::WordEnd { big } => word_end(text, cursor, n, if big { None } else { keyword }),
+        Motion::WordEndBack { big } => {
+            word_end_back(text, cursor, n, if big { None } else { keyword })
+        },
+        Motion::LineStart => Some(line_start(text, cursor)),
+        Motion::FirstNonBlank => Some(first_non_blank(text, cursor)),
+        Motion::LineEnd => line_end(text, cursor, n),
+        Motion::Column => Some(column(text, cursor, n, context.tabstop)),
+        Motion::FirstLine => Some(first_line(text, count)),
+        Motion::LastLine => Some(last_line(text, count)),
+        Motion::Find(ch) => find(text, cursor, n, ch),
+        Motion::FindBack(ch) => find_back(text, cursor, n, ch),
+        Motion::Till(ch) => till(text, cursor, n, ch),
+        Motion::TillBack(ch) => till_back(text, cursor, n, ch),
+        Motion::RepeatFind => repeat_find(text, cursor, n, context.last_find?, false),
+        Motion::RepeatFindReverse => repeat_find(text, cursor, n, context.last_find?, true),
+        Motion::MatchPair => match count {
+            Some(count) => percent(text, count),
+            None => match_pair(text, cursor),
+        },
+        Motion::SentenceForward => sentence_forward(text, cursor, n),
+        Motion::SentenceBackward => sentence_backward(text, cursor, n),
+        Motion::ParagraphForward => paragraph_forward(text, cursor, n),
+        Motion::ParagraphBackward => paragraph_backward(text, cursor, n),
+        Motion::ScreenTop => Some(screen_top(text, n, context.screen.clone())),
+        Motion::ScreenMiddle => Some(screen_middle(text, context.screen.clone())),
+        Motion::ScreenBottom => Some(screen_bottom(text, n, context.screen.clone())),
+        Motion::Mark { .. } | Motion::Search(_) | Motion::SearchNext | Motion::SearchPrev => None,
+    }
+}
+
+/// Moves `count` chars left within the line, as with `h`. Exclusive.
+pub fn left(text: &String, cursor: usize, count: usize) -> Option<Target> {
+    let start = text.line_range(text.line_of(cursor)).start;
+    if cursor == start {
+        return None;
+    }
+    let mut offset = cursor;
+    for _ in 0..count {
+        if offset == start {
+            break;
+        }
+        offset = text.prev_grapheme_boundary(offset);
+    }
+    Some(Target::exclusive(offset))
+}
+
+/// Moves `count` chars right within the line, as with `l`, up to the end of the line.
+/// Exclusive.
+pub fn right(text: &String, cursor: usize, count: usize) -> Option<Target> {
+    let end = text.line_range(text.line_of(cursor)).end;
+    if cursor >= end {
+        return None;
+    }
+    let mut offset = cursor;
+    for _ in 0..count {
+        if offset == end {
+            break;
+        }
+        offset = text.next_grapheme_boundary(offset);
+    }
+    Some(Target::exclusive(offset))
+}
+
+/// Moves `count` lines up, as with `k`, keeping to the column of `curswant`. Linewise.
+pub fn up(
+    text: &String,
+    cursor: usize,
+    count: usize,
+    curswant: Curswant,
+    tabstop: usize,
+) -> Option<Target> {
+    let line = text.line_of(cursor);
+    if line == 0 {
+        return None;
+    }
+    let line = line.saturating_sub(count);
+    let range = text.line_range(line);
+    Some(Target::linewise(range.start + curswant.offset(text.slice(range), tabstop)))
+}
+
+/// Moves `count` lines down, as with `j`, keeping to the column of `curswant`. Linewise.
+pub fn down(
+    text: &String,
+    cursor: usize,
+    count: usize,
+    curswant: Curswant,
+    tabstop: usize,
+) -> Option<Target> {
+    let line = text.line_of(cursor);
+    let last = text.line_count() - 1;
+    if line == last {
+        return None;
+    }
+    let line = line.saturating_add(count).min(last);
+    let range = text.line_range(line);
+    Some(Target::linewise(range.start + curswant.offset(text.slice(range), tabstop)))
+}
+
+/// Moves to the first non-blank `count` lines down, as with `+`. Linewise.
+pub fn next_line(text: &String, cursor: usize, count: usize) -> Option<Target> {
+    let line = text.line_of(cursor);
+    let last = text.line_count() - 1;
+    if line == last {
+        return None;
+    }
+    let line = line.saturating_add(count).min(last);
+    Some(Target::linewise(first_non_blank_of(text, line)))
+}
+
+/// Moves to the first non-blank `count` lines up, as with `-`. Linewise.
+pub fn prev_line(text: &String, cursor: usize, count: usize) -> Option<Target> {
+    let line = text.line_of(cursor);
+    if line == 0 {
+        return None;
+    }
+    Some(Target::linewise(first_non_blank_of(text, line.saturating_sub(count))))
+}
+
+/// Moves to the start of the `count`th next word, as with `w`, or WORD if `keyword` is `None`.
+/// Empty lines are words. Moves to the end of the text after the last word. Exclusive.
+pub fn word_start(
+    text: &String,
+    cursor: usize,
+    count: usize,
+    keyword: Option<&Keyword>,
+) -> Option<Target> {
+    let found = words(text.slice(cursor..), keyword)
+        .map(|span| cursor + span.start)
+        .filter(|&start| start > cursor)
+        .nth(count.saturating_sub(1));
+    match found {
+        Some(start) => Some(Target::exclusive(start)),
+        None if cursor < text.len() => Some(Target::exclusive(text.len())),
+        None => None,
+    }
+}
+
+/// Moves to the start of the `count`th previous word, as with `b`, or WORD if `keyword` is
+/// `None`. Empty lines are words. Exclusive.
+pub fn word_back(
+    text: &String,
+    cursor: usize,
+    count: usize,
+    keyword: Option<&Keyword>,
+) -> Option<Target> {
+    let found = words(text.slice(..cursor), keyword).rev().nth(count.saturating_sub(1));
+    match found {
+        Some(span) => Some(Target::exclusive(span.start)),
+        None if cursor > 0 => Some(Target::exclusive(0)),
+        None => None,
+    }
+}
+
+/// Moves to the end of the `count`th next word, as with `e`, or WORD if `keyword` is `None`.
+/// Empty lines are skipped. Inclusive.
+pub fn word_end(
+    text: &String,
+    cursor: usize,
+    count: usize,
+    keyword: Option<&Keyword>,
+) -> Option<Target> {
+    words(text.slice(cursor..), keyword)
+        .filter(|span| !span.is_empty())
+        .map(|span| text.prev_grapheme_boundary(cursor + span.end))
+        .filter(|&last| last > cursor)
+        .nth(count.saturating_sub(1))
+        .map(Target::inclusive)
+}
+
+/// Moves to the end of the `count`th previous word, as with `ge`, or WORD if `keyword` is
+/// `None`. Empty lines are words. Moves to the start of the text before the first word.
+/// Inclusive.
+pub fn word_end_back(
+    text: &String,
+    cursor: usize,
+    count: usize,
+    keyword: Option<&Keyword>,
+) -> Option<Target> {
+    let next = text.next_grapheme_boundary(cursor);
+    let found = words(text.slice(..next), keyword).rev()
+        .map(|span| {
+            if span.is_empty() { span.start } else { text.prev_grapheme_boundary(span.end) }
+        })
+        .filter(|&last| last < cursor)
+        .nth(count.saturating_sub(1));
+    match found {
+        Some(last) => Some(Target::inclusive(last)),
+        None if cursor > 0 => Some(Target::inclusive(0)),
+        None => None,
+    }
+}
+
+/// Moves to the start of the line, as with `0`. Exclusive.
+pub fn line_start(text: &String, cursor: usize) -> Target {
+    Target::exclusive(text.line_range(text.line_of(cursor)).start)
+}
+
+/// Moves to the first non-blank of the line, or its end if it is blank, as with `^`. Exclusive.
+pub fn first_non_blank(text: &String, cursor: usize) -> Target {
+    Target::exclusive(first_non_blank_of(text, text.line_of(cursor)))
+}
+
+/// Moves to the last char of the line `count - 1` lines down, as with `$`. Inclusive, unless
+/// the line is empty.
+pub fn line_end(text: &String, cursor: usize, count: usize) -> Option<Target> {
+    let line = text.line_of(cursor).checked_add(count.saturating_sub(1))?;
+    if line >= text.line_count() {
+        return None;
+    }
+    let range = text.line_range(line);
+    if range.start == range.end {
+        Some(Target::exclusive(range.start))
+    } else {
+        Some(Target::inclusive(text.prev_grapheme_boundary(range.end)))
+    }
+}
+
+/// Moves to the char displayed at column `count` of the line, or the end of the line if it is
+/// shorter, as with `|`. Columns start at one. Exclusive.
+pub fn column(text: &String, cursor: usize, count: usize, tabstop: usize) -> Target {
+    let line = text.line_of(cursor);
+    Target::exclusive(text.virtcol_offset(line, count.saturating_sub(1), tabstop))
+}
+
+/// Moves to the first non-blank of line `count`, or the first line, as with `gg`. Linewise.
+pub fn first_line(text: &String, count: Option<usize>) -> Target {
+    goto_line(text, count.map_or(0, |count| count.saturating_sub(1)))
+}
+
+/// Moves to the first non-blank of line `count`, or the last line, as with `G`. Linewise.
+pub fn last_line(text: &String, count: Option<usize>) -> Target {
+    goto_line(text, count.map_or(text.line_count() - 1, |count| count.saturating_sub(1)))
+}
+
+/// Moves to the `count`th next `ch` in the line, as with `f`. Inclusive.
+pub fn find(text: &String, cursor: usize, count: usize, ch: char) -> Option<Target> {
+    find_forward(text, cursor, count, ch, false, false)
+}
+
+/// Moves to the `count`th previous `ch` in the line, as with `F`. Exclusive.
+pub fn find_back(text: &String, cursor: usize, count: usize, ch: char) -> Option<Target> {
+    find_backward(text, cursor, count, ch, false, false)
+}
+
+/// Moves before the `count`th next `ch` in the line, as with `t`. Inclusive.
+pub fn till(text: &String, cursor: usize, count: usize, ch: char) -> Option<Target> {
+    find_forward(text, cursor, count, ch, true, false)
+}
+
+/// Moves after the `count`th previous `ch` in the line, as with `T`. Exclusive.
+pub fn till_back(text: &String, cursor: usize, count: usize, ch: char) -> Option<Target> {
+    find_backward(text, cursor, count, ch, true, false)
+}
+
+/// Repeats `last`, which is an `f`, `F`, `t` or `T` motion, as with `;`, or in the opposite
+/// direction if `reverse` is set, as with `,`.
+///
+/// A repeated `t` or `T` skips a `ch` next to the cursor, so that it does not stay in place.
+/// Returns `None` if `last` is another motion.
+pub fn repeat_find(
+    text: &String,
+    cursor: usize,
+    count: usize,
+    last: Motion,
+    reverse: bool,
+) -> Option<Target> {
+    let (ch, forward, till) = match last {
+        Motion::Find(ch) => (ch, true, false),
+        Motion::FindBack(ch) => (ch, false, false),
+        Motion::Till(ch) => (ch, true, true),
+        Motion::TillBack(ch) => (ch, false, true),
+        _ => return None,
+    };
+    if forward != reverse {
+        find_forward(text, cursor, count, ch, till, true)
+    } else {
+        find_backward(text, cursor, count, ch, till, true)
+    }
+}
+
+/// Moves to the bracket matching the first of `()[]{}` at or after the cursor in the line, as
+/// with `%`. Inclusive.
+pub fn match_pair(text: &String, cursor: usize) -> Option<Target> {
+    let end = text.line_range(text.line_of(cursor)).end;
+    let (offset, bracket) = text.slice(cursor..end).char_indices()
+        .map(|(index, ch)| (cursor + index, ch))
+        .find(|&(_, ch)| "()[]{}".contains(ch))?;
+    let (open, close, forward) = match bracket {
+        '(' => ('(', ')', true),
+        '[' => ('[', ']', true),
+        '{' => ('{', '}', true),
+        ')' => ('(', ')', false),
+        ']' => ('[', ']', false),
+        _ => ('{', '}', false),
+    };
+
+    let mut depth = 0usize;
+    let step = |depth: &mut usize, ch: char, inner: char, outer: char| {
+        if ch == inner {
+            *depth += 1;
+        } else if ch == outer {
+            if *depth == 0 {
+                return true;
+            }
+            *depth -= 1;
+        }
+        false
+    };
+    let found = if forward {
+        let start = offset + 1;
+        text.slice(start..).char_indices()
+            .find(|&(_, ch)| step(&mut depth, ch, open, close))
+            .map(|(index, _)| start + index)
+    } else {
+        text.slice(..offset).char_indices().rev()
+            .find(|&(_, ch)| step(&mut depth, ch, close, open))
+            .map(|(index, _)| index)
+    };
+    found.map(Target::inclusive)
+}
+
+/// Moves to the first non-blank of the line `count` percent through the text, as with `%` with
+/// a count. Linewise.
+pub fn percent(text: &String, count: usize) -> Option<Target> {
+    if count == 0 || count > 100 {
+        return None;
+    }
+    let line = (count * text.line_count() + 99) / 100;
+    Some(goto_line(text, line.saturating_sub(1)))
+}
+
+/// Moves to the start of the `count`th next sentence, as with `)`, or the end of the text after
+/// the last sentence. Empty lines are sentences. Exclusive.
+pub fn sentence_forward(text: &String, cursor: usize, count: usize) -> Option<Target> {
+    let found = text.slice(cursor..).sentences()
+        .map(|span| cursor + span.start)
+        .filter(|&start| start > cursor)
+        .nth(count.saturating_sub(1));
+    match found {
+        Some(start) => Some(Target::exclusive(start)),
+        None if cursor < text.len() => Some(Target::exclusive(text.len())),
+        None => None,
+    }
+}
+
+/// Moves to the start of the `count`th previous sentence, as with `(`. Empty lines are
+/// sentences. Exclusive.
+pub fn sentence_backward(text: &String, cursor: usize, count: usize) -> Option<Target> {
+    let found = text.slice(..cursor).sentences().rev().nth(count.saturating_sub(1));
+    match found {
+        Some(span) => Some(Target::exclusive(span.start)),
+        None if cursor > 0 => Some(Target::exclusive(0)),
+        None => None,
+    }
+}
+
+/// Moves to the `count`th next empty line after a paragraph, as with `}`, or the end of the
+/// text. Exclusive.
+pub fn paragraph_forward(text: &String, cursor: usize, count: usize) -> Option<Target> {
+    if cursor == text.len() {
+        return None;
+    }
+    let last = text.line_count() - 1;
+    let mut line = text.line_of(cursor);
+    for _ in 0..count {
+        while line < last && is_empty_line(text, line) {
+            line += 1;
+        }
+        while line < last && !is_empty_line(text, line) {
+            line += 1;
+        }
+    }
+    let start = text.line_range(line).start;
+    if is_empty_line(text, line) && start > cursor {
+        Some(Target::exclusive(start))
+    } else {
+        Some(Target::exclusive(text.len()))
+    }
+}
+
+/// Moves to the `count`th previous empty line before a paragraph, as with `{`, or the start of
+/// the text. Exclusive.
+pub fn paragraph_backward(text: &String, cursor: usize, count: usize) -> Option<Target> {
+    if cursor == 0 {
+        return None;
+    }
+    let mut line = text.line_of(cursor);
+    for _ in 0..count {
+        while line > 0 && is_empty_line(text, line) {
+            line -= 1;
+        }
+        while line > 0 && !is_empty_line(text, line) {
+            line -= 1;
+        }
+    }
+    Some(Target::exclusive(text.line_range(line).start))
+}
+
+/// Moves to the first non-blank of line `count` of the screen, as with `H`. Linewise.
+pub fn screen_top(text: &String, count: usize, screen: Range<usize>) -> Target {
+    let (first, last) = screen_lines(text, screen);
+    goto_line(text, first.saturating_add(count.saturating_sub(1)).min(last))
+}
+
+/// Moves to the first non-blank of the middle line of the screen, as with `M`. Linewise.
+pub fn screen_middle(text: &String, screen: Range<usize>) -> Target {
+    let (first, last) = screen_lines(text, screen);
+    goto_line(text, first + (last - first) / 2)
+}
+
+/// Moves to the first non-blank of line `count` from the bottom of the screen, as with `L`.
+/// Linewise.
+pub fn screen_bottom(text: &String, count: usize, screen: Range<usize>) -> Target {
+    let (first, last) = screen_lines(text, screen);
+    goto_line(text, last.saturating_sub(count.saturating_sub(1)).max(first))
+}
+
+fn words<'a>(slice: Str<'a>, keyword: Option<&Keyword>) -> Words<'a> {
+    match keyword {
+        Some(keyword) => slice.words(keyword),
+        None => slice.big_words(),
+    }
+}
+
+fn first_non_blank_of(text: &String, line: usize) -> usize {
+    let range = text.line_range(line);
+    text.slice(range.clone()).char_indices()
+        .find(|&(_, ch)| ch != ' ' && ch != '\t')
+        .map_or(range.end, |(index, _)| range.start + index)
+}
+
+fn goto_line(text: &String, line: usize) -> Target {
+    Target::linewise(first_non_blank_of(text, line.min(text.line_count() - 1)))
+}
+
+fn is_empty_line(text: &String, line: usize) -> bool {
+    text.line_range(line).is_empty()
+}
+
+// Returns the first and last lines of the screen which are in the text.
+fn screen_lines(text: &String, screen: Range<usize>) -> (usize, usize) {
+    let last = text.line_count() - 1;
+    let first = screen.start.min(last);
+    (first, screen.end.saturating_sub(1).min(last).max(first))
+}
+
+// Finds `ch` after the cursor. A repeated till skips a `ch` just after the cursor.
+fn find_forward(
+    text: &String,
+    cursor: usize,
+    count: usize,
+    ch: char,
+    till: bool,
+    repeat: bool,
+) -> Option<Target> {
+    let end = text.line_range(text.line_of(cursor)).end;
+    let skip = if till && repeat { 2 } else { 1 };
+    let (index, _) = text.slice(cursor..end).char_indices()
+        .skip(skip)
+        .filter(|&(_, found)| found == ch)
+        .nth(count.saturating_sub(1))?;
+    let offset = cursor + index;
+    if till {
+        let before = text.slice(..offset).chars().next_back().map_or(0, char::len_utf8);
+        Some(Target::inclusive(offset - before))
+    } else {
+        Some(Target::inclusive(offset))
+    }
+}
+
+// Finds `ch` before the cursor. A repeated till skips a `ch` just before the cursor.
+fn find_backward(
+    text: &String,
+    cursor: usize,
+    count: usize,
+    ch: char,
+    till: bool,
+    repeat: bool,
+) -> Option<Target> {
+    let start = text.line_range(text.line_of(cursor)).start;
+    let skip = if till && repeat { 1 } else { 0 };
+    let (index, _) = text.slice(start..cursor).char_indices().rev()
+        .skip(skip)
+        .filter(|&(_, found)| found == ch)
+        .nth(count.saturating_sub(1))?;
+    let offset = start + index;
+    Some(Target::exclusive(if till { offset + ch.len_utf8() } else { offset }))
+}
@@ -0,0 +1,728 @@
+//! Normal mode commands.
+//!
+//! Keys typed in normal mode follow vi's grammar `["x][count]operator[count]motion`, where the
+//! motion may also be a text object, or the operator doubled as in `dd` to work on lines. A
+//! `Parser` is given one key at a time and reports whether the keys so far are an incomplete,
+//! complete or invalid command, so that the editor can drive it keystroke by keystroke.
+//!
+//! Keys are chars, with control keys such as `^V` as ASCII control chars.
+
+use std::string::String as StdString;
+
+use search::Direction;
+
+/// Escape key, which cancels a pending command.
+pub const ESCAPE: char = '\x1b';
+
+/// Operator, which works on the text a motion moves over or a text object selects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operator {
+    /// Delete, as with `d`.
+    Delete,
+
+    /// Delete and start insert mode, as with `c`.
+    Change,
+
+    /// Yank into a register, as with `y`.
+    Yank,
+
+    /// Shift lines left, as with `<`.
+    ShiftLeft,
+
+    /// Shift lines right, as with `>`.
+    ShiftRight,
+
+    /// Make lowercase, as with `gu`.
+    Lowercase,
+
+    /// Make uppercase, as with `gU`.
+    Uppercase,
+
+    /// Switch case, as with `g~`.
+    ToggleCase,
+
+    /// Indent lines, as with `=`.
+    Indent,
+
+    /// Format lines to the text width, as with `gq`.
+    Format,
+}
+
+impl Operator {
+    // Returns the last key of the operator, which repeated works on lines, as in `guu`.
+    fn last_key(self) -> char {
+        match self {
+            Operator::Delete => 'd',
+            Operator::Change => 'c',
+            Operator::Yank => 'y',
+            Operator::ShiftLeft => '<',
+            Operator::ShiftRight => '>',
+            Operator::Lowercase => 'u',
+            Operator::Uppercase => 'U',
+            Operator::ToggleCase => '~',
+            Operator::Indent => '=',
+            Operator::Format => 'q',
+        }
+    }
+}
+
+/// Motion, which moves the cursor or gives the text an operator works on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Motion {
+    /// Chars left, as with `h`.
+    Left,
+
+    /// Chars right, as with `l`.
+    Right,
+
+    /// Lines up, as with `k`.
+    Up,
+
+    /// Lines down, as with `j`.
+    Down,
+
+    /// First non-blank of a later line, as with `+`.
+    NextLine,
+
+    /// First non-blank of an earlier line, as with `-`.
+    PrevLine,
+
+    /// Start of the next word, as with `w`, or WORD, as with `W`.
+    WordStart {
+        /// Whether words are only separated by blanks.
+        big: bool,
+    },
+
+    /// Start of the previous word, as with `b`, or WORD, as with `B`.
+    WordBack {
+        /// Whether words are only separated by blanks.
+        big: bool,
+    },
+
+    /// End of the next word, as with `e`, or WORD, as with `E`.
+    WordEnd {
+        /// Whether words are only separated by blanks.
+        big: bool,
+    },
+
+    /// End of the previous word, as with `ge`, or WORD, as with `gE`.
+    WordEndBack {
+        /// Whether words are only separated by blanks.
+        big: bool,
+    },
+
+    /// Start of the line, as with `0`.
+    LineStart,
+
+    /// First non-blank of the line, as with `^`.
+    FirstNonBlank,
+
+    /// End of the line, as with `$`.
+    LineEnd,
+
+    /// A column of the line, or the first column without a count, as with `|`.
+    Column,
+
+    /// A line, or the first line without a count, as with `gg`.
+    FirstLine,
+
+    /// A line, or the last line without a count, as with `G`.
+    LastLine,
+
+    /// Next occurrence of a char in the line, as with `f`.
+    Find(char),
+
+    /// Previous occurrence of a char in the line, as with `F`.
+    FindBack(char),
+
+    /// Before the next occurrence of a char in the line, as with `t`.
+    Till(char),
+
+    /// After the previous occurrence of a char in the line, as with `T`.
+    TillBack(char),
+
+    /// Repeat the last `f`, `F`, `t` or `T`, as with `;`.
+    RepeatFind,
+
+    /// Repeat the last `f`, `F`, `t` or `T` in the opposite direction, as with `,`.
+    RepeatFindReverse,
+
+    /// Matching bracket, as with `%`.
+    MatchPair,
+
+    /// Next sentence, as with `)`.
+    SentenceForward,
+
+    /// Previous sentence, as with `(`.
+    SentenceBackward,
+
+    /// Next paragraph, as with `}`.
+    ParagraphForward,
+
+    /// Previous paragraph, as with `{`.
+    ParagraphBackward,
+
+    /// Top line of the screen, as with `H`.
+    ScreenTop,
+
+    /// Middle line of the screen, as with `M`.
+    ScreenMiddle,
+
+    /// Bottom line of the screen, as with `L`.
+    ScreenBottom,
+
+    /// Position of a mark, as with `` `a ``, or its line, as with `'a`.
+    Mark {
+        /// Mark name.
+        name: char,
+
+        /// Whether the motion goes to the line of the mark.
+        linewise: bool,
+    },
+
+    /// Match of a pattern read from the command line, as with `/` and `?`.
+    Search(Direction),
+
+    /// Next match of the last pattern, as with `n`.
+    SearchNext,
+
+    /// Previous match of the last pattern, as with `N`.
+    SearchPrev,
+}
+
+/// Kind of text object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ObjectKind {
+    /// Word, as with `w`.
+    Word,
+
+    /// WORD, as with `W`.
+    BigWord,
+
+    /// Sentence, as with `s`.
+    Sentence,
+
+    /// Paragraph, as with `p`.
+    Paragraph,
+
+    /// Quoted string, given its quote char, as with `"`, `'` and `` ` ``.
+    Quote(char),
+
+    /// Bracket pair, given its opening bracket, as with `(`, `[`, `{` and `<`.
+    Bracket(char),
+
+    /// XML or HTML tag pair, as with `t`.
+    Tag,
+}
+
+/// Text object, which selects text for an operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Object {
+    /// Kind of object.
+    pub kind: ObjectKind,
+
+    /// Whether only the inside of the object is selected, as with `i`, rather than around it, as
+    /// with `a`.
+    pub inner: bool,
+}
+
+/// Text an operator works on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Target {
+    /// Text a motion moves over.
+    Motion(Motion),
+
+    /// Text object.
+    Object(Object),
+
+    /// Lines from the cursor line, as with a doubled operator.
+    Line,
+}
+
+/// Way insert mode is started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Insert {
+    /// Before the cursor, as with `i`.
+    Before,
+
+    /// After the cursor, as with `a`.
+    After,
+
+    /// Before the first non-blank of the line, as with `I`.
+    LineStart,
+
+    /// At the end of the line, as with `A`.
+    LineEnd,
+
+    /// On a new line below, as with `o`.
+    LineBelow,
+
+    /// On a new line above, as with `O`.
+    LineAbove,
+}
+
+/// Kind of visual mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Visual {
+    /// Charwise, as with `v`.
+    Char,
+
+    /// Linewise, as with `V`.
+    Line,
+
+    /// Blockwise, as with `^V`.
+    Block,
+}
+
+/// Position of the cursor line after scrolling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scroll {
+    /// Top of the screen, as with `zt`.
+    Top,
+
+    /// Middle of the screen, as with `zz`.
+    Middle,
+
+    /// Bottom of the screen, as with `zb`.
+    Bottom,
+}
+
+/// Action of a normal mode command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Move the cursor.
+    Move(Motion),
+
+    /// Apply an operator. `x`, `X`, `D`, `C`, `s`, `S` and `Y` are given as the operator they
+    /// abbreviate, such as `dl` for `x`.
+    Operate(Operator, Target),
+
+    /// Start insert mode.
+    Insert(Insert),
+
+    /// Put a register after the cursor, as with `p`, or before, as with `P`.
+    Put {
+        /// Whether to put before the cursor.
+        before: bool,
+    },
+
+    /// Join lines, as with `J`, or without inserting spaces, as with `gJ`.
+    Join {
+        /// Whether to insert a space between joined lines.
+        spaces: bool,
+    },
+
+    /// Replace chars with a char, as with `r`.
+    Replace(char),
+
+    /// Switch the case of chars and move past them, as with `~`.
+    ToggleCase,
+
+    /// Set a mark at the cursor, as with `m`.
+    SetMark(char),
+
+    /// Undo, as with `u`.
+    Undo,
+
+    /// Redo, as with `^R`.
+    Redo,
+
+    /// Repeat the last change, as with `.`.
+    Repeat,
+
+    /// Start visual mode.
+    Visual(Visual),
+
+    /// Start the ex command line, as with `:`.
+    CommandLine,
+
+    /// Scroll the cursor line, as with `zt`, `zz` and `zb`.
+    Scroll(Scroll),
+}
+
+/// Normal mode command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Command {
+    /// Register, as with `"x`.
+    pub register: Option<char>,
+
+    /// Count, which is the product of the counts before and after an operator.
+    pub count: Option<usize>,
+
+    /// Action.
+    pub action: Action,
+}
+
+/// State of a command after a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Status {
+    /// More keys are needed.
+    Incomplete,
+
+    /// The keys are a complete command.
+    Complete(Command),
+
+    /// The keys are not a command.
+    Invalid,
+}
+
+// Command waiting for a char argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Argument {
+    Find,
+    FindBack,
+    Till,
+    TillBack,
+    Mark { linewise: bool },
+    SetMark,
+    Replace,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Start,
+    Register,
+    G,
+    Z,
+    Argument(Argument),
+    Object { inner: bool },
+}
+
+/// Parser of normal mode commands.
+///
+/// The parser starts again after each complete or invalid command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parser {
+    keys: StdString,
+    state: State,
+    register: Option<char>,
+    count: Option<usize>,
+    operator: Option<Operator>,
+    motion_count: Option<usize>,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Parser {
+            keys: StdString::new(),
+            state: State::Start,
+            register: None,
+            count: None,
+            operator: None,
+            motion_count: None,
+        }
+    }
+}
+
+impl Parser {
+    /// Returns a parser with no keys.
+    pub fn new() -> Self {
+        Parser::default()
+    }
+
+    /// Returns the keys of the pending command, as shown by `showcmd`.
+    pub fn keys(&self) -> &str {
+        &self.keys
+    }
+
+    /// Discards the pending command.
+    pub fn reset(&mut self) {
+        *self = Parser::default();
+    }
+
+    /// Parses the next key.
+    ///
+    /// `ESCAPE` makes the pending command invalid.
+    pub fn push(&mut self, key: char) -> Status {
+        self.keys.push(key);
+        let status = if key == ESCAPE {
+            Status::Invalid
+        } else {
+            match self.state {
+                State::Start => self.start(key),
+                State::Register => {
+                    if is_register(key) {
+                        self.register = Some(key);
+                        self.state = State::Start;
+                        Status::Incomplete
+                    } else {
+                        Status::Invalid
+                    }
+                },
+                State::G => self.g(key),
+                State::Z => self.z(key),
+                State::Argument(argument) => self.argument(argument, key),
+                State::Object { inner } => match object(key) {
+                    Some(kind) => self.target(Target::Object(Object { kind: kind, inner: inner })),
+                    None => Status::Invalid,
+                },
+            }
+        };
+        if status != Status::Incomplete {
+            self.reset();
+        }
+        status
+    }
+
+    fn start(&mut self, key: char) -> Status {
+        if let Some(operator) = self.operator {
+            if key == operator.last_key() {
+                return self.target(Target::Line);
+            }
+        }
+
+        let counting = if self.operator.is_some() { self.motion_count } else { self.count };
+        if let Some(digit) = key.to_digit(10) {
+            if digit != 0 || counting.is_some() {
+                let count = counting.unwrap_or(0).checked_mul(10)
+                    .and_then(|count| count.checked_add(digit as usize));
+                return match count {
+                    Some(count) if self.operator.is_some() => {
+                        self.motion_count = Some(count);
+                        Status::Incomplete
+                    },
+                    Some(count) => {
+                        self.count = Some(count);
+                        Status::Incomplete
+                    },
+                    None => Status::Invalid,
+                };
+            }
+        }
+
+        if let Some(motion) = motion(key) {
+            return self.target(Target::Motion(motion));
+        }
+        let argument = match key {
+            'f' => Some(Argument::Find),
+            'F' => Some(Argument::FindBack),
+            't' => Some(Argument::Till),
+            'T' => Some(Argument::TillBack),
+            '\'' => Some(Argument::Mark { linewise: true }),
+            '`' => Some(Argument::Mark { linewise: false }),
+            _ => None,
+        };
+        if let Some(argument) = argument {
+            self.state = State::Argument(argument);
+            return Status::Incomplete;
+        }
+        if key == 'g' {
+            self.state = State::G;
+            return Status::Incomplete;
+        }
+
+        if self.operator.is_some() {
+            return match key {
+                'i' | 'a' => {
+                    self.state = State::Object { inner: key == 'i' };
+                    Status::Incomplete
+                },
+                _ => Status::Invalid,
+            };
+        }
+
+        let operator = match key {
+            'd' => Some(Operator::Delete),
+            'c' => Some(Operator::Change),
+            'y' => Some(Operator::Yank),
+            '<' => Some(Operator::ShiftLeft),
+            '>' => Some(Operator::ShiftRight),
+            '=' => Some(Operator::Indent),
+            _ => None,
+        };
+        if let Some(operator) = operator {
+            self.operator = Some(operator);
+            return Status::Incomplete;
+        }
+
+        let action = match key {
+            'x' => Action::Operate(Operator::Delete, Target::Motion(Motion::Right)),
+            'X' => Action::Operate(Operator::Delete, Target::Motion(Motion::Left)),
+            'D' => Action::Operate(Operator::Delete, Target::Motion(Motion::LineEnd)),
+            'C' => Action::Operate(Operator::Change, Target::Motion(Motion::LineEnd)),
+            's' => Action::Operate(Operator::Change, Target::Motion(Motion::Right)),
+            'S' => Action::Operate(Operator::Change, Target::Line),
+            'Y' => Action::Operate(Operator::Yank, Target::Line),
+            'i' => Action::Insert(Insert::Before),
+            'a' => Action::Insert(Insert::After),
+            'I' => Action::Insert(Insert::LineStart),
+            'A' => Action::Insert(Insert::LineEnd),
+            'o' => Action::Insert(Insert::LineBelow),
+            'O' => Action::Insert(Insert::LineAbove),
+            'p' => Action::Put { before: false },
+            'P' => Action::Put { before: true },
+            'J' => Action::Join { spaces: true },
+            '~' => Action::ToggleCase,
+            'u' => Action::Undo,
+            '\x12' => Action::Redo,
+            '.' => Action::Repeat,
+            'v' => Action::Visual(Visual::Char),
+            'V' => Action::Visual(Visual::Line),
+            '\x16' => Action::Visual(Visual::Block),
+            ':' => Action::CommandLine,
+            'r' => {
+                self.state = State::Argument(Argument::Replace);
+                return Status::Incomplete;
+            },
+            'm' => {
+                self.state = State::Argument(Argument::SetMark);
+                return Status::Incomplete;
+            },
+            '"' => {
+                self.state = State::Register;
+                return Status::Incomplete;
+            },
+            'z' => {
+                self.state = State::Z;
+                return Status::Incomplete;
+            },
+            _ => return Status::Invalid,
+        };
+        self.complete(action)
+    }
+
+    fn g(&mut self, key: char) -> Status {
+        self.state = State::Start;
+        let motion = match key {
+            'g' => Motion::FirstLine,
+            'e' => Motion::WordEndBack { big: false },
+            'E' => Motion::WordEndBack { big: true },
+            _ => {
+                let operator = match key {
+                    'u' => Operator::Lowercase,
+                    'U' => Operator::Uppercase,
+                    '~' => Operator::ToggleCase,
+                    'q' => Operator::Format,
+                    'J' if self.operator.is_none() => {
+                        return self.complete(Action::Join { spaces: false });
+                    },
+                    _ => return Status::Invalid,
+                };
+                return match self.operator {
+                    Some(pending) if pending == operator => self.target(Target::Line),
+                    Some(_) => Status::Invalid,
+                    None => {
+                        self.operator = Some(operator);
+                        Status::Incomplete
+                    },
+                };
+            },
+        };
+        self.target(Target::Motion(motion))
+    }
+
+    fn z(&mut self, key: char) -> Status {
+        let scroll = match key {
+            't' | '\r' => Scroll::Top,
+            'z' | '.' => Scroll::Middle,
+            'b' | '-' => Scroll::Bottom,
+            _ => return Status::Invalid,
+        };
+        self.complete(Action::Scroll(scroll))
+    }
+
+    fn argument(&mut self, argument: Argument, key: char) -> Status {
+        let motion = match argument {
+            Argument::Find => Motion::Find(key),
+            Argument::FindBack => Motion::FindBack(key),
+            Argument::Till => Motion::Till(key),
+            Argument::TillBack => Motion::TillBack(key),
+            Argument::Mark { linewise } if is_mark(key) => {
+                Motion::Mark { name: key, linewise: linewise }
+            },
+            Argument::SetMark if is_mark(key) => return self.complete(Action::SetMark(key)),
+            Argument::Replace => return self.complete(Action::Replace(key)),
+            Argument::Mark { .. } | Argument::SetMark => return Status::Invalid,
+        };
+        self.target(Target::Motion(motion))
+    }
+
+    // Completes a motion, or an operator if one is pending.
+    fn target(&mut self, target: Target) -> Status {
+        match (self.operator, target) {
+            (Some(operator), _) => self.complete(Action::Operate(operator, target)),
+            (None, Target::Motion(motion)) => self.complete(Action::Move(motion)),
+            (None, _) => Status::Invalid,
+        }
+    }
+
+    fn complete(&mut self, action: Action) -> Status {
+        let count = match (self.count, self.motion_count) {
+            (Some(count), Some(motion_count)) => match count.checked_mul(motion_count) {
+                Some(count) => Some(count),
+                None => return Status::Invalid,
+            },
+            (count, motion_count) => count.or(motion_count),
+        };
+        Status::Complete(Command {
+            register: self.register,
+            count: count,
+            action: action,
+        })
+    }
+}
+
+// Returns the motion of a key without arguments.
+fn motion(key: char) -> Option<Motion> {
+    let motion = match key {
+        'h' | '\x08' => Motion::Left,
+        'l' | ' ' => Motion::Right,
+        'k' => Motion::Up,
+        'j' => Motion::Down,
+        '+' | '\r' => Motion::NextLine,
+        '-' => Motion::PrevLine,
+        'w' => Motion::WordStart { big: false },
+        'W' => Motion::WordStart { big: true },
+        'b' => Motion::WordBack { big: false },
+        'B' => Motion::WordBack { big: true },
+        'e' => Motion::WordEnd { big: false },
+        'E' => Motion::WordEnd { big: true },
+        '0' => Motion::LineStart,
+        '^' => Motion::FirstNonBlank,
+        '$' => Motion::LineEnd,
+        '|' => Motion::Column,
+        'G' => Motion::LastLine,
+        ';' => Motion::RepeatFind,
+        ',' => Motion::RepeatFindReverse,
+        '%' => Motion::MatchPair,
+        ')' => Motion::SentenceForward,
+        '(' => Motion::SentenceBackward,
+        '}' => Motion::ParagraphForward,
+        '{' => Motion::ParagraphBackward,
+        'H' => Motion::ScreenTop,
+        'M' => Motion::ScreenMiddle,
+        'L' => Motion::ScreenBottom,
+        '/' => Motion::Search(Direction::Forward),
+        '?' => Motion::Search(Direction::Backward),
+        'n' => Motion::SearchNext,
+        'N' => Motion::SearchPrev,
+        _ => return None,
+    };
+    Some(motion)
+}
+
+// Returns the text object of a key after `i` or `a`.
+fn object(key: char) -> Option<ObjectKind> {
+    let kind = match key {
+        'w' => ObjectKind::Word,
+        'W' => ObjectKind::BigWord,
+        's' => ObjectKind::Sentence,
+        'p' => ObjectKind::Paragraph,
+        '"' | '\'' | '`' => ObjectKind::Quote(key),
+        '(' | ')' | 'b' => ObjectKind::Bracket('('),
+        '[' | ']' => ObjectKind::Bracket('['),
+        '{' | '}' | 'B' => ObjectKind::Bracket('{'),
+        '<' | '>' => ObjectKind::Bracket('<'),
+        't' => ObjectKind::Tag,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+fn is_register(key: char) -> bool {
+    key.is_ascii_alphanumeric() || "\"-_.:%/+*#".contains(key)
+}
+
+fn is_mark(key: char) -> bool {
+    key.is_ascii_alphanumeric() || "<>[]'`^.\"".contains(key)
+}
@@ -0,0 +1,446 @@
+//! Text objects.
+//!
+//! A text object selects text around the cursor for an operator, such as the word under the
+//! cursor with `iw` or the parenthesized text with `a(`. Inner objects select only the object or
+//! its inside, while around objects also select surrounding blanks or delimiters.
+
+use std::ops::Range;
+use std::string::String as StdString;
+
+use gap::String;
+use normal::{Object, ObjectKind};
+use word::{Class, Keyword};
+
+/// Text selected by a text object.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Selection {
+    /// Byte range of the selection. Linewise selections include the line break of the last line.
+    pub range: Range<usize>,
+
+    /// Whether an operator works on whole lines.
+    pub linewise: bool,
+}
+
+impl Selection {
+    fn charwise(range: Range<usize>) -> Self {
+        Selection { range: range, linewise: false }
+    }
+}
+
+/// Selects a text object at the cursor.
+///
+/// Returns `None` if there is no such object at the cursor.
+///
+/// # Panics
+///
+/// Panics if `cursor` is out of bounds.
+pub fn select(
+    text: &String,
+    cursor: usize,
+    object: Object,
+    count: usize,
+    keyword: &Keyword,
+) -> Option<Selection> {
+    let inner = object.inner;
+    match object.kind {
+        ObjectKind::Word => word(text, cursor, count, Some(keyword), inner),
+        ObjectKind::BigWord => word(text, cursor, count, None, inner),
+        ObjectKind::Sentence => sentence(text, cursor, count, inner),
+        ObjectKind::Paragraph => paragraph(text, cursor, count, inner),
+        ObjectKind::Quote(quote) => self::quote(text, cursor, count, quote, inner),
+        ObjectKind::Bracket(open) => bracket(text, cursor, count, open, inner),
+        ObjectKind::Tag => tag(text, cursor, count, inner),
+    }
+}
+
+/// Selects `count` words, or WORDs if `keyword` is `None`, as with `iw` and `aw`.
+///
+/// Inner words count runs of blanks as words. Around words include the blanks after each word,
+/// or the blanks before the first if there are none after the last. Words do not extend past the
+/// line. An empty line is selected as an empty range.
+pub fn word(
+    text: &String,
+    cursor: usize,
+    count: usize,
+    keyword: Option<&Keyword>,
+    inner: bool,
+) -> Option<Selection> {
+    let line = text.line_range(text.line_of(cursor));
+    if line.is_empty() {
+        return Some(Selection::charwise(line));
+    }
+
+    let mut units: Vec<Unit> = Vec::new();
+    let mut last = None;
+    for (index, ch) in text.slice(line.clone()).char_indices() {
+        let offset = line.start + index;
+        let class = match keyword {
+            Some(keyword) => keyword.class(ch),
+            None if ch.is_whitespace() => Class::Blank,
+            None => Class::Punctuation,
+        };
+        if last == Some(class) {
+            units.last_mut().unwrap().range.end = offset + ch.len_utf8();
+        } else {
+            units.push(Unit {
+                range: offset..(offset + ch.len_utf8()),
+                blank: class == Class::Blank,
+            });
+            last = Some(class);
+        }
+    }
+    let index = units.iter().position(|unit| unit.contains(cursor))?;
+    Some(Selection::charwise(select_units(&units, index, count, inner)))
+}
+
+/// Selects `count` sentences, as with `is` and `as`.
+///
+/// Inner sentences count the blanks between sentences as sentences. Around sentences include the
+/// blanks after each sentence, or the blanks before the first if there are none after the last.
+pub fn sentence(text: &String, cursor: usize, count: usize, inner: bool) -> Option<Selection> {
+    // Sentences end at empty lines, so start at the paragraph.
+    let mut line = text.line_of(cursor);
+    while line > 0 && !text.line_range(line - 1).is_empty() {
+        line -= 1;
+    }
+    let start = text.line_range(line).start;
+
+    let mut units: Vec<Unit> = Vec::new();
+    let mut index = None;
+    for span in text.slice(start..).sentences() {
+        let span = (start + span.start)..(start + span.end);
+        if span.is_empty() {
+            break;
+        }
+        if let Some(end) = units.last().map(|unit| unit.range.end) {
+            units.push(Unit { range: end..span.start, blank: true });
+        }
+        units.push(Unit { range: span, blank: false });
+        if index.is_none() {
+            index = units.iter().position(|unit| unit.contains(cursor));
+        }
+        if index.map_or(false, |index| units.len() > index + 2 * count + 1) {
+            break;
+        }
+    }
+    let index = index?;
+    Some(Selection::charwise(select_units(&units, index, count, inner)))
+}
+
+/// Selects `count` paragraphs, as with `ip` and `ap`. Linewise.
+///
+/// Paragraphs are separated by lines of only blanks. Inner paragraphs count runs of such lines
+/// as paragraphs. Around paragraphs include the blank lines after each paragraph, or the blank
+/// lines before the first if there are none after the last.
+pub fn paragraph(text: &String, cursor: usize, count: usize, inner: bool) -> Option<Selection> {
+    let is_blank = |line: usize| text.slice(text.line_range(line)).chars().all(char::is_whitespace);
+    let last = text.line_count() - 1;
+
+    // Runs of blank or other lines, from the run before the cursor.
+    let cursor_line = text.line_of(cursor);
+    let mut line = cursor_line;
+    let mut runs = 0;
+    while line > 0 && runs < 2 {
+        if is_blank(line - 1) != is_blank(line) {
+            runs += 1;
+        }
+        if runs < 2 {
+            line -= 1;
+        }
+    }
+
+    let mut units: Vec<Unit> = Vec::new();
+    let mut index = None;
+    while line <= last {
+        let (first, blank) = (line, is_blank(line));
+        while line <= last && is_blank(line) == blank {
+            line += 1;
+        }
+        let start = text.line_range(first).start;
+        let end = if line <= last { text.line_range(line).start } else { text.len() };
+        units.push(Unit { range: start..end, blank: blank });
+        if first <= cursor_line && cursor_line < line {
+            index = Some(units.len() - 1);
+        }
+        if index.map_or(false, |index| units.len() > index + 2 * count + 1) {
+            break;
+        }
+    }
+    let index = index?;
+    Some(Selection {
+        range: select_units(&units, index, count, inner),
+        linewise: true,
+    })
+}
+
+/// Selects a string quoted by `quote` in the line, as with `i"` and `a"`.
+///
+/// As in vi, the first quote in the line starts a string, and a quote escaped by a backslash is
+/// skipped. If the cursor is not in a string, the next string in the line is selected. Inner
+/// strings exclude the quotes, unless `count` is above one. Around strings include the blanks
+/// after the closing quote, or the blanks before the opening quote if there are none.
+pub fn quote(
+    text: &String,
+    cursor: usize,
+    count: usize,
+    quote: char,
+    inner: bool,
+) -> Option<Selection> {
+    let line = text.line_range(text.line_of(cursor));
+    let mut quotes = Vec::new();
+    let mut escaped = false;
+    for (index, ch) in text.slice(line.clone()).char_indices() {
+        if escaped {
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if ch == quote {
+            quotes.push(line.start + index);
+        }
+    }
+
+    let pairs: Vec<_> = quotes.chunks(2)
+        .filter(|pair| pair.len() == 2)
+        .map(|pair| (pair[0], pair[1]))
+        .collect();
+    let &(open, close) = pairs.iter().find(|&&(open, close)| open <= cursor && cursor <= close)
+        .or_else(|| pairs.iter().find(|&&(open, _)| open > cursor))?;
+    let close = close + quote.len_utf8();
+
+    if inner {
+        return Some(Selection::charwise(if count > 1 {
+            open..close
+        } else {
+            (open + quote.len_utf8())..(close - quote.len_utf8())
+        }));
+    }
+    // Blanks are one byte each.
+    let is_blank = |ch: &char| *ch == ' ' || *ch == '\t';
+    let after = text.slice(close..line.end).chars().take_while(is_blank).count();
+    if after > 0 {
+        return Some(Selection::charwise(open..(close + after)));
+    }
+    let before = text.slice(line.start..open).chars().rev().take_while(is_blank).count();
+    Some(Selection::charwise((open - before)..close))
+}
+
+/// Selects the `count`th bracket pair around the cursor, given its opening bracket `(`, `[`,
+/// `{` or `<`, as with `i(` and `a(`.
+///
+/// A bracket under the cursor belongs to the innermost pair. Inner pairs exclude the brackets.
+/// As in vi, if the opening bracket ends its line and the closing bracket starts its line after
+/// blanks, the inside excludes that line break and those blanks, so that whole lines are
+/// selected.
+///
+/// # Panics
+///
+/// Panics if `open` is not an opening bracket.
+pub fn bracket(
+    text: &String,
+    cursor: usize,
+    count: usize,
+    open: char,
+    inner: bool,
+) -> Option<Selection> {
+    let close = match open {
+        '(' => ')',
+        '[' => ']',
+        '{' => '}',
+        '<' => '>',
+        _ => panic!("not an opening bracket: {:?}", open),
+    };
+
+    // Find the opening bracket, counting the bracket under the cursor as inside the pair.
+    let mut start = if text.slice(cursor..).chars().next() == Some(open) {
+        cursor + open.len_utf8()
+    } else {
+        cursor
+    };
+    for _ in 0..count.max(1) {
+        let mut depth = 0usize;
+        let (index, _) = text.slice(..start).char_indices().rev().find(|&(_, ch)| {
+            if ch == close {
+                depth += 1;
+            } else if ch == open {
+                if depth == 0 {
+                    return true;
+                }
+                depth -= 1;
+            }
+            false
+        })?;
+        start = index;
+    }
+
+    let mut depth = 0usize;
+    let after = start + open.len_utf8();
+    let (index, _) = text.slice(after..).char_indices().find(|&(_, ch)| {
+        if ch == open {
+            depth += 1;
+        } else if ch == close {
+            if depth == 0 {
+                return true;
+            }
+            depth -= 1;
+        }
+        false
+    })?;
+    let end = after + index;
+
+    if !inner {
+        return Some(Selection::charwise(start..(end + close.len_utf8())));
+    }
+    let mut range = after..end;
+    let end_line = text.line_range(text.line_of(end)).start;
+    let blanks = text.slice(end_line..end).chars().all(|ch| ch == ' ' || ch == '\t');
+    if text.slice(after..).chars().next() == Some('\n') && blanks && end_line > after {
+        range = (after + 1)..end_line;
+    }
+    Some(Selection::charwise(range))
+}
+
+/// Selects the `count`th XML or HTML tag pair around the cursor, as with `it` and `at`.
+///
+/// A tag under the cursor belongs to its pair. Self-closing tags and unclosed opening tags are
+/// skipped. Inner pairs exclude the tags.
+pub fn tag(text: &String, cursor: usize, count: usize, inner: bool) -> Option<Selection> {
+    let mut pairs: Vec<_> = tag_pairs(text).into_iter()
+        .filter(|&(ref open, ref close)| open.start <= cursor && cursor < close.end)
+        .collect();
+    pairs.sort_by(|a, b| b.0.start.cmp(&a.0.start));
+    let (open, close) = pairs.into_iter().nth(count.saturating_sub(1))?;
+    Some(Selection::charwise(if inner { open.end..close.start } else { open.start..close.end }))
+}
+
+// Run of text which is either blank or not, such as a word or the blanks after it.
+#[derive(Debug, Clone)]
+struct Unit {
+    range: Range<usize>,
+    blank: bool,
+}
+
+impl Unit {
+    fn contains(&self, offset: usize) -> bool {
+        self.range.start <= offset && offset < self.range.end
+    }
+}
+
+// Selects `count` units from the one at `index`.
+//
+// Inner objects count blank units. Around objects count other units with the blank unit after
+// each, or the blank unit before the first if there is none after the last. Starting on a blank
+// unit, around objects select it with the unit after it.
+fn select_units(units: &[Unit], index: usize, count: usize, inner: bool) -> Range<usize> {
+    let count = count.max(1);
+    if inner {
+        let last = (index + count - 1).min(units.len() - 1);
+        return units[index].range.start..units[last].range.end;
+    }
+
+    let is_blank = |index: usize| units.get(index).map(|unit| unit.blank);
+    let mut start = index;
+    let mut end = index;
+    if units[index].blank {
+        for n in 0..count {
+            if n > 0 && is_blank(end + 1) == Some(true) {
+                end += 1;
+            }
+            if is_blank(end + 1) != Some(false) {
+                break;
+            }
+            end += 1;
+        }
+    } else {
+        for n in 0..count {
+            if n > 0 {
+                if is_blank(end + 1) != Some(false) {
+                    break;
+                }
+                end += 1;
+            }
+            if is_blank(end + 1) == Some(true) {
+                end += 1;
+            }
+        }
+        if !units[end].blank && index > 0 && units[index - 1].blank {
+            start = index - 1;
+        }
+    }
+    units[start].range.start..units[end].range.end
+}
+
+// Tag being scanned.
+struct Tag {
+    start: usize,
+    closing: bool,
+    name: StdString,
+    named: bool,
+    last: char,
+}
+
+enum Step {
+    Continue,
+    Done,
+    Abort,
+}
+
+impl Tag {
+    fn push(&mut self, ch: char) -> Step {
+        if ch == '>' {
+            return if self.name.is_empty() || self.last == '/' { Step::Abort } else { Step::Done };
+        }
+        if !self.named {
+            if ch == '/' && self.name.is_empty() && !self.closing {
+                self.closing = true;
+            } else if ch.is_alphanumeric() || "-_:.".contains(ch) {
+                self.name.push(ch);
+            } else if ch.is_whitespace() && !self.name.is_empty() {
+                self.named = true;
+            } else {
+                return Step::Abort;
+            }
+        }
+        self.last = ch;
+        Step::Continue
+    }
+}
+
+// Returns the ranges of paired opening and closing tags.
+fn tag_pairs(text: &String) -> Vec<(Range<usize>, Range<usize>)> {
+    let mut open: Vec<(StdString, Range<usize>)> = Vec::new();
+    let mut pairs = Vec::new();
+    let mut scanning: Option<Tag> = None;
+    for (offset, ch) in text.as_str().char_indices() {
+        if ch == '<' {
+            scanning = Some(Tag {
+                start: offset,
+                closing: false,
+                name: StdString::new(),
+                named: false,
+                last: ch,
+            });
+            continue;
+        }
+        let step = match scanning {
+            Some(ref mut tag) => tag.push(ch),
+            None => continue,
+        };
+        match step {
+            Step::Continue => continue,
+            Step::Abort => scanning = None,
+            Step::Done => {
+                let tag = scanning.take().unwrap();
+                let range = tag.start..(offset + 1);
+                if !tag.closing {
+                    open.push((tag.name, range));
+                } else if let Some(index) = open.iter().rposition(|open| open.0 == tag.name) {
+                    // Unclosed tags inside the pair, such as `<br>`, are dropped.
+                    let (_, start) = open.swap_remove(index);
+                    open.truncate(index);
+                    pairs.push((start, range));
+                }
+            },
+        }
+    }
+    pairs
+}
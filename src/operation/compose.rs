@@ -0,0 +1,50 @@
+use std::cmp;
+
+use super::{Data, Operation};
+
+/// Composes two operations applied in sequence into a single equivalent operation.
+///
+/// Returns `None` if the destination of `b` neither overlaps nor touches the data written by
+/// `a`, since the result would not be a single splice.
+///
+/// The inverse of the composed operation is the composition of the revert operations of `b` and
+/// `a`, in that order.
+pub fn compose<T: Data>(a: &Operation<T>, b: &Operation<T>) -> Option<Operation<T>> {
+    let written = a.dest.start..(a.dest.start + a.src.len());
+    if b.dest.end < written.start || b.dest.start > written.end {
+        return None;
+    }
+
+    let start = cmp::min(a.dest.start, b.dest.start);
+    let end = if b.dest.end > written.end {
+        b.dest.end - written.end + a.dest.end
+    } else {
+        a.dest.end
+    };
+
+    let mut src = a.src.clone();
+    let replace_start = cmp::max(b.dest.start, written.start) - written.start;
+    let replace_end = cmp::min(b.dest.end, written.end) - written.start;
+    src.splice(replace_start..replace_end, &b.src);
+
+    Some(Operation {
+        dest: start..end,
+        src: src,
+    })
+}
+
+/// Squashes a sequence of operations by composing adjacent operations where possible.
+///
+/// Applying the result has the same effect as applying `operations` in sequence. Typing or
+/// deleting a run of characters squashes to a single operation.
+pub fn squash<T: Data>(operations: &[Operation<T>]) -> Vec<Operation<T>> {
+    let mut squashed: Vec<Operation<T>> = Vec::with_capacity(operations.len());
+    for operation in operations {
+        let composed = squashed.last().and_then(|last| compose(last, operation));
+        match composed {
+            Some(composed) => *squashed.last_mut().unwrap() = composed,
+            None => squashed.push(operation.clone()),
+        }
+    }
+    squashed
+}
@@ -0,0 +1,133 @@
+//! Splice operations.
+
+use std::borrow::Borrow;
+use std::ops::Range;
+use std::string::String as StdString;
+
+use range;
+use text::{Chunk, TextBuffer};
+
+mod compose;
+mod transform;
+
+pub use self::compose::{compose, squash};
+pub use self::transform::{transform, transform_all};
+
+/// Splice operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operation<T> {
+    /// Destination range.
+    pub dest: Range<usize>,
+
+    /// Source data.
+    pub src: T,
+}
+
+/// Owned source data of an operation.
+pub trait Data: Clone + Default {
+    /// Returns the length in bytes.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if the data is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Replaces a range of the data.
+    fn splice(&mut self, dest: Range<usize>, src: &Self);
+}
+
+impl Data for Vec<u8> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn splice(&mut self, dest: Range<usize>, src: &Self) {
+        self.splice(dest, src.iter().cloned());
+    }
+}
+
+impl Data for StdString {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn splice(&mut self, dest: Range<usize>, src: &Self) {
+        self.replace_range(dest, src);
+    }
+}
+
+/// Types which can have splice operations applied.
+pub trait Operate {
+    /// Simple owned data container.
+    type Owned;
+
+    /// Applies a splice operation, returning the corresponding revert operation.
+    fn apply(&mut self, operation: &Operation<Self::Owned>) -> Operation<Self::Owned>;
+
+    /// Applies a batch of non-overlapping splice operations, returning the batch of revert
+    /// operations.
+    ///
+    /// Destinations in the batch are all relative to the data before any operation is applied,
+    /// and may be given in any order. Empty destinations at the same position are applied in the
+    /// order given. The revert batch is in order of position and its destinations are relative to
+    /// the result, so applying it as a batch restores the original data. Applying it in reverse
+    /// order one operation at a time also restores the original data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any destinations overlap.
+    fn apply_many(
+        &mut self,
+        operations: &[Operation<Self::Owned>],
+    ) -> Vec<Operation<Self::Owned>> where Self::Owned: Data {
+        let dests: Vec<_> = operations.iter().map(|op| op.dest.clone()).collect();
+        let order = range::sort_disjoint(&dests);
+
+        // Apply from the end so that destinations remain valid.
+        let mut reverts = vec![None; operations.len()];
+        for &index in order.iter().rev() {
+            reverts[index] = Some(self.apply(&operations[index]));
+        }
+
+        let mut shift = 0isize;
+        let mut sorted = Vec::with_capacity(operations.len());
+        for index in order {
+            let mut revert = reverts[index].take().unwrap();
+            let start = (revert.dest.start as isize + shift) as usize;
+            revert.dest = start..(start + revert.dest.len());
+            shift += operations[index].src.len() as isize - operations[index].dest.len() as isize;
+            sorted.push(revert);
+        }
+        sorted
+    }
+}
+
+/// Implemented once for all text buffers.
+impl<T: TextBuffer> Operate for T {
+    type Owned = <T::Unit as Chunk>::Owned;
+
+    fn apply(&mut self, operation: &Operation<Self::Owned>) -> Operation<Self::Owned> {
+        let src = self.chunks(operation.dest.clone()).concat();
+        let dest = self.splice(operation.dest.clone(), operation.src.borrow());
+        Operation {
+            dest: dest,
+            src: src,
+        }
+    }
+
+    fn apply_many(&mut self, operations: &[Operation<Self::Owned>]) -> Vec<Operation<Self::Owned>> {
+        let dests: Vec<_> = operations.iter().map(|op| op.dest.clone()).collect();
+        let order = range::sort_disjoint(&dests);
+        let srcs: Vec<_> = order.iter().map(|&i| self.chunks(dests[i].clone()).concat()).collect();
+
+        let splices: Vec<_> = operations.iter()
+            .map(|op| (op.dest.clone(), op.src.borrow()))
+            .collect();
+        let written = self.splice_many(&splices);
+        order.into_iter()
+            .zip(srcs)
+            .map(|(i, src)| Operation { dest: written[i].clone(), src: src })
+            .collect()
+    }
+}
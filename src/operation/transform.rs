@@ -0,0 +1,85 @@
+use std::cmp;
+
+use super::{Data, Operation};
+
+/// Transforms concurrent operations.
+///
+/// Given operations `a` and `b` which apply to the same data, returns `(a', b')` such that
+/// applying `a` then `b'` has the same result as applying `b` then `a'`. Two replicas which each
+/// apply one operation locally and the transformed other operation remotely converge, provided
+/// both call `transform` with the same argument order.
+///
+/// The source of the operation with the lower destination start is placed first. Where both
+/// starts are equal, the operation with the shorter destination is placed first, and where both
+/// destinations are equal, the source of `a` is placed first.
+///
+/// If one destination starts strictly before and ends strictly after the other, the inner
+/// operation is discarded, since its source would be placed inside data deleted by the outer
+/// operation. Its transformed form is an empty operation.
+pub fn transform<T: Data>(a: &Operation<T>, b: &Operation<T>) -> (Operation<T>, Operation<T>) {
+    let a_first = a.dest.start < b.dest.start
+        || (a.dest.start == b.dest.start && a.dest.end <= b.dest.end);
+    if a_first {
+        transform_ordered(a, b)
+    } else {
+        let (b, a) = transform_ordered(b, a);
+        (a, b)
+    }
+}
+
+/// Transforms concurrent sequences of operations.
+///
+/// Given sequences `a` and `b` which apply to the same data, returns `(a', b')` such that
+/// applying `a` then `b'` has the same result as applying `b` then `a'`. Operations in `a` are
+/// placed first as in `transform`.
+pub fn transform_all<T: Data>(
+    a: &[Operation<T>],
+    b: &[Operation<T>],
+) -> (Vec<Operation<T>>, Vec<Operation<T>>) {
+    let mut a_prime = Vec::with_capacity(a.len());
+    let mut b_prime = b.to_vec();
+    for operation in a {
+        let mut operation = operation.clone();
+        for other in &mut b_prime {
+            let (op, ot) = transform(&operation, other);
+            operation = op;
+            *other = ot;
+        }
+        a_prime.push(operation);
+    }
+    (a_prime, b_prime)
+}
+
+// Transforms operations where the source of `first` is placed before the source of `second`.
+fn transform_ordered<T: Data>(
+    first: &Operation<T>,
+    second: &Operation<T>,
+) -> (Operation<T>, Operation<T>) {
+    let first_delta = first.src.len() as isize - first.dest.len() as isize;
+    let second_delta = second.src.len() as isize - second.dest.len() as isize;
+    let shift = |index: usize, delta: isize| (index as isize + delta) as usize;
+
+    if first.dest.end > second.dest.end {
+        let end = first.dest.start + first.src.len();
+        let first = Operation {
+            dest: first.dest.start..shift(first.dest.end, second_delta),
+            src: first.src.clone(),
+        };
+        let second = Operation {
+            dest: end..end,
+            src: T::default(),
+        };
+        return (first, second);
+    }
+
+    let first_prime = Operation {
+        dest: first.dest.start..cmp::min(first.dest.end, second.dest.start),
+        src: first.src.clone(),
+    };
+    let second_prime = Operation {
+        dest: shift(cmp::max(second.dest.start, first.dest.end), first_delta)
+            ..shift(second.dest.end, first_delta),
+        src: second.src.clone(),
+    };
+    (first_prime, second_prime)
+}
@@ -0,0 +1,698 @@
+//! Operators.
+//!
+//! An operator works on a region of the text, such as the text a motion moves over or a text
+//! object selects, and returns its changes as a batch of operations relative to the text before
+//! any is applied. Applying the batch with `Operate::apply_many` returns the batch which reverts
+//! it, so an operator is undone as a single transaction.
+
+use std::cmp;
+use std::iter;
+use std::ops::Range;
+use std::string::String as StdString;
+
+use block::{Block, Insertion};
+use gap::{Str, String};
+use motion::{self, Target};
+use normal::Operator;
+use object::Selection;
+use operation::Operation;
+use register::{Kind, Value};
+use width;
+
+/// Text an operator works on.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Region {
+    /// Byte range of chars.
+    Chars(Range<usize>),
+
+    /// Range of whole lines.
+    Lines(Range<usize>),
+
+    /// Block of display columns.
+    Block(Block),
+}
+
+impl Region {
+    /// Returns the region between the cursor and the target of a motion.
+    ///
+    /// As in vi, an exclusive motion which ends at the start of a later line ends at the end of
+    /// the line before instead, and works on whole lines if it also starts at or before the first
+    /// non-blank of its line. An inclusive motion never includes a line break.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cursor` or the target is out of bounds.
+    pub fn from_motion(text: &String, cursor: usize, target: Target) -> Self {
+        let (start, end) = (cmp::min(cursor, target.offset), cmp::max(cursor, target.offset));
+        let (first, last) = (text.line_of(start), text.line_of(end));
+        if target.linewise {
+            return Region::Lines(first..(last + 1));
+        }
+        if target.inclusive {
+            let end = cmp::min(text.next_grapheme_boundary(end), text.line_range(last).end);
+            return Region::Chars(start..end);
+        }
+        if last > first && end == text.line_range(last).start {
+            if start <= first_non_blank(text, first) {
+                return Region::Lines(first..last);
+            }
+            return Region::Chars(start..text.line_range(last - 1).end);
+        }
+        Region::Chars(start..end)
+    }
+
+    /// Returns the region of a text object selection.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the selection is out of bounds.
+    pub fn from_selection(text: &String, selection: &Selection) -> Self {
+        let range = selection.range.clone();
+        if !selection.linewise {
+            return Region::Chars(range);
+        }
+        let last = if range.end > range.start { range.end - 1 } else { range.end };
+        Region::Lines(text.line_of(range.start)..(text.line_of(last) + 1))
+    }
+
+    /// Returns the range of lines the region is in.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the region is out of bounds.
+    pub fn lines(&self, text: &String) -> Range<usize> {
+        match *self {
+            Region::Chars(ref range) => {
+                let last = if range.end > range.start { range.end - 1 } else { range.end };
+                text.line_of(range.start)..(text.line_of(last) + 1)
+            },
+            Region::Lines(ref lines) => lines.clone(),
+            Region::Block(ref block) => block.lines.clone(),
+        }
+    }
+}
+
+/// Options operators depend on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Options {
+    /// Width of a tab, like the `tabstop` option.
+    pub tabstop: usize,
+
+    /// Width of a shift, like the `shiftwidth` option. Zero uses the tabstop.
+    pub shiftwidth: usize,
+
+    /// Indent with spaces only, like the `expandtab` option.
+    pub expandtab: bool,
+
+    /// Keep the indent of the first line in changed and formatted lines, like the `autoindent`
+    /// option.
+    pub autoindent: bool,
+
+    /// Width to format lines to, like the `textwidth` option. Zero formats to 79 columns.
+    pub textwidth: usize,
+}
+
+impl Options {
+    fn shiftwidth(&self) -> usize {
+        if self.shiftwidth == 0 { self.tabstop } else { self.shiftwidth }
+    }
+
+    fn textwidth(&self) -> usize {
+        if self.textwidth == 0 { 79 } else { self.textwidth }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            tabstop: 8,
+            shiftwidth: 8,
+            expandtab: false,
+            autoindent: false,
+            textwidth: 0,
+        }
+    }
+}
+
+/// Case change of `gu`, `gU` and `g~`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Case {
+    /// Make lowercase.
+    Lower,
+
+    /// Make uppercase.
+    Upper,
+
+    /// Switch case.
+    Toggle,
+}
+
+/// Result of an operator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    /// Changes, as a batch of operations relative to the text before any is applied.
+    pub operations: Vec<Operation<StdString>>,
+
+    /// Text yanked or deleted, for a register.
+    pub yanked: Option<Value>,
+
+    /// Offset vi moves the cursor to, relative to the text after the changes. After a change,
+    /// this is where insert mode starts.
+    pub cursor: usize,
+}
+
+impl Edit {
+    fn new(operations: Vec<Operation<StdString>>, cursor: usize) -> Self {
+        Edit {
+            operations: operations,
+            yanked: None,
+            cursor: cursor,
+        }
+    }
+}
+
+/// Applies an operator to a region of the text, with the cursor at `cursor`.
+///
+/// Shifts are by one shift width.
+///
+/// # Panics
+///
+/// Panics if `cursor` or the region is out of bounds.
+pub fn operate(
+    text: &String,
+    cursor: usize,
+    operator: Operator,
+    region: &Region,
+    options: &Options,
+) -> Edit {
+    match operator {
+        Operator::Delete => delete(text, region, options),
+        Operator::Change => change(text, region, options),
+        Operator::Yank => yank(text, cursor, region, options),
+        Operator::ShiftLeft => shift(text, region, -1, options),
+        Operator::ShiftRight => shift(text, region, 1, options),
+        Operator::Lowercase => change_case(text, cursor, region, Case::Lower, options),
+        Operator::Uppercase => change_case(text, cursor, region, Case::Upper, options),
+        Operator::ToggleCase => change_case(text, cursor, region, Case::Toggle, options),
+        Operator::Indent => indent(text, region, options),
+        Operator::Format => format(text, region, options),
+    }
+}
+
+/// Yanks a region, as with `y`.
+///
+/// The cursor moves to the start of the region. After yanking lines, it moves to the first line
+/// and keeps its column.
+///
+/// # Panics
+///
+/// Panics if `cursor` or the region is out of bounds.
+pub fn yank(text: &String, cursor: usize, region: &Region, options: &Options) -> Edit {
+    let mut edit = Edit::new(Vec::new(), start_cursor(text, cursor, region, options));
+    edit.yanked = Some(yanked(text, region, options.tabstop));
+    edit
+}
+
+/// Deletes a region, as with `d`.
+///
+/// As in vi, deleting chars across lines deletes whole lines if there are only blanks before the
+/// region in its first line and after it in its last line. Deleting part of a wide char, such as
+/// a tab, at the edge of a block leaves spaces for the part outside the block. The cursor moves to
+/// the start of the region, or to the first non-blank of the line after deleted lines.
+///
+/// # Panics
+///
+/// Panics if the region is out of bounds.
+pub fn delete(text: &String, region: &Region, options: &Options) -> Edit {
+    if let Region::Chars(ref range) = *region {
+        let lines = region.lines(text);
+        let (first, last) = (lines.start, lines.end - 1);
+        let before = text.line_range(first).start..range.start;
+        let end = text.line_range(last).end;
+        let after = cmp::min(range.end, end)..end;
+        if last > first && is_blank(text.slice(before)) && is_blank(text.slice(after)) {
+            return delete(text, &Region::Lines(first..(last + 1)), options);
+        }
+    }
+
+    let mut edit = match *region {
+        Region::Lines(ref lines) => {
+            let dest = whole_lines(text, lines.clone());
+            let cursor = if lines.end < text.line_count() {
+                first_non_blank(text, lines.end) - dest.len()
+            } else if lines.start > 0 {
+                first_non_blank(text, lines.start - 1)
+            } else {
+                0
+            };
+            Edit::new(remove(dest), cursor)
+        },
+        _ => remove_region(text, region, options),
+    };
+    edit.yanked = Some(yanked(text, region, options.tabstop));
+    edit
+}
+
+/// Deletes a region to start insert mode, as with `c`.
+///
+/// Changing lines leaves one empty line, which keeps the indent of the first line if `autoindent`
+/// is set. The cursor moves to the start of the region.
+///
+/// # Panics
+///
+/// Panics if the region is out of bounds.
+pub fn change(text: &String, region: &Region, options: &Options) -> Edit {
+    let mut edit = match *region {
+        Region::Lines(ref lines) => {
+            let start = text.line_range(lines.start).start;
+            let end = text.line_range(lines.end - 1).end;
+            let indent = if options.autoindent {
+                text.slice(start..first_non_blank(text, lines.start)).into()
+            } else {
+                StdString::new()
+            };
+            let cursor = start + indent.len();
+            Edit::new(vec![Operation { dest: start..end, src: indent }], cursor)
+        },
+        _ => remove_region(text, region, options),
+    };
+    edit.yanked = Some(yanked(text, region, options.tabstop));
+    edit
+}
+
+/// Shifts the lines of a region by `amount` shift widths, or left if negative, as with `>` and
+/// `<`.
+///
+/// Indents are made of tabs and spaces, or only of spaces if `expandtab` is set, and empty lines
+/// are not shifted. In a block, the blanks at the start of the block are shifted instead, and
+/// lines which end before the block are not shifted. The cursor moves to the first non-blank of
+/// the first line, or to the start of a block.
+///
+/// # Panics
+///
+/// Panics if the region is out of bounds.
+pub fn shift(text: &String, region: &Region, amount: isize, options: &Options) -> Edit {
+    let tabstop = options.tabstop;
+    let by = amount.abs() as usize * options.shiftwidth();
+    let mut operations = Vec::new();
+
+    if let Region::Block(ref block) = *region {
+        let cuts = block.cuts(text, tabstop);
+        for cut in &cuts {
+            if cut.range.is_empty() {
+                continue;
+            }
+            let line_end = text.line_range(text.line_of(cut.range.start)).end;
+            let blanks: usize = text.slice(cut.range.start..line_end).chars()
+                .take_while(|&ch| ch == ' ' || ch == '\t')
+                .map(char::len_utf8)
+                .sum();
+            let blanks = cut.range.start..(cut.range.start + blanks);
+            let end = text.virtcol(blanks.end, tabstop);
+            let end = if amount >= 0 {
+                end + by
+            } else {
+                cmp::max(end.saturating_sub(by), cmp::min(cmp::max(cut.col, block.cols.start), end))
+            };
+            replace(text, &mut operations, blanks, whitespace(cut.col, end, options));
+        }
+        return Edit::new(operations, cuts[0].range.start);
+    }
+
+    let lines = region.lines(text);
+    let mut cursor = first_non_blank(text, lines.start);
+    for line in lines.clone() {
+        let range = text.line_range(line);
+        if range.is_empty() {
+            continue;
+        }
+        let blanks = range.start..first_non_blank(text, line);
+        let indent = text.virtcol(blanks.end, tabstop);
+        let indent = if amount >= 0 { indent + by } else { indent.saturating_sub(by) };
+        let src = whitespace(0, indent, options);
+        if line == lines.start {
+            cursor = range.start + src.len();
+        }
+        replace(text, &mut operations, blanks, src);
+    }
+    Edit::new(operations, cursor)
+}
+
+/// Changes the case of a region, as with `gu`, `gU` and `g~`.
+///
+/// The cursor moves to the start of the region. After changing lines, it moves to the first line
+/// and keeps its column.
+///
+/// # Panics
+///
+/// Panics if `cursor` or the region is out of bounds.
+pub fn change_case(
+    text: &String,
+    cursor: usize,
+    region: &Region,
+    case: Case,
+    options: &Options,
+) -> Edit {
+    let ranges = match *region {
+        Region::Chars(ref range) => vec![range.clone()],
+        Region::Lines(ref lines) => {
+            vec![text.line_range(lines.start).start..text.line_range(lines.end - 1).end]
+        },
+        Region::Block(ref block) => {
+            block.cuts(text, options.tabstop).into_iter().map(|cut| cut.range).collect()
+        },
+    };
+    let mut operations = Vec::new();
+    for range in ranges {
+        let src = text.slice(range.clone()).chars()
+            .flat_map(|ch| convert_case(ch, case))
+            .collect();
+        replace(text, &mut operations, range, src);
+    }
+    Edit::new(operations, start_cursor(text, cursor, region, options))
+}
+
+/// Indents the lines of a region like the last non-blank line before them, as with `=` when
+/// neither an indent program nor an indent expression is set.
+///
+/// Lines of only blanks are emptied. The cursor moves to the first non-blank of the first line.
+///
+/// # Panics
+///
+/// Panics if the region is out of bounds.
+pub fn indent(text: &String, region: &Region, options: &Options) -> Edit {
+    let lines = region.lines(text);
+    let width = (0..lines.start).rev()
+        .find(|&line| !is_blank(text.slice(text.line_range(line))))
+        .map_or(0, |line| text.virtcol(first_non_blank(text, line), options.tabstop));
+    let indent = whitespace(0, width, options);
+
+    let mut operations = Vec::new();
+    let mut cursor = 0;
+    for line in lines.clone() {
+        let range = text.line_range(line);
+        let first = first_non_blank(text, line);
+        let (dest, src) = if first == range.end {
+            (range, StdString::new())
+        } else {
+            (range.start..first, indent.clone())
+        };
+        if line == lines.start {
+            cursor = dest.start + src.len();
+        }
+        replace(text, &mut operations, dest, src);
+    }
+    Edit::new(operations, cursor)
+}
+
+/// Formats the lines of a region to the text width, as with `gq`.
+///
+/// Each paragraph of lines which are not blank is filled with as many words as fit in a line,
+/// separated by single spaces. The first line of a paragraph keeps its indent, which the other
+/// lines get if `autoindent` is set. Words wider than the text width are put on lines of their
+/// own. The cursor moves to the first non-blank of the last line formatted.
+///
+/// # Panics
+///
+/// Panics if the region is out of bounds.
+pub fn format(text: &String, region: &Region, options: &Options) -> Edit {
+    let lines = region.lines(text);
+    let mut operations = Vec::new();
+    let mut cursor = 0;
+    // Difference between offsets after and before the paragraphs formatted so far.
+    let mut shift = 0isize;
+
+    let mut line = lines.start;
+    while line < lines.end {
+        if is_blank(text.slice(text.line_range(line))) {
+            cursor = (first_non_blank(text, line) as isize + shift) as usize;
+            line += 1;
+            continue;
+        }
+        let end = (line..lines.end)
+            .find(|&line| is_blank(text.slice(text.line_range(line))))
+            .unwrap_or(lines.end);
+        let dest = text.line_range(line).start..text.line_range(end - 1).end;
+        let first = first_non_blank(text, line);
+        let indent: StdString = text.slice(dest.start..first).into();
+        let indent_width = text.virtcol(first, options.tabstop);
+        let (next_indent, next_width) = if options.autoindent {
+            (&indent[..], indent_width)
+        } else {
+            ("", 0)
+        };
+
+        let old: StdString = text.slice(dest.clone()).into();
+        let mut src = indent.clone();
+        let mut col = indent_width;
+        let mut last_first = indent.len();
+        for (index, word) in old.split_whitespace().enumerate() {
+            let width = str_width(word, col + 1, options.tabstop);
+            if index > 0 && col + 1 + width > options.textwidth() {
+                src.push('\n');
+                src.push_str(next_indent);
+                last_first = src.len();
+                col = next_width;
+            } else if index > 0 {
+                src.push(' ');
+                col += 1;
+            }
+            src.push_str(word);
+            col += width;
+        }
+
+        cursor = (dest.start as isize + shift) as usize + last_first;
+        shift += src.len() as isize - dest.len() as isize;
+        replace(text, &mut operations, dest, src);
+        line = end;
+    }
+    Edit::new(operations, cursor)
+}
+
+/// Puts the content of a register `count` times after the cursor, or before it if `before` is
+/// set, as with `p` and `P`.
+///
+/// Charwise text is put after or before the char at the cursor, linewise text after or before
+/// its line, and blockwise text after or before its column in the lines from the cursor down. A
+/// block adds lines at the end of the text if needed, pads short lines with spaces, and replaces
+/// a wide char its column splits with spaces. Each of its lines is padded with spaces to the
+/// width of the block, unless nothing follows it. The cursor moves to the last char of charwise
+/// text within a line, to the first non-blank of linewise text, or else to the start of the text
+/// put.
+///
+/// # Panics
+///
+/// Panics if `cursor` is out of bounds.
+pub fn put(
+    text: &String,
+    cursor: usize,
+    value: &Value,
+    count: usize,
+    before: bool,
+    options: &Options,
+) -> Edit {
+    let line = text.line_of(cursor);
+    let range = text.line_range(line);
+    match value.kind {
+        Kind::Charwise => {
+            let at = if before || cursor == range.end {
+                cursor
+            } else {
+                text.next_grapheme_boundary(cursor)
+            };
+            let src = value.text.repeat(count);
+            let cursor = if src.is_empty() || src.contains('\n') {
+                at
+            } else {
+                at + String::from(&src[..]).prev_grapheme_boundary(src.len())
+            };
+            Edit::new(vec![Operation { dest: at..at, src: src }], cursor)
+        },
+        Kind::Linewise => {
+            let mut lines = value.text.clone();
+            if !lines.ends_with('\n') {
+                lines.push('\n');
+            }
+            let blanks = lines.len() - lines.trim_start_matches(|ch| ch == ' ' || ch == '\t').len();
+            let mut src = lines.repeat(count);
+            // Offset to put the lines at, and where the first line starts after putting them.
+            let (at, start) = if before {
+                (range.start, range.start)
+            } else if line + 1 < text.line_count() {
+                let next = text.line_range(line + 1).start;
+                (next, next)
+            } else {
+                src.pop();
+                src.insert(0, '\n');
+                (text.len(), text.len() + 1)
+            };
+            Edit::new(vec![Operation { dest: at..at, src: src }], start + blanks)
+        },
+        Kind::Blockwise => {
+            let tabstop = options.tabstop;
+            let col = if before || cursor == range.end {
+                text.virtcol(cursor, tabstop)
+            } else {
+                text.virtcol(text.next_grapheme_boundary(cursor), tabstop)
+            };
+            let pieces: Vec<_> = value.text.split('\n').collect();
+            let width = pieces.iter().map(|piece| str_width(piece, 0, tabstop)).max().unwrap_or(0);
+            let spaces = |cells| iter::repeat(' ').take(cells).collect::<StdString>();
+            let block_line = |piece: &str, padded: bool| {
+                let mut src = StdString::new();
+                for index in 0..count {
+                    src.push_str(piece);
+                    if padded || index + 1 < count {
+                        src.push_str(&spaces(width - str_width(piece, 0, tabstop)));
+                    }
+                }
+                src
+            };
+
+            let mut operations = Vec::new();
+            let mut cursor = 0;
+            let mut added = StdString::new();
+            for (index, piece) in pieces.iter().enumerate() {
+                let line = line + index;
+                if line >= text.line_count() {
+                    added.push('\n');
+                    added.push_str(&spaces(col));
+                    added.push_str(&block_line(piece, false));
+                    continue;
+                }
+                let insertion = Insertion::new(text, line, col, tabstop);
+                if index == 0 {
+                    cursor = insertion.start();
+                }
+                operations.push(insertion.operation(&block_line(piece, !insertion.short)));
+            }
+            if !added.is_empty() {
+                operations.push(Operation { dest: text.len()..text.len(), src: added });
+            }
+            Edit::new(operations, cursor)
+        },
+    }
+}
+
+// Removes a region, without the linewise exception of `d`.
+fn remove_region(text: &String, region: &Region, options: &Options) -> Edit {
+    match *region {
+        Region::Chars(ref range) => Edit::new(remove(range.clone()), range.start),
+        Region::Lines(ref lines) => {
+            let dest = whole_lines(text, lines.clone());
+            let start = dest.start;
+            Edit::new(remove(dest), start)
+        },
+        Region::Block(ref block) => {
+            let cuts = block.cuts(text, options.tabstop);
+            let cursor = cuts[0].range.start + cuts[0].before;
+            let operations = cuts.into_iter()
+                .filter(|cut| !cut.range.is_empty())
+                .map(|cut| Operation { dest: cut.range, src: cut.outside })
+                .collect();
+            Edit::new(operations, cursor)
+        },
+    }
+}
+
+fn yanked(text: &String, region: &Region, tabstop: usize) -> Value {
+    match *region {
+        Region::Chars(ref range) => Value {
+            text: text.slice(range.clone()).into(),
+            kind: Kind::Charwise,
+        },
+        Region::Lines(ref lines) => {
+            let range = text.line_range(lines.start).start..text.line_range(lines.end - 1).end;
+            let mut lines: StdString = text.slice(range).into();
+            lines.push('\n');
+            Value { text: lines, kind: Kind::Linewise }
+        },
+        Region::Block(ref block) => {
+            Value { text: block.text(text, tabstop), kind: Kind::Blockwise }
+        },
+    }
+}
+
+// Returns where the cursor moves to after yanking or changing case.
+fn start_cursor(text: &String, cursor: usize, region: &Region, options: &Options) -> usize {
+    let tabstop = options.tabstop;
+    match *region {
+        Region::Chars(ref range) => range.start,
+        Region::Lines(ref lines) if text.line_of(cursor) == lines.start => cursor,
+        Region::Lines(ref lines) => {
+            text.virtcol_offset(lines.start, text.virtcol(cursor, tabstop), tabstop)
+        },
+        Region::Block(ref block) => {
+            text.virtcol_offset(block.lines.start, block.cols.start, tabstop)
+        },
+    }
+}
+
+// Returns the range of whole lines with their line breaks, or with the line break before them if
+// they end the text.
+fn whole_lines(text: &String, lines: Range<usize>) -> Range<usize> {
+    let start = text.line_range(lines.start).start;
+    if lines.end < text.line_count() {
+        start..text.line_range(lines.end).start
+    } else if lines.start > 0 {
+        (start - 1)..text.len()
+    } else {
+        start..text.len()
+    }
+}
+
+fn remove(dest: Range<usize>) -> Vec<Operation<StdString>> {
+    if dest.is_empty() {
+        Vec::new()
+    } else {
+        vec![Operation { dest: dest, src: StdString::new() }]
+    }
+}
+
+// Adds an operation unless it would not change the text.
+fn replace(
+    text: &String,
+    operations: &mut Vec<Operation<StdString>>,
+    dest: Range<usize>,
+    src: StdString,
+) {
+    if text.slice(dest.clone()).chars().ne(src.chars()) {
+        operations.push(Operation { dest: dest, src: src });
+    }
+}
+
+fn first_non_blank(text: &String, line: usize) -> usize {
+    motion::first_non_blank(text, text.line_range(line).start).offset
+}
+
+fn is_blank(slice: Str) -> bool {
+    slice.chars().all(|ch| ch == ' ' || ch == '\t')
+}
+
+// Returns blanks from column `start` to column `end`.
+fn whitespace(start: usize, end: usize, options: &Options) -> StdString {
+    let mut blanks = StdString::new();
+    let mut col = start;
+    if !options.expandtab {
+        while (col / options.tabstop + 1) * options.tabstop <= end {
+            blanks.push('\t');
+            col = (col / options.tabstop + 1) * options.tabstop;
+        }
+    }
+    blanks.extend(iter::repeat(' ').take(end.saturating_sub(col)));
+    blanks
+}
+
+// Returns the display width of `s` starting at column `col`.
+fn str_width(s: &str, col: usize, tabstop: usize) -> usize {
+    s.chars().fold(col, |col, ch| col + width::char_width(ch, col, tabstop)) - col
+}
+
+fn convert_case(ch: char, case: Case) -> Vec<char> {
+    match case {
+        Case::Lower => ch.to_lowercase().collect(),
+        Case::Upper => ch.to_uppercase().collect(),
+        Case::Toggle if ch.is_lowercase() => ch.to_uppercase().collect(),
+        Case::Toggle => ch.to_lowercase().collect(),
+    }
+}
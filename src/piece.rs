@@ -0,0 +1,236 @@
+//! Piece table.
+//!
+//! A piece table stores the original text and all inserted text in two append-only buffers, and
+//! represents the current text as a sequence of pieces referring to spans of either buffer.
+//! Existing text is never moved, so the cost of a splice depends on the number of pieces rather
+//! than the length of the text or the distance between edits.
+
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+use std::string::String as StdString;
+
+use range::IntoRange;
+use text::Chunks;
+
+/// Piece table UTF-8 string.
+#[derive(Debug, Clone, Default)]
+pub struct String {
+    original: StdString,
+    add: StdString,
+    pieces: Vec<Piece>,
+    len: usize,
+}
+
+// Span of one of the buffers.
+#[derive(Debug, Clone, Copy)]
+struct Piece {
+    source: Source,
+    start: usize,
+    len: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Original,
+    Add,
+}
+
+impl String {
+    /// Creates an empty string without allocating.
+    pub fn new() -> Self {
+        String::default()
+    }
+
+    /// Returns the length of the string.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the string contains no data.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of pieces.
+    pub fn pieces(&self) -> usize {
+        self.pieces.len()
+    }
+
+    /// Returns `true` if `offset` is a char boundary.
+    pub fn is_char_boundary(&self, offset: usize) -> bool {
+        if offset == 0 || offset == self.len {
+            return true;
+        }
+        if offset > self.len {
+            return false;
+        }
+        let (index, local) = self.find(offset);
+        self.text(&self.pieces[index]).is_char_boundary(local)
+    }
+
+    /// Returns a slice of the string as chunks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point, or if either point is out of
+    /// bounds or not a char boundary.
+    pub fn slice<R: IntoRange>(&self, range: R) -> Chunks<str> {
+        let range = range.into_range(self.len);
+        assert!(range.start <= range.end, "range start greater than end");
+        assert!(range.end <= self.len, "range out of bounds");
+        assert!(self.is_char_boundary(range.start), "range start not char boundary");
+        assert!(self.is_char_boundary(range.end), "range end not char boundary");
+
+        let mut offset = 0;
+        let mut chunks = Vec::new();
+        for piece in &self.pieces {
+            let end = offset + piece.len;
+            if end > range.start && offset < range.end {
+                let start = range.start.saturating_sub(offset);
+                let end = piece.len - end.saturating_sub(range.end);
+                chunks.push(&self.text(piece)[start..end]);
+            }
+            if end >= range.end {
+                break;
+            }
+            offset = end;
+        }
+        Chunks::new(chunks)
+    }
+
+    /// Replaces a slice of the string. Destination and source can be different lengths.
+    ///
+    /// Returns the range of written bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point, or if either point is out of
+    /// bounds or not a char boundary.
+    pub fn splice<R: IntoRange>(&mut self, dest: R, src: &str) -> Range<usize> {
+        let dest = dest.into_range(self.len);
+        assert!(dest.start <= dest.end, "dest start greater than end");
+        assert!(dest.end <= self.len, "dest out of bounds");
+        assert!(self.is_char_boundary(dest.start), "dest start not char boundary");
+        assert!(self.is_char_boundary(dest.end), "dest end not char boundary");
+
+        let start = self.split(dest.start);
+        let end = self.split(dest.end);
+        self.pieces.drain(start..end);
+
+        if !src.is_empty() {
+            let piece = Piece {
+                source: Source::Add,
+                start: self.add.len(),
+                len: src.len(),
+            };
+            self.add.push_str(src);
+
+            // Extend the previous piece when appending to the most recent insertion, as in
+            // typing.
+            let extend = start > 0 && {
+                let prev = &self.pieces[start - 1];
+                prev.source == Source::Add && prev.start + prev.len == piece.start
+            };
+            if extend {
+                self.pieces[start - 1].len += piece.len;
+            } else {
+                self.pieces.insert(start, piece);
+            }
+        }
+
+        self.len = self.len - dest.len() + src.len();
+        dest.start..(dest.start + src.len())
+    }
+
+    // Returns the text of a piece.
+    fn text(&self, piece: &Piece) -> &str {
+        let buf = match piece.source {
+            Source::Original => &self.original,
+            Source::Add => &self.add,
+        };
+        &buf[piece.start..(piece.start + piece.len)]
+    }
+
+    // Returns the index of the piece containing `offset` and the offset within it. An offset on a
+    // boundary between pieces belongs to the later piece.
+    fn find(&self, offset: usize) -> (usize, usize) {
+        let mut start = 0;
+        for (index, piece) in self.pieces.iter().enumerate() {
+            if offset < start + piece.len {
+                return (index, offset - start);
+            }
+            start += piece.len;
+        }
+        (self.pieces.len(), offset - start)
+    }
+
+    // Splits the piece containing `offset` so that a piece starts there, returning its index.
+    fn split(&mut self, offset: usize) -> usize {
+        let (index, local) = self.find(offset);
+        if local == 0 {
+            return index;
+        }
+        let piece = self.pieces[index];
+        self.pieces[index].len = local;
+        self.pieces.insert(index + 1, Piece {
+            source: piece.source,
+            start: piece.start + local,
+            len: piece.len - local,
+        });
+        index + 1
+    }
+}
+
+impl From<StdString> for String {
+    fn from(string: StdString) -> Self {
+        let pieces = if string.is_empty() {
+            Vec::new()
+        } else {
+            vec![Piece {
+                source: Source::Original,
+                start: 0,
+                len: string.len(),
+            }]
+        };
+        String {
+            len: string.len(),
+            original: string,
+            add: StdString::new(),
+            pieces: pieces,
+        }
+    }
+}
+
+impl<'a> From<&'a str> for String {
+    fn from(s: &'a str) -> Self {
+        String::from(StdString::from(s))
+    }
+}
+
+impl Into<StdString> for String {
+    fn into(self) -> StdString {
+        self.slice(..).into()
+    }
+}
+
+impl Display for String {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for chunk in self.slice(..) {
+            f.write_str(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl PartialEq<str> for String {
+    fn eq(&self, other: &str) -> bool {
+        self.slice(..) == other
+    }
+}
+
+// Necessary because only RHS is a reference.
+impl<'a> PartialEq<&'a str> for String {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
@@ -85,3 +85,48 @@ impl RangeExt for Range<usize> {
         (self.start - len)..(self.end - len)
     }
 }
+
+/// Returns the indices of non-overlapping ranges in order of position.
+///
+/// Ranges may touch. Empty ranges at the same position keep their relative order, and are ordered
+/// before a non-empty range starting at that position.
+///
+/// # Panics
+///
+/// Panics if the starting point of any range is greater than its end point, or if any ranges
+/// overlap.
+pub fn sort_disjoint(ranges: &[Range<usize>]) -> Vec<usize> {
+    for range in ranges {
+        assert!(range.start <= range.end, "range start greater than range end");
+    }
+    let mut indices: Vec<usize> = (0..ranges.len()).collect();
+    indices.sort_by_key(|&i| (ranges[i].start, ranges[i].end));
+    for pair in indices.windows(2) {
+        assert!(ranges[pair[0]].end <= ranges[pair[1]].start, "ranges overlap");
+    }
+    indices
+}
+
+/// Performs a batch of non-overlapping splices in order of position with `splice`, returning the
+/// ranges it writes in the order given.
+///
+/// Destinations are relative to the data before any splice. `splice` is called with each
+/// destination shifted by the splices before it, and returns the range it writes.
+///
+/// # Panics
+///
+/// Panics if the starting point of any destination is greater than its end point, or if any
+/// destinations overlap.
+pub fn splice_disjoint<S, F>(splices: &[(Range<usize>, S)], mut splice: F) -> Vec<Range<usize>>
+where S: Copy, F: FnMut(Range<usize>, S) -> Range<usize> {
+    let dests: Vec<_> = splices.iter().map(|&(ref dest, _)| dest.clone()).collect();
+    let mut written = vec![0..0; splices.len()];
+    let mut shift = 0isize;
+    for index in sort_disjoint(&dests) {
+        let (ref dest, src) = splices[index];
+        let start = (dest.start as isize + shift) as usize;
+        written[index] = splice(start..(start + dest.len()), src);
+        shift += written[index].len() as isize - dest.len() as isize;
+    }
+    written
+}
@@ -0,0 +1,120 @@
+use super::Error;
+use super::parse::{Assertion, Class, Node};
+
+// Largest number of instructions, which limits the expansion of counts.
+const MAX_INSTS: usize = 100_000;
+
+// Instruction of a Pike VM program.
+#[derive(Debug, Clone)]
+pub(super) enum Inst {
+    Char(char),
+    Any { newline: bool },
+    Class(Class),
+    Assert(Assertion),
+
+    // Record the current offset in a capture slot.
+    Save(usize),
+
+    // Continue at both targets, preferring the first.
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+pub(super) fn compile(node: &Node) -> Result<Vec<Inst>, Error> {
+    let mut program = vec![Inst::Save(0)];
+    emit(&mut program, node)?;
+    program.push(Inst::Match);
+    Ok(program)
+}
+
+fn emit(program: &mut Vec<Inst>, node: &Node) -> Result<(), Error> {
+    if program.len() > MAX_INSTS {
+        return Err(Error::TooLarge);
+    }
+    match *node {
+        Node::Empty => {},
+        Node::Char(ch) => program.push(Inst::Char(ch)),
+        Node::Any { newline } => program.push(Inst::Any { newline: newline }),
+        Node::Class(ref class) => program.push(Inst::Class(class.clone())),
+        Node::Assert(assertion) => program.push(Inst::Assert(assertion)),
+        Node::MatchStart => program.push(Inst::Save(0)),
+        Node::MatchEnd => program.push(Inst::Save(1)),
+        Node::Group(index, ref node) => {
+            if let Some(index) = index {
+                program.push(Inst::Save(index * 2));
+            }
+            emit(program, node)?;
+            if let Some(index) = index {
+                program.push(Inst::Save(index * 2 + 1));
+            }
+        },
+        Node::Concat(ref nodes) => {
+            for node in nodes {
+                emit(program, node)?;
+            }
+        },
+        Node::Alternate(ref nodes) => {
+            let mut jumps = Vec::new();
+            for (i, node) in nodes.iter().enumerate() {
+                let split = program.len();
+                if i + 1 < nodes.len() {
+                    program.push(Inst::Split(split + 1, 0));
+                }
+                emit(program, node)?;
+                if i + 1 < nodes.len() {
+                    jumps.push(program.len());
+                    program.push(Inst::Jump(0));
+                    let next = program.len();
+                    program[split] = Inst::Split(split + 1, next);
+                }
+            }
+            let end = program.len();
+            for jump in jumps {
+                program[jump] = Inst::Jump(end);
+            }
+        },
+        Node::Repeat { ref node, min, max, greedy } => {
+            if max.unwrap_or(min) > MAX_INSTS {
+                return Err(Error::TooLarge);
+            }
+            for _ in 0..min {
+                emit(program, node)?;
+            }
+            match max {
+                None => {
+                    // Loop: split between the body and the exit.
+                    let split = program.len();
+                    program.push(Inst::Split(0, 0));
+                    emit(program, node)?;
+                    program.push(Inst::Jump(split));
+                    let end = program.len();
+                    program[split] = branch(greedy, split + 1, end);
+                },
+                Some(max) => {
+                    // Nested optional copies, so that all exit to the end.
+                    let mut splits = Vec::new();
+                    for _ in min..max {
+                        splits.push(program.len());
+                        program.push(Inst::Split(0, 0));
+                        emit(program, node)?;
+                    }
+                    let end = program.len();
+                    for split in splits {
+                        program[split] = branch(greedy, split + 1, end);
+                    }
+                },
+            }
+        },
+    }
+    Ok(())
+}
+
+// Returns a split which prefers `body` if greedy, and `exit` otherwise.
+fn branch(greedy: bool, body: usize, exit: usize) -> Inst {
+    if greedy {
+        Inst::Split(body, exit)
+    } else {
+        Inst::Split(exit, body)
+    }
+}
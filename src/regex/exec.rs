@@ -0,0 +1,310 @@
+use std::mem;
+
+use gap::Str;
+use word::Keyword;
+use super::Regex;
+use super::compile::Inst;
+use super::parse::{Assertion, Class, Item, Named};
+
+// Capture slots of a thread, holding the start and end offsets of each group.
+type Slots = Vec<Option<usize>>;
+
+#[derive(Debug)]
+struct Thread {
+    pc: usize,
+    slots: Slots,
+}
+
+// Chars around an offset, for assertions.
+#[derive(Debug, Clone, Copy)]
+struct Context {
+    offset: usize,
+    len: usize,
+    prev: Option<char>,
+    next: Option<char>,
+}
+
+// Pending work when following the empty transitions of a thread.
+enum Frame {
+    Follow(usize),
+    Restore(usize, Option<usize>),
+}
+
+// Runs a Pike VM over `haystack`, starting threads at each offset from `start` up to but excluding
+// `limit`. Returns the capture slots of the leftmost match, preferring earlier alternatives and
+// greedy repeats as a backtracking matcher would.
+//
+// The chars are streamed from both fragments of the haystack, so it never needs to be contiguous.
+pub(super) fn exec(regex: &Regex, haystack: Str, start: usize, limit: usize) -> Option<Slots> {
+    let mut vm = Vm {
+        regex: regex,
+        seen: vec![usize::max_value(); regex.program.len()],
+        stack: Vec::new(),
+    };
+    let mut current = Vec::new();
+    let mut next = Vec::new();
+    let mut matched = None;
+
+    let mut chars = haystack.slice(start..).chars();
+    let mut context = Context {
+        offset: start,
+        len: haystack.len(),
+        prev: haystack.slice(..start).chars().next_back(),
+        next: chars.next(),
+    };
+    let mut step = 0;
+
+    loop {
+        if matched.is_none() && context.offset < limit {
+            let slots = vec![None; (regex.groups + 1) * 2];
+            vm.add(&mut current, step, 0, slots, context);
+        }
+        if current.is_empty() && (matched.is_some() || context.offset >= limit) {
+            break;
+        }
+
+        let ch = context.next;
+        let following = Context {
+            offset: context.offset + ch.map_or(0, char::len_utf8),
+            len: context.len,
+            prev: ch,
+            next: if ch.is_some() { chars.next() } else { None },
+        };
+
+        step += 1;
+        for thread in current.drain(..) {
+            let accept = match regex.program[thread.pc] {
+                Inst::Char(expected) => ch.map_or(false, |ch| regex.eq(ch, expected)),
+                Inst::Any { newline } => ch.map_or(false, |ch| newline || ch != '\n'),
+                Inst::Class(ref class) => ch.map_or(false, |ch| regex.is_in(ch, class)),
+                Inst::Match => {
+                    let mut slots = thread.slots;
+                    if slots[1].is_none() {
+                        slots[1] = Some(context.offset);
+                    }
+                    matched = Some(slots);
+                    // Lower priority threads can no longer win.
+                    break;
+                },
+                _ => unreachable!(),
+            };
+            if accept {
+                vm.add(&mut next, step, thread.pc + 1, thread.slots, following);
+            }
+        }
+
+        if ch.is_none() {
+            break;
+        }
+        context = following;
+        mem::swap(&mut current, &mut next);
+    }
+    matched
+}
+
+// Returns the last offset from `start` up to but excluding `limit` at which a thread begins that
+// reaches a match, or `None` if there is none. Where `\zs` moves the start of the match is not
+// taken into account.
+//
+// Threads are kept in order of the offset they began at, latest first, so of the threads reaching
+// an instruction at the same offset only the latest is kept. When a thread matches, the threads
+// which began before it are dropped, so the run ends as soon as no later match start is possible.
+pub(super) fn last_start(
+    regex: &Regex,
+    haystack: Str,
+    start: usize,
+    limit: usize,
+) -> Option<usize> {
+    let mut vm = Vm {
+        regex: regex,
+        seen: vec![usize::max_value(); regex.program.len()],
+        stack: Vec::new(),
+    };
+    // The offset each thread began at is kept in a slot after the capture slots.
+    let began = (regex.groups + 1) * 2;
+    let mut current = Vec::new();
+    let mut next = Vec::new();
+    let mut last = None;
+
+    let mut chars = haystack.slice(start..).chars();
+    let mut context = Context {
+        offset: start,
+        len: haystack.len(),
+        prev: haystack.slice(..start).chars().next_back(),
+        next: chars.next(),
+    };
+    let mut step = 0;
+    if start < limit {
+        vm.add(&mut current, step, 0, beginning(began, start), context);
+    }
+
+    while !current.is_empty() || context.offset < limit {
+        let ch = context.next;
+        let following = Context {
+            offset: context.offset + ch.map_or(0, char::len_utf8),
+            len: context.len,
+            prev: ch,
+            next: if ch.is_some() { chars.next() } else { None },
+        };
+
+        step += 1;
+        if ch.is_some() && following.offset < limit {
+            vm.add(&mut next, step, 0, beginning(began, following.offset), following);
+        }
+        for thread in current.drain(..) {
+            let accept = match regex.program[thread.pc] {
+                Inst::Char(expected) => ch.map_or(false, |ch| regex.eq(ch, expected)),
+                Inst::Any { newline } => ch.map_or(false, |ch| newline || ch != '\n'),
+                Inst::Class(ref class) => ch.map_or(false, |ch| regex.is_in(ch, class)),
+                Inst::Match => {
+                    last = thread.slots[began];
+                    // Threads which began earlier can no longer give a later start.
+                    break;
+                },
+                _ => unreachable!(),
+            };
+            if accept {
+                vm.add(&mut next, step, thread.pc + 1, thread.slots, following);
+            }
+        }
+
+        if ch.is_none() {
+            break;
+        }
+        context = following;
+        mem::swap(&mut current, &mut next);
+    }
+    last
+}
+
+// Returns the slots of a thread beginning at `offset`.
+fn beginning(began: usize, offset: usize) -> Slots {
+    let mut slots = vec![None; began + 1];
+    slots[began] = Some(offset);
+    slots
+}
+
+struct Vm<'r> {
+    regex: &'r Regex,
+
+    // Step at which each instruction was last added, so that each is added once per offset.
+    seen: Vec<usize>,
+    stack: Vec<Frame>,
+}
+
+impl<'r> Vm<'r> {
+    // Adds a thread, following empty transitions in priority order.
+    fn add(
+        &mut self,
+        list: &mut Vec<Thread>,
+        step: usize,
+        pc: usize,
+        mut slots: Slots,
+        context: Context,
+    ) {
+        self.stack.push(Frame::Follow(pc));
+        while let Some(frame) = self.stack.pop() {
+            let pc = match frame {
+                Frame::Follow(pc) => pc,
+                Frame::Restore(slot, value) => {
+                    slots[slot] = value;
+                    continue;
+                },
+            };
+            if self.seen[pc] == step {
+                continue;
+            }
+            self.seen[pc] = step;
+
+            match self.regex.program[pc] {
+                Inst::Jump(target) => self.stack.push(Frame::Follow(target)),
+                Inst::Split(first, second) => {
+                    self.stack.push(Frame::Follow(second));
+                    self.stack.push(Frame::Follow(first));
+                },
+                Inst::Save(slot) => {
+                    self.stack.push(Frame::Restore(slot, slots[slot]));
+                    self.stack.push(Frame::Follow(pc + 1));
+                    slots[slot] = Some(context.offset);
+                },
+                Inst::Assert(assertion) => {
+                    if self.regex.holds(assertion, context) {
+                        self.stack.push(Frame::Follow(pc + 1));
+                    }
+                },
+                _ => list.push(Thread {
+                    pc: pc,
+                    slots: slots.clone(),
+                }),
+            }
+        }
+    }
+}
+
+impl Regex {
+    // Returns `true` if two chars are equal, ignoring case if needed.
+    fn eq(&self, a: char, b: char) -> bool {
+        a == b || self.ignore_case && fold(a) == fold(b)
+    }
+
+    // Returns `true` if `ch` is in a class, ignoring case if needed.
+    fn is_in(&self, ch: char, class: &Class) -> bool {
+        if ch == '\n' {
+            return class.newline;
+        }
+        let found = self.contains(class, ch) || self.ignore_case && (
+            ch.to_lowercase().any(|ch| self.contains(class, ch))
+                || ch.to_uppercase().any(|ch| self.contains(class, ch))
+        );
+        found != class.negated
+    }
+
+    fn contains(&self, class: &Class, ch: char) -> bool {
+        class.items.iter().any(|item| match *item {
+            Item::Range(start, end) => start <= ch && ch <= end,
+            Item::Named(named) => is_named(named, ch, &self.keyword),
+        })
+    }
+
+    fn holds(&self, assertion: Assertion, context: Context) -> bool {
+        let is_keyword = |ch: Option<char>| ch.map_or(false, |ch| self.keyword.contains(ch));
+        match assertion {
+            Assertion::LineStart => context.prev.map_or(true, |ch| ch == '\n'),
+            Assertion::LineEnd => context.next.map_or(true, |ch| ch == '\n'),
+            Assertion::WordStart => !is_keyword(context.prev) && is_keyword(context.next),
+            Assertion::WordEnd => is_keyword(context.prev) && !is_keyword(context.next),
+            Assertion::TextStart => context.offset == 0,
+            Assertion::TextEnd => context.offset == context.len,
+        }
+    }
+}
+
+// Returns the simple lower case of a char.
+fn fold(ch: char) -> char {
+    let mut lower = ch.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(lower), None) => lower,
+        _ => ch,
+    }
+}
+
+fn is_named(named: Named, ch: char, keyword: &Keyword) -> bool {
+    match named {
+        Named::Digit => ch.is_ascii_digit(),
+        Named::Word => ch.is_ascii_alphanumeric() || ch == '_',
+        Named::Alpha => ch.is_ascii_alphabetic(),
+        Named::Lower => ch.is_lowercase(),
+        Named::Upper => ch.is_uppercase(),
+        Named::Hex => ch.is_ascii_hexdigit(),
+        Named::Octal => ch >= '0' && ch <= '7',
+        Named::Head => ch.is_ascii_alphabetic() || ch == '_',
+        Named::Keyword => keyword.contains(ch),
+        Named::Alnum => ch.is_ascii_alphanumeric(),
+        Named::Blank => ch == ' ' || ch == '\t',
+        Named::Cntrl => ch.is_ascii_control(),
+        Named::Graph => ch.is_ascii_graphic(),
+        Named::Print => ch == ' ' || ch.is_ascii_graphic(),
+        Named::Punct => ch.is_ascii_punctuation(),
+        Named::Whitespace => ch.is_ascii_whitespace() || ch == '\x0b',
+    }
+}
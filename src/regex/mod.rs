@@ -0,0 +1,352 @@
+//! Regular expressions with vi syntax.
+//!
+//! Patterns are matched against gap buffer strings by streaming the chars of both fragments, so
+//! the gap never has to be closed. Matching uses a Pike VM, which runs in time linear in the
+//! length of the text and prefers the same match a backtracking matcher would.
+//!
+//! # Syntax
+//!
+//! Which chars are special depends on the magic level, which `\v`, `\m`, `\M` and `\V` switch
+//! within a pattern as in vi:
+//!
+//! | very magic | magic      | nomagic    | very nomagic | matches                          |
+//! |------------|------------|------------|--------------|----------------------------------|
+//! | `^`        | `^`        | `^`        | `\^`         | start of line                    |
+//! | `$`        | `$`        | `$`        | `\$`         | end of line                      |
+//! | `.`        | `.`        | `\.`       | `\.`         | any char except a line feed      |
+//! | `[a-z]`    | `[a-z]`    | `\[a-z]`   | `\[a-z]`     | collection                       |
+//! | `*`        | `*`        | `\*`       | `\*`         | zero or more                     |
+//! | `+`        | `\+`       | `\+`       | `\+`         | one or more                      |
+//! | `=`, `?`   | `\=`, `\?` | `\=`, `\?` | `\=`, `\?`   | zero or one                      |
+//! | `{n,m}`    | `\{n,m}`   | `\{n,m}`   | `\{n,m}`     | `n` to `m`, `\{-n,m}` lazily     |
+//! | `(a)`      | `\(a\)`    | `\(a\)`    | `\(a\)`      | capture group                    |
+//! | `%(a)`     | `\%(a\)`   | `\%(a\)`   | `\%(a\)`     | group without capture            |
+//! | `a|b`      | `a\|b`     | `a\|b`     | `a\|b`       | alternation                      |
+//! | `<`, `>`   | `\<`, `\>` | `\<`, `\>` | `\<`, `\>`   | start and end of a keyword word  |
+//!
+//! Also supported are the class escapes `\s`, `\d`, `\w`, `\a`, `\l`, `\u`, `\x`, `\o`, `\h`
+//! and `\k` with their upper case negations, `\_` before a class or `.` to also match line feeds,
+//! `\_^` and `\_$` anywhere in a pattern, `\%^` and `\%$` for the start and end of the text,
+//! `\zs` and `\ze` to set the start and end of the match, and `\c` and `\C` to ignore or match
+//! case. Backreferences, lookaround and `~` are not supported.
+
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+use std::string::String as StdString;
+
+use gap::Str;
+use word::Keyword;
+
+mod compile;
+mod exec;
+mod parse;
+
+/// How many chars are special in a pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Magic {
+    /// All ASCII chars except letters, digits and `_` are special, as after `\v`.
+    VeryMagic,
+
+    /// The default, as after `\m`.
+    Magic,
+
+    /// Only `^` and `$` are special, as after `\M` or with `nomagic`.
+    NoMagic,
+
+    /// Only `\` is special, as after `\V`.
+    VeryNoMagic,
+}
+
+/// Options for compiling a pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Options {
+    /// Magic level at the start of the pattern.
+    pub magic: Magic,
+
+    /// Ignore case, like the `ignorecase` option.
+    pub ignore_case: bool,
+
+    /// Match case if the pattern contains upper case letters, like the `smartcase` option. Only
+    /// applies if `ignore_case` is set.
+    pub smart_case: bool,
+
+    /// Keyword chars for `\k`, `\<` and `\>`.
+    pub keyword: Keyword,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            magic: Magic::Magic,
+            ignore_case: false,
+            smart_case: false,
+            keyword: Keyword::default(),
+        }
+    }
+}
+
+/// Pattern syntax error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Unmatched `\(` or `\)`.
+    UnmatchedParen,
+
+    /// Unterminated `\_[`.
+    UnmatchedBracket,
+
+    /// Invalid `\{` count.
+    InvalidCount,
+
+    /// A multi such as `\+` with no atom before it.
+    NothingToRepeat,
+
+    /// Pattern ending in a backslash.
+    TrailingBackslash,
+
+    /// Pattern too large after expanding counts.
+    TooLarge,
+
+    /// Valid vi syntax which is not supported.
+    Unsupported(StdString),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Error::UnmatchedParen => f.write_str("unmatched \\( or \\)"),
+            Error::UnmatchedBracket => f.write_str("missing ] after \\_["),
+            Error::InvalidCount => f.write_str("syntax error in \\{...}"),
+            Error::NothingToRepeat => f.write_str("nothing to repeat"),
+            Error::TrailingBackslash => f.write_str("trailing \\"),
+            Error::TooLarge => f.write_str("pattern too large"),
+            Error::Unsupported(ref item) => write!(f, "unsupported item {}", item),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// Compiled regular expression.
+#[derive(Debug, Clone)]
+pub struct Regex {
+    program: Vec<compile::Inst>,
+    groups: usize,
+    ignore_case: bool,
+    keyword: Keyword,
+}
+
+impl Regex {
+    /// Compiles a pattern with the default options.
+    pub fn new(pattern: &str) -> Result<Self, Error> {
+        Regex::with_options(pattern, &Options::default())
+    }
+
+    /// Compiles a pattern.
+    pub fn with_options(pattern: &str, options: &Options) -> Result<Self, Error> {
+        let parsed = parse::parse(pattern, options.magic)?;
+        let ignore_case = parsed.ignore_case.unwrap_or(
+            options.ignore_case && !(options.smart_case && parse::has_upper(pattern))
+        );
+        Ok(Regex {
+            program: compile::compile(&parsed.node)?,
+            groups: parsed.groups,
+            ignore_case: ignore_case,
+            keyword: options.keyword,
+        })
+    }
+
+    /// Returns the number of capture groups, including the whole match.
+    pub fn captures_len(&self) -> usize {
+        self.groups + 1
+    }
+
+    /// Returns `true` if the pattern ignores case.
+    pub fn ignores_case(&self) -> bool {
+        self.ignore_case
+    }
+
+    /// Returns `true` if the pattern matches anywhere in `haystack`.
+    pub fn is_match(&self, haystack: Str) -> bool {
+        self.find_at(haystack, 0).is_some()
+    }
+
+    /// Returns the first match starting at or after `offset`.
+    ///
+    /// Matches see the text before `offset`, so for example `^` does not match at an offset in
+    /// the middle of a line.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is not a char boundary of `haystack`.
+    pub fn find_at(&self, haystack: Str, offset: usize) -> Option<Range<usize>> {
+        self.captures_at(haystack, offset).and_then(|captures| captures.get(0))
+    }
+
+    /// Returns the capture groups of the first match starting at or after `offset`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is not a char boundary of `haystack`.
+    pub fn captures_at(&self, haystack: Str, offset: usize) -> Option<Captures> {
+        // Threads may also start at the end of the haystack.
+        let slots = exec::exec(self, haystack, offset, haystack.len() + 1)?;
+        Some(Captures { slots: slots })
+    }
+
+    /// Returns the last match starting before `offset`, as found by vi's backward search.
+    ///
+    /// Each line is searched from its start for non-overlapping matches, so a match may overlap
+    /// `offset` but another match ending after the start of the returned one is not found.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is not a char boundary of `haystack`.
+    pub fn rfind_at(&self, haystack: Str, offset: usize) -> Option<Range<usize>> {
+        self.rcaptures_at(haystack, offset).and_then(|captures| captures.get(0))
+    }
+
+    /// Returns the capture groups of the last match starting before `offset`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is not a char boundary of `haystack`.
+    pub fn rcaptures_at(&self, haystack: Str, offset: usize) -> Option<Captures> {
+        let mut limit = offset;
+        loop {
+            // Skips the lines in which no match starts.
+            let start = self.last_start(haystack, limit)?;
+            let line = line_start(haystack, start);
+            let end = match haystack.slice(start..limit).find("\n") {
+                Some(index) => start + index + 1,
+                None => limit,
+            };
+
+            let mut last = None;
+            let mut position = line;
+            while position < end {
+                let slots = match exec::exec(self, haystack, position, end) {
+                    Some(slots) => slots,
+                    None => break,
+                };
+                let captures = Captures { slots: slots };
+                let found = captures.get(0).unwrap();
+                if found.start >= end {
+                    break;
+                }
+                position = next_position(haystack, found);
+                last = Some(captures);
+            }
+
+            // A match may start after the end of the line with `\zs`.
+            if last.is_some() || line == 0 {
+                return last;
+            }
+            limit = line;
+        }
+    }
+
+    // Returns the last offset before `limit` at which a match begins.
+    //
+    // The line containing `limit` is searched first, and then twice as many lines before those each
+    // time, so that threads running on past the lines searched are only run a logarithmic number
+    // of times.
+    fn last_start(&self, haystack: Str, limit: usize) -> Option<usize> {
+        let mut end = limit;
+        let mut start = line_start(haystack, limit);
+        let mut lines = 1;
+        loop {
+            if start < end {
+                if let Some(found) = exec::last_start(self, haystack, start, end) {
+                    return Some(found);
+                }
+            }
+            if start == 0 {
+                return None;
+            }
+            end = start;
+            for _ in 0..lines {
+                if start == 0 {
+                    break;
+                }
+                start = line_start(haystack, start - 1);
+            }
+            lines *= 2;
+        }
+    }
+
+    /// Returns an iterator over the non-overlapping matches in `haystack`.
+    ///
+    /// After an empty match the search continues from the next char.
+    pub fn find_iter<'r, 'a>(&'r self, haystack: Str<'a>) -> Matches<'r, 'a> {
+        self.find_iter_at(haystack, 0)
+    }
+
+    /// Returns an iterator over the non-overlapping matches starting at or after `offset`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is not a char boundary of `haystack`.
+    pub fn find_iter_at<'r, 'a>(&'r self, haystack: Str<'a>, offset: usize) -> Matches<'r, 'a> {
+        Matches {
+            regex: self,
+            haystack: haystack,
+            position: Some(offset),
+        }
+    }
+}
+
+// Returns the offset of the start of the line containing `offset`.
+fn line_start(haystack: Str, offset: usize) -> usize {
+    haystack.slice(..offset).rfind("\n").map_or(0, |index| index + 1)
+}
+
+// Returns the offset to continue a search from after a match, or the length of the haystack plus
+// one if the match is empty and at its end.
+fn next_position(haystack: Str, found: Range<usize>) -> usize {
+    if found.end > found.start {
+        found.end
+    } else {
+        found.end + haystack.slice(found.end..).chars().next().map_or(1, char::len_utf8)
+    }
+}
+
+/// Capture groups of a match, as byte ranges of the haystack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Captures {
+    slots: Vec<Option<usize>>,
+}
+
+impl Captures {
+    /// Returns the range of a group, where group zero is the whole match, or `None` if the group
+    /// did not participate in the match.
+    pub fn get(&self, index: usize) -> Option<Range<usize>> {
+        match (self.slots.get(index * 2), self.slots.get(index * 2 + 1)) {
+            (Some(&Some(start)), Some(&Some(end))) => Some(start..end.max(start)),
+            _ => None,
+        }
+    }
+}
+
+/// Iterator over the matches of a regular expression in a gap buffer string.
+#[derive(Debug, Clone)]
+pub struct Matches<'r, 'a> {
+    regex: &'r Regex,
+    haystack: Str<'a>,
+    position: Option<usize>,
+}
+
+impl<'r, 'a> Iterator for Matches<'r, 'a> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        let position = self.position?;
+        let found = if position <= self.haystack.len() {
+            self.regex.find_at(self.haystack, position)
+        } else {
+            None
+        };
+        self.position = found.clone().map(|found| next_position(self.haystack, found));
+        found
+    }
+}
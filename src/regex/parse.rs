@@ -0,0 +1,539 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+use super::{Error, Magic};
+
+// Syntax tree of a pattern.
+#[derive(Debug, Clone)]
+pub(super) enum Node {
+    Empty,
+    Char(char),
+    Any { newline: bool },
+    Class(Class),
+    Assert(Assertion),
+    MatchStart,
+    MatchEnd,
+    Group(Option<usize>, Box<Node>),
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+// Zero-width assertions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Assertion {
+    LineStart,
+    LineEnd,
+    WordStart,
+    WordEnd,
+    TextStart,
+    TextEnd,
+}
+
+// Character class, either a collection or a class escape like `\s`.
+#[derive(Debug, Clone)]
+pub(super) struct Class {
+    pub(super) items: Vec<Item>,
+    pub(super) negated: bool,
+    pub(super) newline: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Item {
+    Range(char, char),
+    Named(Named),
+}
+
+// Named sets of chars, from class escapes and `[:name:]` items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Named {
+    Digit,
+    Word,
+    Alpha,
+    Lower,
+    Upper,
+    Hex,
+    Octal,
+    Head,
+    Keyword,
+    Alnum,
+    Blank,
+    Cntrl,
+    Graph,
+    Print,
+    Punct,
+    Whitespace,
+}
+
+// Parsed pattern.
+#[derive(Debug, Clone)]
+pub(super) struct Pattern {
+    pub(super) node: Node,
+    pub(super) groups: usize,
+
+    // Case sensitivity forced by `\c` or `\C`.
+    pub(super) ignore_case: Option<bool>,
+}
+
+// Pattern items after applying magic.
+#[derive(Debug, Clone)]
+enum Token {
+    Char(char),
+    Any { newline: bool },
+    Class(Class),
+    Assert(Assertion),
+    Caret,
+    Dollar,
+    Open { capture: bool },
+    Close,
+    Alternate,
+    Repeat { min: usize, max: Option<usize>, greedy: bool },
+    Star,
+    MatchStart,
+    MatchEnd,
+}
+
+pub(super) fn parse(pattern: &str, magic: Magic) -> Result<Pattern, Error> {
+    let mut lexer = Lexer {
+        chars: pattern.chars().peekable(),
+        magic: magic,
+        ignore_case: None,
+    };
+    let mut tokens = Vec::new();
+    while let Some(token) = lexer.next()? {
+        tokens.push(token);
+    }
+
+    let mut parser = Parser {
+        tokens: tokens,
+        pos: 0,
+        groups: 0,
+    };
+    let node = parser.alternate()?;
+    if parser.pos < parser.tokens.len() {
+        return Err(Error::UnmatchedParen);
+    }
+    Ok(Pattern {
+        node: node,
+        groups: parser.groups,
+        ignore_case: lexer.ignore_case,
+    })
+}
+
+// Returns `true` if the pattern contains an upper case letter which is not part of an escape, for
+// `smartcase`.
+pub(super) fn has_upper(pattern: &str) -> bool {
+    let mut chars = pattern.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            chars.next();
+        } else if ch.is_uppercase() {
+            return true;
+        }
+    }
+    false
+}
+
+struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+    magic: Magic,
+    ignore_case: Option<bool>,
+}
+
+impl<'a> Lexer<'a> {
+    fn next(&mut self) -> Result<Option<Token>, Error> {
+        loop {
+            let (escaped, ch) = match self.chars.next() {
+                None => return Ok(None),
+                Some('\\') => (true, self.chars.next().ok_or(Error::TrailingBackslash)?),
+                Some(ch) => (false, ch),
+            };
+
+            if escaped {
+                match ch {
+                    'v' => { self.magic = Magic::VeryMagic; continue },
+                    'm' => { self.magic = Magic::Magic; continue },
+                    'M' => { self.magic = Magic::NoMagic; continue },
+                    'V' => { self.magic = Magic::VeryNoMagic; continue },
+                    'c' => { self.ignore_case = Some(true); continue },
+                    'C' => { self.ignore_case = Some(false); continue },
+                    _ => {},
+                }
+                if ch.is_ascii_alphanumeric() || ch == '_' {
+                    return self.escape(ch).map(Some);
+                }
+            }
+
+            if !self.is_special(ch, escaped) {
+                return Ok(Some(Token::Char(ch)));
+            }
+            let token = match ch {
+                '^' => Token::Caret,
+                '$' => Token::Dollar,
+                '.' => Token::Any { newline: false },
+                '*' => Token::Star,
+                '[' => match self.collection(false) {
+                    Some(class) => Token::Class(class),
+                    None => Token::Char('['),
+                },
+                '~' => return Err(Error::Unsupported("~".to_owned())),
+                '(' => Token::Open { capture: true },
+                ')' => Token::Close,
+                '|' => Token::Alternate,
+                '+' => Token::Repeat { min: 1, max: None, greedy: true },
+                '=' | '?' => Token::Repeat { min: 0, max: Some(1), greedy: true },
+                '{' => self.count()?,
+                '<' => Token::Assert(Assertion::WordStart),
+                '>' => Token::Assert(Assertion::WordEnd),
+                '%' => self.percent()?,
+                '@' => return Err(Error::Unsupported("\\@".to_owned())),
+                _ => Token::Char(ch),
+            };
+            return Ok(Some(token));
+        }
+    }
+
+    // Returns `true` if a char has its special meaning under the current magic.
+    fn is_special(&self, ch: char, escaped: bool) -> bool {
+        match ch {
+            '(' | ')' | '|' | '+' | '=' | '?' | '{' | '@' | '%' | '<' | '>' => {
+                escaped != (self.magic == Magic::VeryMagic)
+            },
+            '.' | '*' | '[' | '~' => escaped != (self.magic <= Magic::Magic),
+            '^' | '$' => escaped != (self.magic <= Magic::NoMagic),
+            _ => false,
+        }
+    }
+
+    // Parses a backslash followed by a letter, digit or underscore.
+    fn escape(&mut self, ch: char) -> Result<Token, Error> {
+        let token = match ch {
+            'n' => Token::Char('\n'),
+            't' => Token::Char('\t'),
+            'e' => Token::Char('\x1b'),
+            'r' => Token::Char('\r'),
+            'b' => Token::Char('\x08'),
+            'z' => match self.chars.next() {
+                Some('s') => Token::MatchStart,
+                Some('e') => Token::MatchEnd,
+                Some(ch) => return Err(Error::Unsupported(format!("\\z{}", ch))),
+                None => return Err(Error::TrailingBackslash),
+            },
+            '_' => match self.chars.next() {
+                Some('.') => Token::Any { newline: true },
+                Some('^') => Token::Assert(Assertion::LineStart),
+                Some('$') => Token::Assert(Assertion::LineEnd),
+                Some('[') => match self.collection(true) {
+                    Some(class) => Token::Class(class),
+                    None => return Err(Error::UnmatchedBracket),
+                },
+                Some(ch) => match class_escape(ch) {
+                    Some(mut class) => {
+                        class.newline = true;
+                        Token::Class(class)
+                    },
+                    None => return Err(Error::Unsupported(format!("\\_{}", ch))),
+                },
+                None => return Err(Error::TrailingBackslash),
+            },
+            '1'..='9' => return Err(Error::Unsupported(format!("\\{}", ch))),
+            _ => match class_escape(ch) {
+                Some(class) => Token::Class(class),
+                None => return Err(Error::Unsupported(format!("\\{}", ch))),
+            },
+        };
+        Ok(token)
+    }
+
+    // Parses the rest of a `\%` item.
+    fn percent(&mut self) -> Result<Token, Error> {
+        match self.chars.next() {
+            Some('(') => Ok(Token::Open { capture: false }),
+            Some('^') => Ok(Token::Assert(Assertion::TextStart)),
+            Some('$') => Ok(Token::Assert(Assertion::TextEnd)),
+            Some(ch) => Err(Error::Unsupported(format!("\\%{}", ch))),
+            None => Err(Error::TrailingBackslash),
+        }
+    }
+
+    // Parses the rest of a `\{n,m}` count.
+    fn count(&mut self) -> Result<Token, Error> {
+        let greedy = self.chars.peek() != Some(&'-');
+        if !greedy {
+            self.chars.next();
+        }
+        let min = self.number();
+        let comma = self.chars.peek() == Some(&',');
+        let max = if comma {
+            self.chars.next();
+            self.number()
+        } else {
+            min
+        };
+        if self.chars.peek() == Some(&'\\') {
+            self.chars.next();
+        }
+        if self.chars.next() != Some('}') {
+            return Err(Error::InvalidCount);
+        }
+
+        let min = min.unwrap_or(0);
+        // Vi allows the bounds in either order.
+        let (min, max) = match max {
+            Some(max) if max < min => (max, Some(min)),
+            max => (min, max),
+        };
+        Ok(Token::Repeat {
+            min: min,
+            max: max,
+            greedy: greedy,
+        })
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let mut n: Option<usize> = None;
+        while let Some(digit) = self.chars.peek().and_then(|ch| ch.to_digit(10)) {
+            self.chars.next();
+            n = Some(n.unwrap_or(0).saturating_mul(10).saturating_add(digit as usize));
+        }
+        n
+    }
+
+    // Parses the rest of a `[]` collection, or returns `None` and consumes nothing if it is not
+    // terminated.
+    fn collection(&mut self, newline: bool) -> Option<Class> {
+        let mut chars = self.chars.clone();
+        let mut class = Class {
+            items: Vec::new(),
+            negated: false,
+            newline: newline,
+        };
+        if chars.peek() == Some(&'^') {
+            chars.next();
+            class.negated = true;
+        }
+
+        let mut first = true;
+        loop {
+            let ch = chars.next()?;
+            let start = match ch {
+                ']' if !first => break,
+                '[' if chars.peek() == Some(&':') => {
+                    let mut lookahead = chars.clone();
+                    lookahead.next();
+                    let name: String = lookahead.by_ref().take_while(|&ch| ch != ':').collect();
+                    match (named(&name), lookahead.next()) {
+                        (Some(named), Some(']')) => {
+                            chars = lookahead;
+                            class.items.push(Item::Named(named));
+                            first = false;
+                            continue;
+                        },
+                        _ => '[',
+                    }
+                },
+                '\\' => match chars.peek().cloned() {
+                    Some('n') => {
+                        chars.next();
+                        class.newline = true;
+                        first = false;
+                        continue;
+                    },
+                    Some(escape) => match collection_escape(escape) {
+                        Some(ch) => {
+                            chars.next();
+                            ch
+                        },
+                        None => '\\',
+                    },
+                    None => '\\',
+                },
+                ch => ch,
+            };
+            first = false;
+
+            let mut lookahead = chars.clone();
+            let end = match (lookahead.next(), lookahead.next()) {
+                (Some('-'), Some(end)) if end != ']' => {
+                    chars = lookahead;
+                    if end == '\\' {
+                        match chars.peek().cloned().and_then(collection_escape) {
+                            Some(ch) => {
+                                chars.next();
+                                ch
+                            },
+                            None => '\\',
+                        }
+                    } else {
+                        end
+                    }
+                },
+                _ => start,
+            };
+            if start <= end {
+                class.items.push(Item::Range(start, end));
+            }
+        }
+
+        self.chars = chars;
+        Some(class)
+    }
+}
+
+// Returns the char for a backslash escape in a collection.
+fn collection_escape(ch: char) -> Option<char> {
+    match ch {
+        'e' => Some('\x1b'),
+        't' => Some('\t'),
+        'r' => Some('\r'),
+        'b' => Some('\x08'),
+        '\\' | ']' | '^' | '-' => Some(ch),
+        _ => None,
+    }
+}
+
+// Returns the class for a class escape like `\s`.
+fn class_escape(ch: char) -> Option<Class> {
+    let named = match ch.to_ascii_lowercase() {
+        's' => Named::Blank,
+        'd' => Named::Digit,
+        'w' => Named::Word,
+        'a' => Named::Alpha,
+        'l' => Named::Lower,
+        'u' => Named::Upper,
+        'x' => Named::Hex,
+        'o' => Named::Octal,
+        'h' => Named::Head,
+        'k' => Named::Keyword,
+        _ => return None,
+    };
+    // Lower and upper are not negations of each other, so `\L` and `\U` are negated explicitly.
+    Some(Class {
+        items: vec![Item::Named(named)],
+        negated: ch.is_ascii_uppercase(),
+        newline: false,
+    })
+}
+
+// Returns the set for a `[:name:]` item.
+fn named(name: &str) -> Option<Named> {
+    let named = match name {
+        "alnum" => Named::Alnum,
+        "alpha" => Named::Alpha,
+        "blank" => Named::Blank,
+        "cntrl" => Named::Cntrl,
+        "digit" => Named::Digit,
+        "graph" => Named::Graph,
+        "lower" => Named::Lower,
+        "print" => Named::Print,
+        "punct" => Named::Punct,
+        "space" => Named::Whitespace,
+        "upper" => Named::Upper,
+        "xdigit" => Named::Hex,
+        _ => return None,
+    };
+    Some(named)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    groups: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn alternate(&mut self) -> Result<Node, Error> {
+        let mut branches = vec![self.concat()?];
+        while let Some(&Token::Alternate) = self.peek() {
+            self.pos += 1;
+            branches.push(self.concat()?);
+        }
+        if branches.len() == 1 {
+            Ok(branches.pop().unwrap())
+        } else {
+            Ok(Node::Alternate(branches))
+        }
+    }
+
+    fn concat(&mut self) -> Result<Node, Error> {
+        let mut nodes = Vec::new();
+        loop {
+            let token = match self.peek() {
+                None | Some(&Token::Alternate) | Some(&Token::Close) => break,
+                Some(token) => token.clone(),
+            };
+            self.pos += 1;
+
+            let node = match token {
+                Token::Char(ch) => Node::Char(ch),
+                Token::Any { newline } => Node::Any { newline: newline },
+                Token::Class(class) => Node::Class(class),
+                Token::Assert(assertion) => Node::Assert(assertion),
+                Token::MatchStart => Node::MatchStart,
+                Token::MatchEnd => Node::MatchEnd,
+
+                // Only special at the start or end of a branch.
+                Token::Caret if nodes.is_empty() => Node::Assert(Assertion::LineStart),
+                Token::Caret => Node::Char('^'),
+                Token::Dollar => match self.peek() {
+                    None | Some(&Token::Alternate) | Some(&Token::Close) => {
+                        Node::Assert(Assertion::LineEnd)
+                    },
+                    _ => Node::Char('$'),
+                },
+
+                Token::Open { capture } => {
+                    let index = if capture {
+                        self.groups += 1;
+                        Some(self.groups)
+                    } else {
+                        None
+                    };
+                    let node = self.alternate()?;
+                    match self.peek() {
+                        Some(&Token::Close) => self.pos += 1,
+                        _ => return Err(Error::UnmatchedParen),
+                    }
+                    Node::Group(index, Box::new(node))
+                },
+
+                // A star with nothing to repeat is literal.
+                Token::Star if nodes.is_empty() => Node::Char('*'),
+                Token::Star | Token::Repeat { .. } if nodes.is_empty() => {
+                    return Err(Error::NothingToRepeat);
+                },
+                Token::Star => repeat(nodes.pop().unwrap(), 0, None, true),
+                Token::Repeat { min, max, greedy } => {
+                    repeat(nodes.pop().unwrap(), min, max, greedy)
+                },
+
+                Token::Alternate | Token::Close => unreachable!(),
+            };
+            nodes.push(node);
+        }
+
+        match nodes.len() {
+            0 => Ok(Node::Empty),
+            1 => Ok(nodes.pop().unwrap()),
+            _ => Ok(Node::Concat(nodes)),
+        }
+    }
+}
+
+fn repeat(node: Node, min: usize, max: Option<usize>, greedy: bool) -> Node {
+    Node::Repeat {
+        node: Box::new(node),
+        min: min,
+        max: max,
+        greedy: greedy,
+    }
+}
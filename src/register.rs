@@ -0,0 +1,242 @@
+//! Registers.
+//!
+//! As in vi, registers are named by a char:
+//!
+//! - `"` is the unnamed register, which refers to the register written last.
+//! - `0` holds the last yank without a register name, and `1` to `9` the last deletes of one or
+//!   more lines, shifting down with each delete.
+//! - `-` holds the last delete within a line without a register name.
+//! - `a` to `z` are written only when named. Naming `A` to `Z` appends to them.
+//! - `.`, `%`, `:` and `/` hold the last inserted text, the file name, the last command line and
+//!   the last search pattern. They are read-only.
+//! - `_` is the black hole register, which discards what is written to it and is always empty.
+
+use std::collections::HashMap;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::string::String as StdString;
+
+use normal::Motion;
+
+/// How the content of a register is put into the text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    /// Text within and across lines.
+    Charwise,
+
+    /// Whole lines.
+    Linewise,
+
+    /// Block of columns.
+    Blockwise,
+}
+
+/// Content of a register.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Value {
+    /// Text. Linewise text has a line break after each line, and blockwise text between lines.
+    pub text: StdString,
+
+    /// How the text is put.
+    pub kind: Kind,
+}
+
+impl Value {
+    fn charwise(text: &str) -> Self {
+        Value { text: text.to_owned(), kind: Kind::Charwise }
+    }
+
+    // Appends another value, as when writing to an uppercase register.
+    //
+    // As in vi, appending lines makes the value linewise, and charwise text continues the last
+    // line of a charwise value. Other text is appended as new lines.
+    fn append(&mut self, other: Value) {
+        if other.kind == Kind::Linewise {
+            if self.kind != Kind::Linewise {
+                self.text.push('\n');
+            }
+            self.kind = Kind::Linewise;
+        } else if self.kind == Kind::Blockwise {
+            self.text.push('\n');
+        }
+        self.text.push_str(&other.text);
+        if self.kind == Kind::Linewise && other.kind != Kind::Linewise {
+            self.text.push('\n');
+        }
+    }
+}
+
+/// Error writing a register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Error {
+    /// No register has the name.
+    Invalid(char),
+
+    /// The register is read-only.
+    ReadOnly(char),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Error::Invalid(name) => write!(f, "invalid register name {:?}", name),
+            Error::ReadOnly(name) => write!(f, "register {:?} is read-only", name),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// Register store.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Registers {
+    // Writable registers by lowercase name, except the unnamed register.
+    values: HashMap<char, Value>,
+
+    // Register the unnamed register refers to.
+    unnamed: Option<char>,
+
+    last_insert: Option<StdString>,
+    file_name: Option<StdString>,
+    last_command: Option<StdString>,
+    last_search: Option<StdString>,
+}
+
+impl Registers {
+    /// Creates an empty register store.
+    pub fn new() -> Self {
+        Registers::default()
+    }
+
+    /// Returns the content of a register, or `None` if it is empty or no register has the name.
+    pub fn get(&self, name: char) -> Option<Value> {
+        let read_only = match name {
+            '"' => return self.unnamed.and_then(|name| self.get(name)),
+            '.' => &self.last_insert,
+            '%' => &self.file_name,
+            ':' => &self.last_command,
+            '/' => &self.last_search,
+            _ => return self.values.get(&name.to_ascii_lowercase()).cloned(),
+        };
+        read_only.as_ref().map(|text| Value::charwise(text))
+    }
+
+    /// Stores yanked text, as with `y`.
+    ///
+    /// Without a name, or with the unnamed register, the text is stored in register `0`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, without storing the text, if the register is read-only or no register
+    /// has the name.
+    pub fn yank(&mut self, name: Option<char>, value: Value) -> Result<(), Error> {
+        match name {
+            None | Some('"') => {
+                self.set('0', value);
+                Ok(())
+            },
+            Some(name) => self.write(name, value),
+        }
+    }
+
+    /// Stores deleted text, as with `d` and `c`.
+    ///
+    /// Deletes of lines are stored in register `1`, after shifting registers `1` to `8` into `2`
+    /// to `9`, even with a name. So are deletes over the `%`, `(`, `)`, `` ` ``, `/`, `?`, `n`,
+    /// `N`, `{` and `}` motions. Other deletes without a name, or with the unnamed register, are
+    /// stored in register `-`, as are deletes within a line over those motions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, without storing the text, if the register is read-only or no register
+    /// has the name.
+    pub fn delete(
+        &mut self,
+        name: Option<char>,
+        value: Value,
+        motion: Option<Motion>,
+    ) -> Result<(), Error> {
+        let name = match name {
+            Some('"') => None,
+            Some('_') => return Ok(()),
+            name => name,
+        };
+        if let Some(name) = name {
+            self.write(name, value.clone())?;
+        }
+
+        let lines = value.kind == Kind::Linewise || value.text.contains('\n');
+        if lines || motion.map_or(false, is_numbered_motion) {
+            for digit in (b'1'..b'9').rev() {
+                if let Some(value) = self.values.remove(&(digit as char)) {
+                    self.values.insert((digit + 1) as char, value);
+                }
+            }
+            self.set('1', value.clone());
+        }
+        if name.is_none() && !lines {
+            self.set('-', value);
+        }
+        Ok(())
+    }
+
+    /// Sets the last inserted text, which is read from register `.`.
+    pub fn set_last_insert(&mut self, text: &str) {
+        self.last_insert = Some(text.to_owned());
+    }
+
+    /// Sets the file name, which is read from register `%`.
+    pub fn set_file_name(&mut self, name: &str) {
+        self.file_name = Some(name.to_owned());
+    }
+
+    /// Sets the last command line, which is read from register `:`.
+    pub fn set_last_command(&mut self, command: &str) {
+        self.last_command = Some(command.to_owned());
+    }
+
+    /// Sets the last search pattern, which is read from register `/`.
+    pub fn set_last_search(&mut self, pattern: &str) {
+        self.last_search = Some(pattern.to_owned());
+    }
+
+    // Writes a named register.
+    fn write(&mut self, name: char, value: Value) -> Result<(), Error> {
+        match name {
+            '_' => Ok(()),
+            '.' | '%' | ':' | '/' => Err(Error::ReadOnly(name)),
+            'A'..='Z' => {
+                let name = name.to_ascii_lowercase();
+                match self.values.get_mut(&name) {
+                    Some(old) => old.append(value),
+                    None => {
+                        self.values.insert(name, value);
+                    },
+                }
+                self.unnamed = Some(name);
+                Ok(())
+            },
+            'a'..='z' | '0'..='9' | '-' => {
+                self.set(name, value);
+                Ok(())
+            },
+            _ => Err(Error::Invalid(name)),
+        }
+    }
+
+    fn set(&mut self, name: char, value: Value) {
+        self.values.insert(name, value);
+        self.unnamed = Some(name);
+    }
+}
+
+// Returns `true` if deletes over the motion are always stored in register `1`, as in vi.
+fn is_numbered_motion(motion: Motion) -> bool {
+    match motion {
+        Motion::MatchPair | Motion::SentenceForward | Motion::SentenceBackward
+        | Motion::ParagraphForward | Motion::ParagraphBackward | Motion::Search(_)
+        | Motion::SearchNext | Motion::SearchPrev => true,
+        Motion::Mark { linewise, .. } => !linewise,
+        _ => false,
+    }
+}
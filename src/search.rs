@@ -0,0 +1,239 @@
+//! Incremental search.
+//!
+//! A search session follows vi's `/` and `?` with `incsearch`: the pattern is replaced as it is
+//! typed, and each time the match nearest to where the search started becomes current. The
+//! session also counts matches and finds the matches to highlight on screen.
+
+use std::ops::Range;
+use std::string::String as StdString;
+
+use gap::String;
+use regex::{self, Regex};
+
+/// Direction of a search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Towards the end of the text, as with `/`.
+    Forward,
+
+    /// Towards the start of the text, as with `?`.
+    Backward,
+}
+
+impl Direction {
+    /// Returns the opposite direction.
+    pub fn reverse(self) -> Self {
+        match self {
+            Direction::Forward => Direction::Backward,
+            Direction::Backward => Direction::Forward,
+        }
+    }
+}
+
+/// Search options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Options {
+    /// Options for compiling patterns, including `ignorecase` and `smartcase`.
+    pub regex: regex::Options,
+
+    /// Continue from the other end of the text, like the `wrapscan` option.
+    pub wrapscan: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            regex: regex::Options::default(),
+            wrapscan: true,
+        }
+    }
+}
+
+/// Position of the current match among all matches, as in "match 3 of 17".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Count {
+    /// One-based index of the current match.
+    pub index: usize,
+
+    /// Number of matches in the text.
+    pub total: usize,
+}
+
+/// Search session.
+#[derive(Debug, Clone)]
+pub struct Search {
+    direction: Direction,
+    origin: usize,
+    options: Options,
+    pattern: StdString,
+    regex: Option<Regex>,
+    current: Option<Range<usize>>,
+    wrapped: bool,
+}
+
+impl Search {
+    /// Starts a session searching in `direction` from the cursor at `origin`, with an empty
+    /// pattern.
+    pub fn new(direction: Direction, origin: usize, options: Options) -> Self {
+        Search {
+            direction: direction,
+            origin: origin,
+            options: options,
+            pattern: StdString::new(),
+            regex: None,
+            current: None,
+            wrapped: false,
+        }
+    }
+
+    /// Returns the direction of the search.
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Returns the pattern.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns the compiled pattern, or `None` if it is empty or invalid.
+    pub fn regex(&self) -> Option<&Regex> {
+        self.regex.as_ref()
+    }
+
+    /// Replaces the pattern and searches again from the origin.
+    ///
+    /// An empty or invalid pattern matches nothing. Since a pattern is often invalid while it is
+    /// being typed, the error is only worth reporting when the search is confirmed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the origin is out of bounds of `text` or not a char boundary.
+    pub fn set_pattern(&mut self, text: &String, pattern: &str) -> Result<(), regex::Error> {
+        self.pattern = pattern.to_owned();
+        self.regex = None;
+        self.current = None;
+        self.wrapped = false;
+        if pattern.is_empty() {
+            return Ok(());
+        }
+        self.regex = Some(Regex::with_options(pattern, &self.options.regex)?);
+        let (found, wrapped) = self.search(text, self.origin, self.direction);
+        self.current = found;
+        self.wrapped = wrapped;
+        Ok(())
+    }
+
+    /// Returns the range of the current match.
+    pub fn current(&self) -> Option<Range<usize>> {
+        self.current.clone()
+    }
+
+    /// Returns `true` if finding the current match continued from the other end of the text.
+    pub fn wrapped(&self) -> bool {
+        self.wrapped
+    }
+
+    /// Moves to the next match in the direction of the search, as with `n`.
+    ///
+    /// Searches from the current match, or from the origin if there is none. If no match is
+    /// found, the current match is kept.
+    pub fn find_next(&mut self, text: &String) -> Option<Range<usize>> {
+        let direction = self.direction;
+        self.advance(text, direction)
+    }
+
+    /// Moves to the next match in the opposite direction, as with `N`.
+    pub fn find_prev(&mut self, text: &String) -> Option<Range<usize>> {
+        let direction = self.direction.reverse();
+        self.advance(text, direction)
+    }
+
+    fn advance(&mut self, text: &String, direction: Direction) -> Option<Range<usize>> {
+        let from = self.current.as_ref().map_or(self.origin, |current| current.start);
+        let (found, wrapped) = self.search(text, from, direction);
+        if found.is_some() {
+            self.current = found.clone();
+            self.wrapped = wrapped;
+        }
+        found
+    }
+
+    // Finds the nearest match starting after `from`, or before it if searching backward, and
+    // whether the search wrapped around.
+    fn search(
+        &self,
+        text: &String,
+        from: usize,
+        direction: Direction,
+    ) -> (Option<Range<usize>>, bool) {
+        let regex = match self.regex {
+            Some(ref regex) => regex,
+            None => return (None, false),
+        };
+        let haystack = text.as_str();
+        let (found, wrapped) = match direction {
+            Direction::Forward => {
+                // A match at the cursor is skipped.
+                let start = from + haystack.slice(from..).chars().next().map_or(0, char::len_utf8);
+                match regex.find_at(haystack, start) {
+                    Some(found) => (Some(found), false),
+                    None if self.options.wrapscan => (regex.find_at(haystack, 0), true),
+                    None => (None, false),
+                }
+            },
+            Direction::Backward => match regex.rfind_at(haystack, from) {
+                Some(found) => (Some(found), false),
+                None if self.options.wrapscan => (regex.rfind_at(haystack, haystack.len()), true),
+                None => (None, false),
+            },
+        };
+        let wrapped = wrapped && found.is_some();
+        (found, wrapped)
+    }
+
+    /// Counts the matches in the text and returns the position of the current match among them.
+    ///
+    /// Matches are counted from the start of the text without overlapping, and the index is the
+    /// number of those starting at or before the current match.
+    pub fn count(&self, text: &String) -> Option<Count> {
+        let (regex, current) = match (&self.regex, &self.current) {
+            (&Some(ref regex), &Some(ref current)) => (regex, current),
+            _ => return None,
+        };
+        let mut count = Count {
+            index: 0,
+            total: 0,
+        };
+        for found in regex.find_iter(text.as_str()) {
+            count.total += 1;
+            if found.start <= current.start {
+                count.index = count.total;
+            }
+        }
+        Some(count)
+    }
+
+    /// Returns the matches overlapping `range`, such as the visible part of the text, for
+    /// highlighting.
+    ///
+    /// Matches are found from the start of the line containing the start of the range, so a
+    /// match which starts on an earlier line is not included.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds of `text`.
+    pub fn visible(&self, text: &String, range: Range<usize>) -> Vec<Range<usize>> {
+        let regex = match self.regex {
+            Some(ref regex) => regex,
+            None => return Vec::new(),
+        };
+        assert!(range.start <= range.end && range.end <= text.len(), "range out of bounds");
+        let haystack = text.as_str();
+        let start = text.line_range(text.line_of(range.start)).start;
+        regex.find_iter_at(haystack, start)
+            .take_while(|found| found.start < range.end)
+            .filter(|found| found.end > range.start || found.start >= range.start)
+            .collect()
+    }
+}
@@ -0,0 +1,465 @@
+//! Text storage backends.
+//!
+//! `TextBuffer` abstracts over text storage so that the backend can be chosen per file. The gap
+//! buffer is fastest for edits clustered around a cursor, while the piece table never moves
+//! existing text, so edits far apart in large files cost the same as edits close together.
+//!
+//! Slices of a text buffer are viewed as a sequence of contiguous chunks, so code written against
+//! `TextBuffer` works with both bytes and strings, and with contiguous or fragmented storage.
+
+use std::borrow::Borrow;
+use std::iter::Cloned;
+use std::ops::Range;
+use std::slice::Iter as SliceIter;
+use std::str::CharIndices as StrCharIndices;
+use std::string::String as StdString;
+use std::vec;
+
+use gap::{self, Buffer, Slice, Str};
+use operation::Data;
+use piece;
+use range::{self, IntoRange};
+
+/// Contiguous slice types stored in text buffers, `str` or `[u8]`.
+pub trait Chunk: 'static {
+    /// Owned data type.
+    type Owned: Data + Borrow<Self>;
+
+    /// Returns the length in bytes.
+    fn len(&self) -> usize;
+
+    /// Appends a chunk to owned data.
+    fn push(owned: &mut Self::Owned, chunk: &Self);
+}
+
+impl Chunk for str {
+    type Owned = StdString;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn push(owned: &mut StdString, chunk: &str) {
+        owned.push_str(chunk);
+    }
+}
+
+impl Chunk for [u8] {
+    type Owned = Vec<u8>;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn push(owned: &mut Vec<u8>, chunk: &[u8]) {
+        owned.extend_from_slice(chunk);
+    }
+}
+
+/// Text storage.
+pub trait TextBuffer {
+    /// Contiguous slice type of the stored data.
+    type Unit: ?Sized + Chunk;
+
+    /// Returns the length in bytes.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if the buffer contains no data.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a slice of the buffer as chunks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point, or if either point is out of
+    /// bounds or not a boundary of `Unit`.
+    fn chunks<R: IntoRange>(&self, range: R) -> Chunks<Self::Unit>;
+
+    /// Replaces a slice of the buffer. Destination and source can be different lengths.
+    ///
+    /// Returns the range of written bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point, or if either point is out of
+    /// bounds or not a boundary of `Unit`.
+    fn splice<R: IntoRange>(&mut self, dest: R, src: &Self::Unit) -> Range<usize>;
+
+    /// Replaces several non-overlapping slices of the buffer.
+    ///
+    /// Destinations are all relative to the buffer before any splice, and may be given in any
+    /// order. Empty destinations at the same position are written in the order given.
+    ///
+    /// Returns the ranges of written bytes in the resulting buffer, in the order given.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any destinations overlap, or if any point is out of bounds or not a boundary of
+    /// `Unit`.
+    fn splice_many(&mut self, splices: &[(Range<usize>, &Self::Unit)]) -> Vec<Range<usize>> {
+        range::splice_disjoint(splices, |dest, src| self.splice(dest, src))
+    }
+}
+
+/// Slice of a text buffer as a sequence of non-empty contiguous chunks.
+#[derive(Debug, Clone)]
+pub struct Chunks<'a, U: ?Sized + 'a> {
+    chunks: Vec<&'a U>,
+    len: usize,
+}
+
+impl<'a, U: ?Sized + Chunk> Chunks<'a, U> {
+    /// Creates a view of chunks, skipping empty chunks.
+    pub fn new<I: IntoIterator<Item = &'a U>>(chunks: I) -> Self {
+        let chunks: Vec<_> = chunks.into_iter().filter(|chunk| chunk.len() != 0).collect();
+        Chunks {
+            len: chunks.iter().map(|chunk| chunk.len()).sum(),
+            chunks: chunks,
+        }
+    }
+
+    /// Returns an iterator over the chunks.
+    pub fn iter(&self) -> Cloned<SliceIter<&'a U>> {
+        self.chunks.iter().cloned()
+    }
+
+    /// Copies the chunks into owned data.
+    pub fn concat(&self) -> U::Owned {
+        let mut owned = U::Owned::default();
+        for chunk in &self.chunks {
+            U::push(&mut owned, chunk);
+        }
+        owned
+    }
+
+    /// Returns the length of the slice in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the slice contains no data.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of chunks.
+    pub fn count(&self) -> usize {
+        self.chunks.len()
+    }
+}
+
+impl<'a> Chunks<'a, str> {
+    /// Returns an iterator over the chars of the slice.
+    pub fn chars(&self) -> Chars<'a> {
+        Chars(self.char_indices())
+    }
+
+    /// Returns an iterator over the chars of the slice, and their positions.
+    pub fn char_indices(&self) -> CharIndices<'a> {
+        let mut offset = 0;
+        let mut chunks = Vec::with_capacity(self.chunks.len());
+        for &chunk in &self.chunks {
+            chunks.push((offset, chunk));
+            offset += chunk.len();
+        }
+        CharIndices {
+            chunks: chunks.into_iter(),
+            front: None,
+            back: None,
+        }
+    }
+}
+
+impl<'a, U: ?Sized> IntoIterator for Chunks<'a, U> {
+    type Item = &'a U;
+    type IntoIter = vec::IntoIter<&'a U>;
+
+    fn into_iter(self) -> vec::IntoIter<&'a U> {
+        self.chunks.into_iter()
+    }
+}
+
+impl<'a> PartialEq<str> for Chunks<'a, str> {
+    fn eq(&self, other: &str) -> bool {
+        if self.len != other.len() {
+            return false;
+        }
+        let mut offset = 0;
+        self.chunks.iter().all(|chunk| {
+            let eq = other.as_bytes()[offset..(offset + chunk.len())] == *chunk.as_bytes();
+            offset += chunk.len();
+            eq
+        })
+    }
+}
+
+// Necessary because only RHS is a reference.
+impl<'a, 'b> PartialEq<&'b str> for Chunks<'a, str> {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl<'a> PartialEq<[u8]> for Chunks<'a, [u8]> {
+    fn eq(&self, other: &[u8]) -> bool {
+        if self.len != other.len() {
+            return false;
+        }
+        let mut offset = 0;
+        self.chunks.iter().all(|chunk| {
+            let eq = other[offset..(offset + chunk.len())] == **chunk;
+            offset += chunk.len();
+            eq
+        })
+    }
+}
+
+// Necessary because only RHS is a reference.
+impl<'a, 'b> PartialEq<&'b [u8]> for Chunks<'a, [u8]> {
+    fn eq(&self, other: &&[u8]) -> bool {
+        self == *other
+    }
+}
+
+impl<'a> Into<StdString> for Chunks<'a, str> {
+    fn into(self) -> StdString {
+        self.concat()
+    }
+}
+
+impl<'a> Into<Vec<u8>> for Chunks<'a, [u8]> {
+    fn into(self) -> Vec<u8> {
+        self.concat()
+    }
+}
+
+/// Iterator over the chars of string chunks.
+#[derive(Debug, Clone)]
+pub struct Chars<'a>(CharIndices<'a>);
+
+impl<'a> Iterator for Chars<'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        self.0.next().map(|(_, ch)| ch)
+    }
+}
+
+impl<'a> DoubleEndedIterator for Chars<'a> {
+    fn next_back(&mut self) -> Option<char> {
+        self.0.next_back().map(|(_, ch)| ch)
+    }
+}
+
+/// Iterator over the chars of string chunks, and their byte offsets.
+///
+/// Essentially a variation of `Flatten`.
+#[derive(Debug, Clone)]
+pub struct CharIndices<'a> {
+    chunks: vec::IntoIter<(usize, &'a str)>,
+    front: Option<(usize, StrCharIndices<'a>)>,
+    back: Option<(usize, StrCharIndices<'a>)>,
+}
+
+impl<'a> Iterator for CharIndices<'a> {
+    type Item = (usize, char);
+
+    fn next(&mut self) -> Option<(usize, char)> {
+        loop {
+            if let Some((offset, ref mut iter)) = self.front {
+                if let Some((index, ch)) = iter.next() {
+                    return Some((offset + index, ch));
+                }
+            }
+            match self.chunks.next() {
+                Some((offset, chunk)) => self.front = Some((offset, chunk.char_indices())),
+                None => {
+                    let (offset, ref mut iter) = *self.back.as_mut()?;
+                    return iter.next().map(|(index, ch)| (offset + index, ch));
+                },
+            }
+        }
+    }
+}
+
+impl<'a> DoubleEndedIterator for CharIndices<'a> {
+    fn next_back(&mut self) -> Option<(usize, char)> {
+        loop {
+            if let Some((offset, ref mut iter)) = self.back {
+                if let Some((index, ch)) = iter.next_back() {
+                    return Some((offset + index, ch));
+                }
+            }
+            match self.chunks.next_back() {
+                Some((offset, chunk)) => self.back = Some((offset, chunk.char_indices())),
+                None => {
+                    let (offset, ref mut iter) = *self.front.as_mut()?;
+                    return iter.next_back().map(|(index, ch)| (offset + index, ch));
+                },
+            }
+        }
+    }
+}
+
+impl<'a> From<Slice<'a>> for Chunks<'a, [u8]> {
+    fn from(slice: Slice<'a>) -> Self {
+        match slice {
+            Slice::Contiguous(back) => Chunks::new(Some(back)),
+            Slice::Fragmented(front, back) => Chunks::new(vec![front, back]),
+        }
+    }
+}
+
+impl<'a> From<Str<'a>> for Chunks<'a, str> {
+    fn from(slice: Str<'a>) -> Self {
+        match slice {
+            Str::Contiguous(back) => Chunks::new(Some(back)),
+            Str::Fragmented(front, back) => Chunks::new(vec![front, back]),
+        }
+    }
+}
+
+impl TextBuffer for Buffer {
+    type Unit = [u8];
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn chunks<R: IntoRange>(&self, range: R) -> Chunks<[u8]> {
+        self.slice(range).into()
+    }
+
+    fn splice<R: IntoRange>(&mut self, dest: R, src: &[u8]) -> Range<usize> {
+        self.splice(dest, src)
+    }
+
+    fn splice_many(&mut self, splices: &[(Range<usize>, &[u8])]) -> Vec<Range<usize>> {
+        self.splice_many(splices)
+    }
+}
+
+impl TextBuffer for gap::String {
+    type Unit = str;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn chunks<R: IntoRange>(&self, range: R) -> Chunks<str> {
+        self.slice(range).into()
+    }
+
+    fn splice<R: IntoRange>(&mut self, dest: R, src: &str) -> Range<usize> {
+        self.splice(dest, src)
+    }
+
+    fn splice_many(&mut self, splices: &[(Range<usize>, &str)]) -> Vec<Range<usize>> {
+        self.splice_many(splices)
+    }
+}
+
+impl TextBuffer for piece::String {
+    type Unit = str;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn chunks<R: IntoRange>(&self, range: R) -> Chunks<str> {
+        self.slice(range)
+    }
+
+    fn splice<R: IntoRange>(&mut self, dest: R, src: &str) -> Range<usize> {
+        self.splice(dest, src)
+    }
+}
+
+impl TextBuffer for Vec<u8> {
+    type Unit = [u8];
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn chunks<R: IntoRange>(&self, range: R) -> Chunks<[u8]> {
+        let range = range.into_range(self.len());
+        Chunks::new(Some(&self[range]))
+    }
+
+    fn splice<R: IntoRange>(&mut self, dest: R, src: &[u8]) -> Range<usize> {
+        let dest = dest.into_range(self.len());
+        self.splice(dest.clone(), src.iter().cloned());
+        dest.start..(dest.start + src.len())
+    }
+}
+
+impl TextBuffer for StdString {
+    type Unit = str;
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn chunks<R: IntoRange>(&self, range: R) -> Chunks<str> {
+        let range = range.into_range(self.len());
+        Chunks::new(Some(&self[range]))
+    }
+
+    fn splice<R: IntoRange>(&mut self, dest: R, src: &str) -> Range<usize> {
+        let dest = dest.into_range(self.len());
+        self.replace_range(dest.clone(), src);
+        dest.start..(dest.start + src.len())
+    }
+}
+
+/// Text buffer with a backend chosen by size.
+#[derive(Debug)]
+pub enum Backend {
+    /// Gap buffer.
+    Gap(gap::String),
+
+    /// Piece table.
+    Piece(piece::String),
+}
+
+/// Length in bytes from which `Backend::from` chooses the piece table.
+pub const PIECE_THRESHOLD: usize = 64 * 1024 * 1024;
+
+/// Chooses the piece table for strings of at least `PIECE_THRESHOLD` bytes.
+impl From<StdString> for Backend {
+    fn from(string: StdString) -> Self {
+        if string.len() >= PIECE_THRESHOLD {
+            Backend::Piece(piece::String::from(string))
+        } else {
+            Backend::Gap(gap::String::from(string))
+        }
+    }
+}
+
+impl TextBuffer for Backend {
+    type Unit = str;
+
+    fn len(&self) -> usize {
+        match *self {
+            Backend::Gap(ref string) => string.len(),
+            Backend::Piece(ref string) => string.len(),
+        }
+    }
+
+    fn chunks<R: IntoRange>(&self, range: R) -> Chunks<str> {
+        match *self {
+            Backend::Gap(ref string) => TextBuffer::chunks(string, range),
+            Backend::Piece(ref string) => TextBuffer::chunks(string, range),
+        }
+    }
+
+    fn splice<R: IntoRange>(&mut self, dest: R, src: &str) -> Range<usize> {
+        match *self {
+            Backend::Gap(ref mut string) => string.splice(dest, src),
+            Backend::Piece(ref mut string) => string.splice(dest, src),
+        }
+    }
+}
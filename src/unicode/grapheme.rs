@@ -0,0 +1,109 @@
+use std::cmp::Ordering;
+
+use super::tables::GRAPHEME_BREAK;
+
+/// Grapheme cluster break property of a char.
+///
+/// As defined by UAX #29, with Extended_Pictographic as an additional value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GraphemeBreak {
+    /// Any other char.
+    Other,
+
+    /// Carriage return.
+    Cr,
+
+    /// Line feed.
+    Lf,
+
+    /// Control and separator chars.
+    Control,
+
+    /// Combining marks and other extending chars.
+    Extend,
+
+    /// Zero width joiner.
+    Zwj,
+
+    /// Regional indicator symbols, which pair into flags.
+    RegionalIndicator,
+
+    /// Prepended concatenation marks.
+    Prepend,
+
+    /// Spacing combining marks.
+    SpacingMark,
+
+    /// Hangul leading jamo.
+    L,
+
+    /// Hangul vowel jamo.
+    V,
+
+    /// Hangul trailing jamo.
+    T,
+
+    /// Hangul LV syllables.
+    Lv,
+
+    /// Hangul LVT syllables.
+    Lvt,
+
+    /// Pictographic chars such as emoji.
+    ExtendedPictographic,
+}
+
+/// Returns the grapheme cluster break property of a char.
+pub fn grapheme_break(ch: char) -> GraphemeBreak {
+    let index = GRAPHEME_BREAK.binary_search_by(|&(start, end, _)| {
+        if ch < start {
+            Ordering::Greater
+        } else if ch > end {
+            Ordering::Less
+        } else {
+            Ordering::Equal
+        }
+    });
+    match index {
+        Ok(index) => GRAPHEME_BREAK[index].2,
+        Err(_) => GraphemeBreak::Other,
+    }
+}
+
+/// Returns `true` if there is a grapheme cluster boundary before `after`.
+///
+/// `before` iterates backwards over the chars preceding `after`, and is only advanced as far as
+/// needed for context. Implements the extended grapheme cluster rules of UAX #29, except for the
+/// Indic conjunct rule GB9c.
+pub fn is_grapheme_break<I: Iterator<Item = char>>(mut before: I, after: char) -> bool {
+    use self::GraphemeBreak::*;
+
+    let prev = match before.next() {
+        Some(prev) => grapheme_break(prev),
+        None => return true,
+    };
+    match (prev, grapheme_break(after)) {
+        (Cr, Lf) => false,
+        (Cr, _) | (Lf, _) | (Control, _) => true,
+        (_, Cr) | (_, Lf) | (_, Control) => true,
+        (L, L) | (L, V) | (L, Lv) | (L, Lvt) => false,
+        (Lv, V) | (Lv, T) | (V, V) | (V, T) => false,
+        (Lvt, T) | (T, T) => false,
+        (_, Extend) | (_, Zwj) | (_, SpacingMark) => false,
+        (Prepend, _) => false,
+
+        // Emoji ZWJ sequences.
+        (Zwj, ExtendedPictographic) => {
+            let base = before.map(grapheme_break).find(|&prop| prop != Extend);
+            base != Some(ExtendedPictographic)
+        },
+
+        // Flags, paired from the start of a run of regional indicators.
+        (RegionalIndicator, RegionalIndicator) => {
+            let run = before.take_while(|&ch| grapheme_break(ch) == RegionalIndicator).count();
+            run % 2 == 1
+        },
+
+        _ => true,
+    }
+}
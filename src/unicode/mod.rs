@@ -0,0 +1,11 @@
+//! Unicode text segmentation and display width.
+//!
+//! Properties are looked up in tables generated from the Unicode character database, so no
+//! external data is needed at runtime.
+
+mod grapheme;
+mod tables;
+mod width;
+
+pub use self::grapheme::{GraphemeBreak, grapheme_break, is_grapheme_break};
+pub use self::width::char_width;
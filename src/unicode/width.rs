@@ -0,0 +1,32 @@
+use std::cmp::Ordering;
+
+use super::tables::{WIDE, ZERO_WIDTH};
+
+/// Returns the number of terminal cells occupied by a printable char.
+///
+/// Combining marks, format chars and Hangul medial and final jamo occupy no cells, East Asian wide
+/// and fullwidth chars and emoji occupy two cells, and all others occupy one. Control chars are
+/// not printable and are not handled specially.
+pub fn char_width(ch: char) -> usize {
+    if contains(ZERO_WIDTH, ch) {
+        0
+    } else if contains(WIDE, ch) {
+        2
+    } else {
+        1
+    }
+}
+
+// Returns `true` if `ch` is in one of the sorted ranges.
+fn contains(table: &[(char, char)], ch: char) -> bool {
+    let search = table.binary_search_by(|&(start, end)| {
+        if ch < start {
+            Ordering::Greater
+        } else if ch > end {
+            Ordering::Less
+        } else {
+            Ordering::Equal
+        }
+    });
+    search.is_ok()
+}
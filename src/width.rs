@@ -0,0 +1,81 @@
+//! Display width.
+//!
+//! Columns are counted in terminal cells from zero at the start of a line. As in vi, tabs advance
+//! to the next multiple of the tabstop, other control chars are displayed in caret notation such
+//! as `^A`, and C1 control chars are displayed as hex codes such as `<80>`.
+
+use gap::Str;
+use unicode;
+
+/// Returns the number of cells occupied by `ch` when displayed at column `col`.
+///
+/// Line feeds occupy no cells.
+///
+/// # Panics
+///
+/// Panics if `tabstop` is zero.
+pub fn char_width(ch: char, col: usize, tabstop: usize) -> usize {
+    assert!(tabstop > 0, "zero tabstop");
+    match ch {
+        '\n' => 0,
+        '\t' => tabstop - col % tabstop,
+        '\0'..='\x1f' | '\x7f' => 2,
+        '\u{80}'..='\u{9f}' => 4,
+        _ => unicode::char_width(ch),
+    }
+}
+
+/// Returns the display width of a slice starting at column zero.
+pub fn width(slice: Str, tabstop: usize) -> usize {
+    slice.chars().fold(0, |col, ch| col + char_width(ch, col, tabstop))
+}
+
+/// Returns the offset of the char displayed at column `col` of a slice starting at column zero,
+/// or the length of the slice if it ends before the column.
+///
+/// A char occupying several cells, such as a tab, is displayed at each of its columns. Chars
+/// occupying no cells are never returned.
+pub fn offset_at(slice: Str, col: usize, tabstop: usize) -> usize {
+    let mut start = 0;
+    for (offset, ch) in slice.char_indices() {
+        let end = start + char_width(ch, start, tabstop);
+        if end > col && end > start {
+            return offset;
+        }
+        start = end;
+    }
+    slice.len()
+}
+
+/// Column the cursor tries to stay in when moving between lines, like vi's `curswant`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Curswant {
+    /// Stay in a column.
+    Col(usize),
+
+    /// Stay at the end of the line, as after `$`.
+    End,
+}
+
+impl Curswant {
+    /// Remembers the column of `offset` in a line.
+    pub fn at(line: Str, offset: usize, tabstop: usize) -> Self {
+        Curswant::Col(width(line.slice(..offset), tabstop))
+    }
+
+    /// Returns the offset of the cursor in a line, excluding its line ending.
+    ///
+    /// The cursor is placed on the char displayed at the wanted column, or on the last grapheme
+    /// cluster if the line is shorter.
+    pub fn offset(&self, line: Str, tabstop: usize) -> usize {
+        let offset = match *self {
+            Curswant::Col(col) => offset_at(line, col, tabstop),
+            Curswant::End => line.len(),
+        };
+        if offset < line.len() {
+            offset
+        } else {
+            line.prev_grapheme_boundary(line.len())
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Word classification.
+//!
+//! Vi divides non-blank text into words of keyword chars and words of other chars, as configured
+//! by the `iskeyword` option. A WORD is any run of non-blank chars.
+
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// Class of a char for word motions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Class {
+    /// Whitespace.
+    Blank,
+
+    /// Non-blank chars which are not keyword chars.
+    Punctuation,
+
+    /// Keyword chars.
+    Keyword,
+}
+
+/// Set of keyword chars, like the `iskeyword` option.
+///
+/// Chars above 255 are keyword chars if they are alphanumeric, regardless of the set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Keyword([u64; 4]);
+
+/// The default `iskeyword` value.
+pub const DEFAULT_KEYWORD: &'static str = "@,48-57,_,192-255";
+
+impl Keyword {
+    /// Creates an empty set, like an empty `iskeyword` value.
+    ///
+    /// Use `Keyword::default()` for vi's default set.
+    pub fn empty() -> Self {
+        Keyword([0; 4])
+    }
+
+    /// Returns `true` if `ch` is a keyword char.
+    pub fn contains(&self, ch: char) -> bool {
+        let n = ch as u32;
+        if n < 256 {
+            self.0[n as usize / 64] & (1 << (n % 64)) != 0
+        } else {
+            ch.is_alphanumeric()
+        }
+    }
+
+    /// Returns the class of `ch`.
+    pub fn class(&self, ch: char) -> Class {
+        if ch.is_whitespace() {
+            Class::Blank
+        } else if self.contains(ch) {
+            Class::Keyword
+        } else {
+            Class::Punctuation
+        }
+    }
+
+    // Adds or removes a char below 256.
+    fn set(&mut self, n: u32, value: bool) {
+        let bit = 1 << (n % 64);
+        if value {
+            self.0[n as usize / 64] |= bit;
+        } else {
+            self.0[n as usize / 64] &= !bit;
+        }
+    }
+}
+
+impl Default for Keyword {
+    fn default() -> Self {
+        DEFAULT_KEYWORD.parse().unwrap()
+    }
+}
+
+/// Error parsing an `iskeyword` value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseKeywordError {
+    part: String,
+}
+
+impl Display for ParseKeywordError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "invalid iskeyword part {:?}", self.part)
+    }
+}
+
+impl error::Error for ParseKeywordError {}
+
+/// Parses a comma-separated list of chars, as in `iskeyword`.
+///
+/// Each part is a char, a decimal char code, a range of either separated by `-`, or `@` for all
+/// alphabetic chars. A part prefixed with `^` removes chars from the set.
+impl FromStr for Keyword {
+    type Err = ParseKeywordError;
+
+    fn from_str(spec: &str) -> Result<Self, ParseKeywordError> {
+        let mut keyword = Keyword::empty();
+        for part in spec.split(',') {
+            let error = || ParseKeywordError { part: part.to_owned() };
+            let (value, chars) = if part.len() > 1 && part.starts_with('^') {
+                (false, &part[1..])
+            } else {
+                (true, part)
+            };
+
+            if chars == "@" {
+                for n in (0..256).filter(|&n| (n as u8 as char).is_alphabetic()) {
+                    keyword.set(n, value);
+                }
+                continue;
+            }
+
+            // A leading `-` is the char itself rather than a separator.
+            let first = chars.chars().next().ok_or_else(&error)?.len_utf8();
+            let (start, end) = match chars[first..].find('-') {
+                Some(index) => (&chars[..(first + index)], &chars[(first + index + 1)..]),
+                None => (chars, chars),
+            };
+            let start = parse_char(start).ok_or_else(&error)?;
+            let end = parse_char(end).ok_or_else(&error)?;
+            if start > end {
+                return Err(error());
+            }
+            for n in start..(end + 1) {
+                keyword.set(n, value);
+            }
+        }
+        Ok(keyword)
+    }
+}
+
+// Parses a decimal char code or a single char below 256.
+fn parse_char(s: &str) -> Option<u32> {
+    let mut chars = s.chars();
+    let n = if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) {
+        s.parse().ok()?
+    } else {
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => ch as u32,
+            _ => return None,
+        }
+    };
+    if n < 256 {
+        Some(n)
+    } else {
+        None
+    }
+}
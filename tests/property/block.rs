@@ -0,0 +1,140 @@
+use std::string::String as StdString;
+use std::usize;
+
+use viola::block::{self, Block};
+use viola::gap::String;
+use viola::operator::{self, Options, Region};
+use viola::register::{Kind, Value};
+
+use fixture::{self, apply, with_gap};
+
+// Maps bytes to lines of different lengths with tabs and wide chars.
+fn ragged(bytes: Vec<u8>) -> StdString {
+    let alphabet = ['a', 'b', ' ', '\t', '\n', '\n', 'é', '字', 'e', '\u{301}'];
+    fixture::from_alphabet(bytes, &alphabet)
+}
+
+fn block(lines: ::std::ops::Range<usize>, cols: ::std::ops::Range<usize>) -> Block {
+    Block { lines: lines, cols: cols }
+}
+
+const INIT: &'static str = "abcdef\n\tx\nab\nabcdefgh";
+
+#[test]
+fn selections() {
+    let text = String::from(INIT);
+    assert_eq!(Block::new(&text, 1, 16, false, 8), block(0..4, 1..4));
+    assert_eq!(Block::new(&text, 16, 1, false, 8), block(0..4, 1..4));
+    assert_eq!(Block::new(&text, 1, 16, true, 8), block(0..4, 1..usize::MAX));
+    assert!(Block::new(&text, 1, 16, true, 8).is_to_end());
+    assert_eq!(Block::new(&text, 7, 1, false, 8), block(0..2, 0..8));
+    assert_eq!(Block::new(&text, 8, 8, false, 8), block(1..2, 8..9));
+    assert_eq!(Block::new(&text, 9, 9, false, 8), block(1..2, 9..10));
+    assert_eq!(Block::new(&String::from("a\n\nb"), 2, 0, false, 8), block(0..2, 0..1));
+    assert_eq!(Block::new(&String::from("字\ne\u{301}x"), 0, 4, false, 8), block(0..2, 0..2));
+
+    assert_eq!(block(0..4, 1..4).text(&text, 8), "bcd\n   \nb\nbcd");
+    assert_eq!(block(1..3, 4..usize::MAX).text(&text, 8), "    x\n");
+    assert_eq!(block(0..1, 2..4).text(&String::from("a字字b"), 8), "  ");
+    assert_eq!(block(0..1, 1..4).text(&String::from("a字字b"), 8), "字 ");
+}
+
+#[test]
+fn insert() {
+    let text = String::from(INIT);
+    let edit = block::insert(&text, &block(0..4, 1..4), "X", 8);
+    assert_eq!(apply(INIT, &edit), "aXbcdef\n X       x\naXb\naXbcdefgh");
+    assert_eq!(edit.cursor, 1);
+    assert_eq!(edit.yanked, None);
+    let edit = block::insert(&text, &block(0..4, 1..4), "X\nY", 8);
+    assert_eq!(apply(INIT, &edit), "aX\nYbcdef\n\tx\nab\nabcdefgh");
+
+    let init = "abc\n\nabc";
+    let text = String::from(init);
+    assert_eq!(apply(init, &block::insert(&text, &block(0..3, 2..3), "X", 8)), "abXc\n\nabXc");
+    let edit = block::insert(&text, &block(1..3, 2..3), "X", 8);
+    assert_eq!(apply(init, &edit), "abc\nX\nabXc");
+    assert_eq!(edit.cursor, 4);
+}
+
+#[test]
+fn append() {
+    let text = String::from(INIT);
+    let edit = block::append(&text, &block(0..4, 1..4), "X", 8);
+    assert_eq!(apply(INIT, &edit), "abcdXef\n    X    x\nab  X\nabcdXefgh");
+    assert_eq!(edit.cursor, 4);
+    let edit = block::append(&text, &block(0..3, 1..usize::MAX), "X", 8);
+    assert_eq!(apply(INIT, &edit), "abcdefX\n\txX\nabX\nabcdefgh");
+    assert_eq!(edit.cursor, 6);
+    let edit = block::append(&text, &block(1..3, 1..usize::MAX), "X\n", 8);
+    assert_eq!(apply(INIT, &edit), "abcdef\n\txX\n\nab\nabcdefgh");
+}
+
+#[test]
+fn change() {
+    let text = String::from(INIT);
+    let edit = block::change(&text, &block(0..4, 1..4), "X", 8);
+    assert_eq!(apply(INIT, &edit), "aXef\n X    x\naX\naXefgh");
+    assert_eq!(edit.cursor, 1);
+    assert_eq!(edit.yanked, Some(Value {
+        text: "bcd\n   \nb\nbcd".to_owned(),
+        kind: Kind::Blockwise,
+    }));
+    let edit = block::change(&text, &block(0..4, 1..4), "X\nY", 8);
+    assert_eq!(apply(INIT, &edit), "aX\nYef\n     x\na\naefgh");
+    let edit = block::change(&text, &block(0..4, 2..usize::MAX), "", 8);
+    assert_eq!(apply(INIT, &edit), "ab\n  \nab\nab");
+
+    let init = "abc\n\nabc";
+    let text = String::from(init);
+    assert_eq!(apply(init, &block::change(&text, &block(0..3, 1..2), "X", 8)), "aXc\n\naXc");
+    let edit = block::change(&text, &block(1..3, 1..2), "X", 8);
+    assert_eq!(apply(init, &edit), "abc\nX\naXc");
+    assert_eq!(edit.cursor, 4);
+}
+
+#[test]
+fn yank_delete_put() {
+    let text = String::from(INIT);
+    let options = Options::default();
+    let region = Region::Block(block(0..4, 1..4));
+    let yanked = operator::yank(&text, 0, &region, &options).yanked.unwrap();
+    let deleted = operator::delete(&text, &region, &options);
+    let result = apply(INIT, &deleted);
+    assert_eq!(result, "aef\n     x\na\naefgh");
+    assert_eq!(deleted.yanked.as_ref(), Some(&yanked));
+
+    let text = String::from(&result[..]);
+    let put = operator::put(&text, 0, &yanked, 1, false, &options);
+    assert_eq!(apply(&result, &put), "abcdef\n        x\nab\nabcdefgh");
+    let put = operator::put(&text, 0, &yanked, 1, true, &options);
+    assert_eq!(apply(&result, &put), "bcdaef\n        x\nb  a\nbcdaefgh");
+}
+
+#[quickcheck]
+fn revertible(bytes: Vec<u8>, gap: usize, corners: (usize, usize), to_end: bool) -> bool {
+    let init = ragged(bytes);
+    let boundaries: Vec<_> = (0..(init.len() + 1)).filter(|&i| init.is_char_boundary(i)).collect();
+    let gap = boundaries[gap % boundaries.len()];
+    let (contiguous, fragmented) = (String::from(&init[..]), with_gap(&init, gap));
+    let (anchor, cursor) = (corners.0 % boundaries.len(), corners.1 % boundaries.len());
+    let (anchor, cursor) = (boundaries[anchor], boundaries[cursor]);
+    let block = Block::new(&contiguous, anchor, cursor, to_end, 4);
+    let options = Options { tabstop: 4, ..Options::default() };
+    let region = Region::Block(block.clone());
+    let edits = |text: &String| {
+        let yanked = operator::yank(text, cursor, &region, &options).yanked.unwrap();
+        vec![
+            block::insert(text, &block, "xy", 4),
+            block::append(text, &block, "字", 4),
+            block::change(text, &block, "\t", 4),
+            operator::delete(text, &region, &options),
+            operator::put(text, cursor, &yanked, 2, false, &options),
+        ]
+    };
+    let edits = (edits(&contiguous), edits(&fragmented));
+    edits.0 == edits.1 && edits.0.iter().all(|edit| {
+        let result = apply(&init, edit);
+        edit.cursor <= result.len() && result.is_char_boundary(edit.cursor)
+    })
+}
@@ -0,0 +1,134 @@
+use std::ops::Range;
+use std::string::String as StdString;
+
+use quickcheck::TestResult;
+use viola::ex::{Target, global};
+use viola::gap::String;
+use viola::history::{History, Tree};
+use viola::operation::Operation;
+use viola::regex::Regex;
+
+// Returns the range of a line including its line break, or the line break before it if it is the
+// last line.
+fn whole_line(text: &String, line: usize) -> Range<usize> {
+    let range = text.line_range(line);
+    if range.end < text.len() {
+        range.start..(range.end + 1)
+    } else if line > 0 {
+        (range.start - 1)..range.end
+    } else {
+        range
+    }
+}
+
+fn splice<T: Target>(target: &mut T, dest: Range<usize>, src: &str) {
+    target.apply(&Operation { dest: dest, src: src.to_owned() });
+}
+
+// Commands given the current line.
+fn delete<T: Target>(target: &mut T, line: usize) -> Result<(), ()> {
+    let dest = whole_line(target.text(), line);
+    splice(target, dest, "");
+    Ok(())
+}
+
+fn join<T: Target>(target: &mut T, line: usize) -> Result<(), ()> {
+    let end = target.text().line_range(line).end;
+    if end < target.text().len() {
+        splice(target, end..(end + 1), " ");
+    }
+    Ok(())
+}
+
+fn copy<T: Target>(target: &mut T, line: usize) -> Result<(), ()> {
+    let range = target.text().line_range(line);
+    let copy = format!("\n{}", target.text().slice(range.clone()));
+    splice(target, range.end..range.end, &copy);
+    Ok(())
+}
+
+fn move_to_top<T: Target>(target: &mut T, line: usize) -> Result<(), ()> {
+    let range = target.text().line_range(line);
+    let moved = format!("{}\n", target.text().slice(range));
+    delete(target, line)?;
+    splice(target, 0..0, &moved);
+    Ok(())
+}
+
+fn run<F>(init: &str, pattern: &str, invert: bool, command: F) -> StdString
+where F: FnMut(&mut History<String>, usize) -> Result<(), ()> {
+    let mut history = History::new(String::from(init));
+    let lines = 0..history.get_ref().line_count();
+    global(&mut history, lines, &Regex::new(pattern).unwrap(), invert, 0, command).unwrap();
+    let result = history.get_ref().to_string();
+    history.undo();
+    assert_eq!(history.get_ref().to_string(), init);
+    assert!(!history.can_undo());
+    result
+}
+
+#[test]
+fn idioms() {
+    assert_eq!(run("a1\nb\na2\nc", "a", false, delete), "b\nc");
+    assert_eq!(run("a1\nb\na2\nc", "a", true, delete), "a1\na2");
+    assert_eq!(run("1\n2\n3\n4\n5", "^", false, join), "1 2\n3 4\n5");
+    assert_eq!(run("1\n2\n3", "^", false, copy), "1\n1\n2\n2\n3\n3");
+    assert_eq!(run("1\n2\n3\n", "^", false, move_to_top), "\n3\n2\n1");
+    assert_eq!(run("a\na\nb\na", "a", false, |target, line| {
+        let next = line + 1;
+        if next < target.text().line_count() {
+            delete(target, next)?;
+        }
+        Ok(())
+    }), "a\nb\na");
+}
+
+#[test]
+fn appends_to_moved_lines() {
+    let append = |target: &mut History<String>, line: usize| {
+        let end = target.text().line_range(line).end;
+        splice(target, end..end, ";");
+        splice(target, 0..0, "new\n");
+        Ok(())
+    };
+    assert_eq!(run("x\ny\nx", "x", false, append), "new\nnew\nx;\ny\nx;");
+}
+
+#[test]
+fn ranges_and_errors() {
+    let mut tree = Tree::new(String::from("a\na\na\na"));
+    let regex = Regex::new("a").unwrap();
+    let mut lines = Vec::new();
+    let count = global(&mut tree, 1..3, &regex, false, 0, |_, line| {
+        lines.push(line);
+        Ok::<(), ()>(())
+    });
+    assert_eq!((count, lines), (Ok(2), vec![1, 2]));
+    assert!(!tree.can_undo());
+
+    let result = global(&mut tree, 0..4, &regex, false, 0, |tree, line| {
+        if line == 2 {
+            return Err("stop");
+        }
+        let start = tree.text().line_range(line).start;
+        splice(tree, start..start, "b");
+        Ok(())
+    });
+    assert_eq!(result, Err("stop"));
+    assert_eq!(tree.get_ref().to_string(), "ba\nba\na\na");
+    assert_eq!(tree.undo(), Some(0));
+    assert_eq!(tree.get_ref().to_string(), "a\na\na\na");
+
+    let matched = global(&mut tree, 0..4, &Regex::new("b").unwrap(), false, 0, |_, _| Err(()));
+    assert_eq!(matched, Ok(0));
+}
+
+#[quickcheck]
+fn delete_matching(lines: Vec<StdString>, invert: bool) -> TestResult {
+    if lines.is_empty() || lines.iter().any(|line| line.contains('\n')) {
+        return TestResult::discard();
+    }
+    let init = lines.join("\n");
+    let kept: Vec<_> = lines.iter().filter(|line| line.contains('a') == invert).cloned().collect();
+    TestResult::from_bool(run(&init, "a", invert, delete) == kept.join("\n"))
+}
@@ -0,0 +1,3 @@
+mod global;
+mod parse;
+mod substitute;
@@ -0,0 +1,146 @@
+use viola::ex::{Address, Arg, Base, Command, ErrorKind, Flags, LineRange, Name, ParseError};
+use viola::ex::{Substitute, parse};
+use viola::search::Direction;
+
+fn address(base: Base, offset: isize) -> Address {
+    Address { base: base, offset: offset }
+}
+
+fn command(range: Option<LineRange>, name: Name, bang: bool, arg: Arg) -> Command {
+    Command { range: range, name: name, bang: bang, arg: arg }
+}
+
+fn error(line: &str) -> (usize, ErrorKind) {
+    let error = parse(line).unwrap_err();
+    (error.column, error.kind)
+}
+
+#[test]
+fn addresses() {
+    let range = |line: &str| parse(line).unwrap().remove(0).range.unwrap();
+    assert_eq!(range(":."), LineRange {
+        start: address(Base::Current, 0),
+        end: None,
+        set_current: false,
+    });
+    assert_eq!(range("%").end, Some(address(Base::Last, 0)));
+    assert_eq!(range("3,$-2").start, address(Base::Line(3), 0));
+    assert_eq!(range("3,$-2").end, Some(address(Base::Last, -2)));
+    assert_eq!(range("'a;'b+").set_current, true);
+    assert_eq!(range("'a;'b+").end, Some(address(Base::Mark('b'), 1)));
+    assert_eq!(range("+3").start, address(Base::Current, 3));
+    assert_eq!(range("-").start, address(Base::Current, -1));
+    assert_eq!(range(".5").start, address(Base::Current, 5));
+    assert_eq!(range("0").start, address(Base::Line(0), 0));
+    assert_eq!(range("/a\\/b/+1").start,
+               address(Base::Search("a\\/b".to_owned(), Direction::Forward), 1));
+    assert_eq!(range("?x?--").start,
+               address(Base::Search("x".to_owned(), Direction::Backward), -2));
+    assert_eq!(range(",5").start, address(Base::Current, 0));
+    assert_eq!(range("5,").end, Some(address(Base::Current, 0)));
+    assert_eq!(range("1,2,3").start, address(Base::Line(2), 0));
+    assert_eq!(parse("5").unwrap(), [command(Some(range("5")), Name::Goto, false, Arg::None)]);
+}
+
+#[test]
+fn names() {
+    let name = |line: &str| parse(line).unwrap()[0].name;
+    assert_eq!(name("w"), Name::Write);
+    assert_eq!(name("wri"), Name::Write);
+    assert_eq!(name("wq"), Name::WriteQuit);
+    assert_eq!(name("s"), Name::Substitute);
+    assert_eq!(name("se"), Name::Set);
+    assert_eq!(name("d"), Name::Delete);
+    assert_eq!(name("m0"), Name::Move);
+    assert_eq!(name("ma a"), Name::Mark);
+    assert_eq!(name("t."), Name::Copy);
+    assert_eq!(name("co$"), Name::Copy);
+    assert_eq!(name("norm x"), Name::Normal);
+    assert_eq!(name("normal! x"), Name::Normal);
+    assert_eq!(name("pu"), Name::Put);
+    assert_eq!(name("p"), Name::Print);
+    assert_eq!(name("re"), Name::Read);
+    assert_eq!(name("red"), Name::Redo);
+    assert_eq!(name("v/a/d"), Name::VGlobal);
+    assert_eq!(name(">"), Name::ShiftRight);
+    assert_eq!(error("no x"), (0, ErrorKind::UnknownCommand("no".to_owned())));
+    assert_eq!(error("1,2foo"), (3, ErrorKind::UnknownCommand("foo".to_owned())));
+    assert_eq!(name("copy2"), Name::Copy);
+}
+
+#[test]
+fn arguments() {
+    let first = |line: &str| parse(line).unwrap().remove(0);
+    assert_eq!(first("w! foo\\|bar.txt  "),
+               command(None, Name::Write, true, Arg::Text("foo|bar.txt".to_owned())));
+    assert_eq!(first("q!"), command(None, Name::Quit, true, Arg::None));
+    assert_eq!(first("m-2").arg, Arg::Address(address(Base::Current, -2)));
+    assert_eq!(first("d x 3").arg, Arg::Register { register: Some('x'), count: Some(3) });
+    assert_eq!(first("y 2").arg, Arg::Register { register: None, count: Some(2) });
+    assert_eq!(first("d").arg, Arg::None);
+    assert_eq!(first(">>>").arg, Arg::Shift { shifts: 3, count: None });
+    assert_eq!(first(">> 3").arg, Arg::Shift { shifts: 2, count: Some(3) });
+    assert_eq!(first("<").arg, Arg::Shift { shifts: 1, count: None });
+    assert_eq!(first("j 4").arg, Arg::Count(4));
+    assert_eq!(first("ka").arg, Arg::Mark('a'));
+    assert_eq!(first("s#a/b#c\\#d#gI").arg, Arg::Substitute(Substitute {
+        pattern: Some("a/b".to_owned()),
+        replacement: Some("c\\#d".to_owned()),
+        flags: Flags { global: true, ignore_case: Some(false), ..Flags::default() },
+    }));
+    assert_eq!(first("s/a").arg, Arg::Substitute(Substitute {
+        pattern: Some("a".to_owned()),
+        replacement: Some("".to_owned()),
+        flags: Flags::default(),
+    }));
+    assert_eq!(first("&g").arg, Arg::Substitute(Substitute {
+        pattern: None,
+        replacement: None,
+        flags: Flags { global: true, ..Flags::default() },
+    }));
+    assert_eq!(first("g!/x/").bang, true);
+    assert_eq!(first("g!/x/").arg, Arg::Global {
+        pattern: "x".to_owned(),
+        command: "p".to_owned(),
+    });
+    assert_eq!(first("!ls | wc").arg, Arg::Text("ls | wc".to_owned()));
+}
+
+#[test]
+fn chaining() {
+    let names = |line: &str| -> Vec<Name> {
+        parse(line).unwrap().into_iter().map(|command| command.name).collect()
+    };
+    assert_eq!(names("w | q"), [Name::Write, Name::Quit]);
+    assert_eq!(names(":s/a|b/c/|d"), [Name::Substitute, Name::Delete]);
+    assert_eq!(names("|| :: d |"), [Name::Delete]);
+    assert_eq!(names("g/a/s/b/c/ | d"), [Name::Global]);
+    assert_eq!(names("norm! A|x"), [Name::Normal]);
+    assert!(names("").is_empty());
+}
+
+#[test]
+fn errors() {
+    assert_eq!(error("'"), (1, ErrorKind::InvalidMark));
+    assert_eq!(error("1,'!d"), (3, ErrorKind::InvalidMark));
+    assert_eq!(error("99999999999999999999"), (0, ErrorKind::NumberTooLarge));
+    assert_eq!(error("d | m"), (5, ErrorKind::MissingAddress));
+    assert_eq!(error("norm"), (4, ErrorKind::MissingArgument));
+    assert_eq!(error("sxaxbx"), (0, ErrorKind::UnknownCommand("sxaxbx".to_owned())));
+    assert_eq!(error("s\\a\\b\\"), (1, ErrorKind::InvalidDelimiter));
+    assert_eq!(error("s/a/b/gx"), (7, ErrorKind::InvalidFlag('x')));
+    assert_eq!(error("1q"), (1, ErrorKind::NoRangeAllowed));
+    assert_eq!(error("d!"), (1, ErrorKind::NoBangAllowed));
+    assert_eq!(error("é|q x"), (0, ErrorKind::UnknownCommand("é".to_owned())));
+    assert_eq!(error("w|q x"), (4, ErrorKind::TrailingCharacters));
+    let error = ParseError { column: 4, kind: ErrorKind::TrailingCharacters };
+    assert_eq!(error.to_string(), "trailing characters at column 5");
+}
+
+#[quickcheck]
+fn columns_in_bounds(line: String) -> bool {
+    match parse(&line) {
+        Ok(_) => true,
+        Err(error) => error.column <= line.chars().count(),
+    }
+}
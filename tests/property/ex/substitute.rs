@@ -0,0 +1,127 @@
+use std::string::String as StdString;
+
+use quickcheck::TestResult;
+use viola::ex::{Confirm, Flags, Template, substitute};
+use viola::gap::String;
+use viola::history::History;
+use viola::operation::Operate;
+use viola::regex::{self, Regex};
+
+// Runs `:s` over all lines, answering `y` to any confirmation.
+fn run(init: &str, pattern: &str, replacement: &str, flags: &str) -> StdString {
+    let flags: Flags = flags.parse().unwrap();
+    let regex = Regex::with_options(pattern, &flags.regex_options(&regex::Options::default()));
+    let mut text = String::from(init);
+    let lines = 0..text.line_count();
+    let template = Template::new(replacement, true);
+    let substitution = substitute(&text, lines, &regex.unwrap(), &template, &flags, |_| {
+        Confirm::Yes
+    });
+    text.apply_many(&substitution.operations);
+    text.to_string()
+}
+
+#[test]
+fn first_and_global() {
+    assert_eq!(run("aa\naa", "a", "b", ""), "ba\nba");
+    assert_eq!(run("aa\naa", "a", "b", "g"), "bb\nbb");
+    assert_eq!(run("aa\naa", "a", "b", "gg"), "ba\nba");
+    assert_eq!(run("abxc", "x*", "-", "g"), "-a-b-c-");
+    assert_eq!(run("a\nb\n", "$", ";", ""), "a;\nb;\n;");
+    assert_eq!(run("a\nb\nc", "a\\nb\\n", "", "g"), "c");
+    assert_eq!(run("one two", "\\<\\w", "\\u&", "g"), "One Two");
+    assert_eq!(run("x\ny\nz\n\nyy", "y", "-", ""), "x\n-\nz\n\n-y");
+}
+
+#[test]
+fn templates() {
+    assert_eq!(run("john smith", "\\(\\w\\+\\) \\(\\w\\+\\)", "\\2, \\1", ""), "smith, john");
+    assert_eq!(run("abc", "b", "[&][\\&][\\0]", ""), "a[b][&][b]c");
+    assert_eq!(run("abc", "b", "\\r\\t\\n\\\\", ""), "a\n\t\0\\c");
+    assert_eq!(run("hello world", ".*", "\\U&", ""), "HELLO WORLD");
+    assert_eq!(run("hello world", "\\w\\+", "\\u\\L&X", "g"), "Hellox Worldx");
+    assert_eq!(run("Hello World", "\\w\\+", "\\U&\\e!", "g"), "HELLO! WORLD!");
+    assert_eq!(run("hello", "\\(x\\)\\=h", "\\1~", ""), "~ello");
+
+    let text = String::from("a&b");
+    let regex = Regex::new("b").unwrap();
+    let captures = regex.captures_at(text.as_str(), 0).unwrap();
+    assert_eq!(Template::new("&\\&", false).expand(text.as_str(), &captures), "&b");
+}
+
+#[test]
+fn flags() {
+    assert_eq!(run("Aa", "a", "b", "g"), "Ab");
+    assert_eq!(run("Aa", "a", "b", "gi"), "bb");
+    assert_eq!(run("Aa", "a\\c", "b", "gI"), "bb");
+    let ignore = regex::Options { ignore_case: true, ..Default::default() };
+    assert!(!"I".parse::<Flags>().unwrap().regex_options(&ignore).ignore_case);
+    assert!("gx".parse::<Flags>().is_err());
+
+    let text = String::from("aa\nb\naxa");
+    let regex = Regex::new("a").unwrap();
+    let template = Template::new("b", true);
+    let flags = "gn".parse().unwrap();
+    let substitution = substitute(&text, 0..3, &regex, &template, &flags, |_| Confirm::Yes);
+    assert!(substitution.operations.is_empty());
+    assert_eq!((substitution.count, substitution.lines, substitution.last_line), (4, 2, Some(2)));
+
+    let substitution = substitute(&text, 1..2, &regex, &template, &flags, |_| Confirm::Yes);
+    assert_eq!((substitution.count, substitution.lines, substitution.last_line), (0, 0, None));
+}
+
+#[test]
+fn confirm() {
+    let text = String::from("aaa\naaa\naaa");
+    let regex = Regex::new("a").unwrap();
+    let template = Template::new("b", true);
+    let run = |flags: &str, answers: &[Confirm]| {
+        let mut answers = answers.iter();
+        let mut asked = Vec::new();
+        let substitution = substitute(&text, 0..3, &regex, &template, &flags.parse().unwrap(),
+            |found| {
+                asked.push(found.start);
+                *answers.next().unwrap()
+            });
+        let mut result = String::from(&text.to_string()[..]);
+        result.apply_many(&substitution.operations);
+        (result.to_string(), asked)
+    };
+
+    let (yes, no, all, last, quit) =
+        (Confirm::Yes, Confirm::No, Confirm::All, Confirm::Last, Confirm::Quit);
+    assert_eq!(run("gc", &[yes, no, yes, quit]), ("bab\naaa\naaa".to_owned(), vec![0, 1, 2, 4]));
+    assert_eq!(run("gc", &[no, no, last]), ("aab\naaa\naaa".to_owned(), vec![0, 1, 2]));
+    assert_eq!(run("gc", &[no, all]), ("abb\nbbb\nbbb".to_owned(), vec![0, 1]));
+    assert_eq!(run("c", &[no, yes, yes]), ("aaa\nbaa\nbaa".to_owned(), vec![0, 4, 8]));
+    assert_eq!(run("g", &[]), ("bbb\nbbb\nbbb".to_owned(), vec![]));
+}
+
+#[test]
+fn undo() {
+    let init = "foo bar\nbar foo\nfoo";
+    let mut history = History::new(String::from(init));
+    let regex = Regex::new("foo").unwrap();
+    let template = Template::new("baz", true);
+    let flags = "g".parse().unwrap();
+    let substitution = substitute(history.get_ref(), 0..3, &regex, &template, &flags, |_| {
+        Confirm::Yes
+    });
+    history.apply_many(&substitution.operations);
+    assert_eq!(history.get_ref().to_string(), "baz bar\nbar baz\nbaz");
+    assert_eq!(history.undo(), Some(0));
+    assert_eq!(history.get_ref().to_string(), init);
+    assert!(!history.can_undo());
+}
+
+#[quickcheck]
+fn literal(init: StdString, needle: StdString, replacement: StdString) -> TestResult {
+    if needle.is_empty() || needle.contains('\n') {
+        return TestResult::discard();
+    }
+    let escape = |s: &str| s.replace('\\', "\\\\");
+    let pattern = format!("\\V{}", escape(&needle));
+    let template = escape(&replacement).replace('&', "\\&").replace('\n', "\\r");
+    let expected = init.replace(&needle[..], &replacement);
+    TestResult::from_bool(run(&init, &pattern, &template, "g") == expected)
+}
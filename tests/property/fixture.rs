@@ -0,0 +1,29 @@
+use std::iter::FromIterator;
+use std::string::String as StdString;
+
+use viola::gap::String;
+use viola::operation::Operate;
+use viola::operator::Edit;
+
+// Returns a gap string of `init` with the gap at `gap`.
+pub fn with_gap(init: &str, gap: usize) -> String {
+    let mut buf = String::from(init);
+    buf.splice(gap..gap, "");
+    buf
+}
+
+// Maps bytes to text made of the items of an alphabet.
+pub fn from_alphabet<T: Copy>(bytes: Vec<u8>, alphabet: &[T]) -> StdString
+where StdString: FromIterator<T> {
+    bytes.into_iter().map(|b| alphabet[b as usize % alphabet.len()]).collect()
+}
+
+// Applies the changes of an edit, checking that reverting them restores the text.
+pub fn apply(init: &str, edit: &Edit) -> StdString {
+    let mut text = String::from(init);
+    let reverts = text.apply_many(&edit.operations);
+    let result = text.to_string();
+    text.apply_many(&reverts);
+    assert_eq!(text.to_string(), init);
+    result
+}
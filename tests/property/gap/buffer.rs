@@ -1,7 +1,10 @@
+use std::ops::Range;
+
 use quickcheck::TestResult;
-use viola::gap::Buffer;
+use viola::gap::{Buffer, Gravity};
+use viola::range::IntoRange;
 
-use super::{SliceRange, Splice};
+use super::{SliceRange, Splice, hash};
 
 macro_rules! discard {
     ($range:expr, $slice:expr) => {
@@ -148,3 +151,153 @@ fn splice_slice_into_vec(
     discard!(range, &vec);
     TestResult::from_bool(buf.slice(range).into(): Vec<u8> == &vec[range])
 }
+
+#[quickcheck]
+fn splice_hash(init: Vec<u8>, dest: SliceRange, src: Vec<u8>) -> TestResult {
+    discard!(dest, &init);
+    let mut std = init.clone();
+    let mut buf = Buffer::from(&init[..]);
+
+    Splice::splice(&mut std, dest, &src);
+    Splice::splice(&mut buf, dest, &src);
+
+    TestResult::from_bool(hash(&buf) == hash(&std[..]) && hash(&buf.slice(..)) == hash(&std[..]))
+}
+
+// Returns the expected offset of an anchor after a splice.
+fn track(offset: usize, gravity: Gravity, dest: Range<usize>, src_len: usize) -> usize {
+    if offset < dest.start {
+        offset
+    } else if offset > dest.end || (offset == dest.end && dest.start < dest.end) {
+        offset - dest.len() + src_len
+    } else if gravity == Gravity::Left {
+        dest.start
+    } else {
+        dest.start + src_len
+    }
+}
+
+#[quickcheck]
+fn splice_anchors(
+    init: Vec<u8>,
+    anchors: Vec<(usize, bool)>,
+    splices: Vec<(SliceRange, Vec<u8>)>,
+    removes: Vec<usize>,
+) -> bool {
+    let mut vec = init.clone();
+    let mut buf = Buffer::from(init);
+    let mut expected: Vec<_> = anchors.into_iter()
+        .filter(|&(offset, _)| offset <= vec.len())
+        .map(|(offset, left)| {
+            let gravity = if left { Gravity::Left } else { Gravity::Right };
+            (buf.anchor(offset, gravity), offset, gravity)
+        })
+        .collect();
+
+    for (dest, src) in splices {
+        if !dest.valid_slice(&vec) {
+            continue;
+        }
+        Splice::splice(&mut vec, dest, &src);
+        Splice::splice(&mut buf, dest, &src);
+        for &mut (_, ref mut offset, gravity) in &mut expected {
+            *offset = track(*offset, gravity, dest.into_range(0), src.len());
+        }
+    }
+
+    for remove in removes {
+        if remove >= expected.len() {
+            continue;
+        }
+        let (anchor, offset, _) = expected.remove(remove);
+        if buf.remove_anchor(anchor) != Some(offset) || buf.anchor_offset(anchor).is_some() {
+            return false;
+        }
+    }
+
+    expected.into_iter().all(|(anchor, offset, _)| buf.anchor_offset(anchor) == Some(offset))
+}
+
+#[quickcheck]
+fn splice_remove_anchors(
+    init: Vec<u8>,
+    steps: Vec<(usize, bool, usize, SliceRange, Vec<u8>)>,
+) -> bool {
+    let mut vec = init.clone();
+    let mut buf = Buffer::from(init);
+    let mut expected = Vec::new();
+
+    for (offset, left, remove, dest, src) in steps {
+        if offset <= vec.len() {
+            let gravity = if left { Gravity::Left } else { Gravity::Right };
+            expected.push((buf.anchor(offset, gravity), offset, gravity));
+        }
+        if remove < expected.len() {
+            let (anchor, offset, _) = expected.remove(remove);
+            if buf.remove_anchor(anchor) != Some(offset) || buf.remove_anchor(anchor).is_some() {
+                return false;
+            }
+        }
+        if dest.valid_slice(&vec) {
+            Splice::splice(&mut vec, dest, &src);
+            Splice::splice(&mut buf, dest, &src);
+            for &mut (_, ref mut offset, gravity) in &mut expected {
+                *offset = track(*offset, gravity, dest.into_range(0), src.len());
+            }
+        }
+    }
+
+    expected.into_iter().all(|(anchor, offset, _)| buf.anchor_offset(anchor) == Some(offset))
+}
+
+#[quickcheck]
+fn search(haystack: Vec<u8>, needle: Vec<u8>, gap: usize) -> TestResult {
+    let haystack: Vec<u8> = haystack.into_iter().map(|b| b % 2).collect();
+    let needle: Vec<u8> = needle.into_iter().map(|b| b % 2).collect();
+    if gap > haystack.len() {
+        return TestResult::discard();
+    }
+    let mut buf = Buffer::from(haystack.clone());
+    buf.splice(gap..gap, &[]);
+
+    let mut expected = Vec::new();
+    let mut position = 0;
+    while position + needle.len() <= haystack.len() {
+        if haystack[position..].starts_with(&needle) {
+            expected.push(position..(position + needle.len()));
+            position += needle.len().max(1);
+        } else {
+            position += 1;
+        }
+    }
+    let matches: Vec<_> = buf.as_slice().match_indices(&needle).collect();
+    let rfind = (0..(haystack.len() + 1)).rev()
+        .find(|&i| haystack[i..].starts_with(&needle));
+    TestResult::from_bool(matches == expected && buf.as_slice().rfind(&needle) == rfind)
+}
+
+#[quickcheck]
+fn search_range(haystack: Vec<u8>, needle: Vec<u8>, gap: usize, range: SliceRange) -> TestResult {
+    let haystack: Vec<u8> = haystack.into_iter().map(|b| b % 2).collect();
+    let needle: Vec<u8> = needle.into_iter().map(|b| b % 2).collect();
+    discard!(range, &haystack);
+    if gap > haystack.len() {
+        return TestResult::discard();
+    }
+    let mut buf = Buffer::from(haystack.clone());
+    buf.splice(gap..gap, &[]);
+
+    let start = range.into_range(haystack.len()).start;
+    let slice = buf.slice(range);
+    let shift = |matches: Vec<Range<usize>>| -> Vec<Range<usize>> {
+        matches.into_iter().map(|m| (m.start + start)..(m.end + start)).collect()
+    };
+    TestResult::from_bool(
+        buf.find(range, &needle) == slice.find(&needle).map(|i| i + start)
+            && buf.rfind(range, &needle) == slice.rfind(&needle).map(|i| i + start)
+            && buf.match_indices(range, &needle).collect::<Vec<_>>()
+                == shift(slice.match_indices(&needle).collect())
+            && buf.rmatch_indices(range, &needle).collect::<Vec<_>>()
+                == shift(slice.rmatch_indices(&needle).collect())
+    )
+}
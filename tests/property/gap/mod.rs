@@ -1,6 +1,8 @@
 mod buffer;
-mod string;
+pub mod string;
 
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::ops::{Index, Range};
 use std::string::String as StdString;
 
@@ -11,17 +13,17 @@ use viola::range::IntoRange;
 // A `Range<usize>` where start <= end and `Copy` is implemented. Helps to generate more valid
 // slice/splice operations.
 #[derive(Debug, Clone, Copy)]
-struct SliceRange {
+pub struct SliceRange {
     start: usize,
     end: usize,
 }
 
 impl SliceRange {
-    fn valid_slice(&self, slice: &[u8]) -> bool {
+    pub fn valid_slice(&self, slice: &[u8]) -> bool {
         self.end <= slice.len()
     }
 
-    fn valid_str(&self, slice: &str) -> bool {
+    pub fn valid_str(&self, slice: &str) -> bool {
         slice.is_char_boundary(self.start) && slice.is_char_boundary(self.end)
     }
 }
@@ -85,7 +87,13 @@ impl Arbitrary for SliceRange {
     }
 }
 
-trait Splice<S: ?Sized> {
+fn hash<T: Hash + ?Sized>(t: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    t.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub trait Splice<S: ?Sized> {
     fn splice(&mut self, dest: SliceRange, src: &S);
 }
 
@@ -1,9 +1,13 @@
+use std::ops::Range;
 use std::string::String as StdString;
 
 use quickcheck::TestResult;
 use viola::gap::String;
+use viola::range::IntoRange;
+use viola::word::{Class, Keyword};
 
-use super::{SliceRange, Splice};
+use fixture;
+use super::{SliceRange, Splice, hash};
 
 macro_rules! discard {
     ($range:expr, $slice:expr) => {
@@ -180,3 +184,353 @@ fn splice_slice_to_string(
     discard!(range, &std);
     TestResult::from_bool(buf.slice(range).to_string() == &std[range])
 }
+
+// Returns the byte ranges of lines, excluding newlines.
+fn line_ranges(s: &str) -> Vec<Range<usize>> {
+    let mut start = 0;
+    let mut ranges = Vec::new();
+    for (i, _) in s.match_indices('\n') {
+        ranges.push(start..i);
+        start = i + 1;
+    }
+    ranges.push(start..s.len());
+    ranges
+}
+
+fn lines_eq(buf: &String, std: &str) -> bool {
+    let ranges = line_ranges(std);
+    buf.line_count() == ranges.len()
+        && ranges.iter().enumerate().all(|(line, range)| buf.line_range(line) == *range)
+        && (0..(std.len() + 1)).all(|offset| {
+            let line = std.as_bytes()[..offset].iter().filter(|&&b| b == b'\n').count();
+            let col = offset - ranges[line].start;
+            buf.line_of(offset) == line
+                && buf.line_col(offset) == (line, col)
+                && buf.offset(line, col) == offset
+        })
+}
+
+#[quickcheck]
+fn lines(init: StdString) -> bool {
+    let buf = String::from(&init[..]);
+    lines_eq(&buf, &init)
+}
+
+#[quickcheck]
+fn splice_lines(init: StdString, dest: SliceRange, src: StdString) -> TestResult {
+    discard!(dest, &init);
+    let mut std = init.clone();
+    let mut buf = String::from(init);
+
+    Splice::splice(&mut std, dest, &src);
+    Splice::splice(&mut buf, dest, &src);
+
+    TestResult::from_bool(lines_eq(&buf, &std))
+}
+
+#[quickcheck]
+fn splice_splice_lines(
+    init: StdString,
+    one: (SliceRange, StdString),
+    two: (SliceRange, StdString),
+) -> TestResult {
+    discard!(one.0, &init);
+    let mut std = init.clone();
+    let mut buf = String::from(&init[..]);
+
+    Splice::splice(&mut std, one.0, &one.1);
+    Splice::splice(&mut buf, one.0, &one.1);
+
+    discard!(two.0, &std);
+    Splice::splice(&mut std, two.0, &two.1);
+    Splice::splice(&mut buf, two.0, &two.1);
+
+    TestResult::from_bool(lines_eq(&buf, &std))
+}
+
+#[quickcheck]
+fn splice_hash(init: StdString, dest: SliceRange, src: StdString) -> TestResult {
+    discard!(dest, &init);
+    let mut std = init.clone();
+    let mut buf = String::from(&init[..]);
+
+    Splice::splice(&mut std, dest, &src);
+    Splice::splice(&mut buf, dest, &src);
+
+    TestResult::from_bool(hash(&buf) == hash(&std[..]) && hash(&buf.slice(..)) == hash(&std[..]))
+}
+
+// Filters splices to those valid and not overlapping an earlier splice.
+pub fn disjoint(
+    init: &str,
+    splices: Vec<(SliceRange, StdString)>,
+) -> Vec<(Range<usize>, StdString)> {
+    let mut disjoint: Vec<(Range<usize>, StdString)> = Vec::new();
+    for (dest, src) in splices {
+        if !dest.valid_str(init) {
+            continue;
+        }
+        let dest = dest.into_range(0);
+        let overlaps = disjoint.iter().any(|&(ref other, _)| {
+            dest.start < other.end && other.start < dest.end
+                || dest.start == other.start
+                || dest.end == other.end
+        });
+        if !overlaps {
+            disjoint.push((dest, src));
+        }
+    }
+    disjoint
+}
+
+#[quickcheck]
+fn splice_many(init: StdString, splices: Vec<(SliceRange, StdString)>) -> bool {
+    let splices = disjoint(&init, splices);
+    let mut buf = String::from(&init[..]);
+    let refs: Vec<_> = splices.iter()
+        .map(|&(ref dest, ref src)| (dest.clone(), &src[..]))
+        .collect();
+    let written = buf.splice_many(&refs);
+
+    let mut sorted = splices.clone();
+    sorted.sort_by_key(|&(ref dest, _)| dest.start);
+    let mut std = init.clone();
+    for &(ref dest, ref src) in sorted.iter().rev() {
+        std.drain(dest.clone());
+        std.insert_str(dest.start, src);
+    }
+
+    buf == std
+        && lines_eq(&buf, &std)
+        && written.iter().zip(&splices).all(|(range, &(_, ref src))| {
+            buf.slice(range.clone()) == &src[..]
+        })
+}
+
+#[quickcheck]
+fn graphemes(init: StdString, gap: usize) -> TestResult {
+    if !init.is_char_boundary(gap) {
+        return TestResult::discard();
+    }
+    let mut buf = String::from(&init[..]);
+    buf.splice(gap..gap, "");
+
+    let forward: Vec<_> = buf.as_str().grapheme_indices().collect();
+    let mut backward: Vec<_> = buf.as_str().grapheme_indices().rev().collect();
+    backward.reverse();
+
+    let concat: StdString = buf.as_str().graphemes().map(|cluster| cluster.to_string()).collect();
+    TestResult::from_bool(
+        concat == init
+            && forward.len() == backward.len()
+            && forward.iter().zip(&backward).all(|(&(a, x), &(b, y))| a == b && x.len() == y.len())
+            && forward.iter().all(|&(offset, _)| buf.is_grapheme_boundary(offset))
+    )
+}
+
+#[quickcheck]
+fn grapheme_boundary_snap(init: StdString, offset: usize) -> TestResult {
+    if offset > init.len() {
+        return TestResult::discard();
+    }
+    let buf = String::from(&init[..]);
+    let prev = buf.prev_grapheme_boundary(offset);
+    let next = buf.next_grapheme_boundary(offset);
+    TestResult::from_bool(
+        buf.is_grapheme_boundary(prev)
+            && buf.is_grapheme_boundary(next)
+            && (offset == 0 || prev < offset)
+            && (offset == init.len() || next > offset)
+            && (prev + 1..offset).all(|i| !buf.is_grapheme_boundary(i))
+            && (offset + 1..next).all(|i| !buf.is_grapheme_boundary(i))
+    )
+}
+
+#[test]
+fn graphemes_straddle_gap() {
+    let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+    let init = format!("e\u{301}{}\u{1f1fa}\u{1f1f8}\u{1f1ec}\r\n", family);
+    for gap in (0..init.len()).filter(|&i| init.is_char_boundary(i)) {
+        let mut buf = String::from(&init[..]);
+        buf.splice(gap..gap, "");
+        let clusters: Vec<_> = buf.as_str().graphemes().map(|c| c.to_string()).collect();
+        assert_eq!(clusters, ["e\u{301}", family, "\u{1f1fa}\u{1f1f8}", "\u{1f1ec}", "\r\n"]);
+    }
+}
+
+// Maps bytes to text with plenty of word and sentence boundaries.
+fn prose(bytes: Vec<u8>) -> StdString {
+    let alphabet = ['a', 'b', '_', '1', 'é', '.', '!', '?', ')', '"', ',', ' ', '\t', '\n', '\n'];
+    fixture::from_alphabet(bytes, &alphabet)
+}
+
+// Checks that iterating from both ends, meeting anywhere, yields the same spans.
+fn double_ended<I, F>(iter: F, meet: usize) -> bool
+where I: DoubleEndedIterator<Item = Range<usize>>, F: Fn() -> I {
+    let forward: Vec<_> = iter().collect();
+    let mut backward: Vec<_> = iter().rev().collect();
+    backward.reverse();
+
+    let mut both = iter();
+    let mut front: Vec<_> = both.by_ref().take(meet).collect();
+    let back: Vec<_> = both.rev().collect();
+    front.extend(back.into_iter().rev());
+
+    forward == backward && forward == front
+}
+
+#[quickcheck]
+fn words(bytes: Vec<u8>, gap: usize, meet: usize) -> TestResult {
+    let init = prose(bytes);
+    if !init.is_char_boundary(gap) {
+        return TestResult::discard();
+    }
+    let mut buf = String::from(&init[..]);
+    buf.splice(gap..gap, "");
+    let keyword = Keyword::default();
+    let slice = buf.as_str();
+
+    let spans: Vec<_> = slice.words(&keyword).collect();
+    let maximal = spans.iter().filter(|span| !span.is_empty()).all(|span| {
+        let class = keyword.class(init[span.clone()].chars().next().unwrap());
+        class != Class::Blank
+            && init[span.clone()].chars().all(|ch| keyword.class(ch) == class)
+            && init[..span.start].chars().next_back().map_or(true, |ch| keyword.class(ch) != class)
+            && init[span.end..].chars().next().map_or(true, |ch| keyword.class(ch) != class)
+    });
+    let nonblank = init.char_indices()
+        .filter(|&(_, ch)| !ch.is_whitespace())
+        .all(|(i, _)| spans.iter().any(|span| span.start <= i && i < span.end));
+
+    TestResult::from_bool(
+        maximal
+            && nonblank
+            && double_ended(|| slice.words(&keyword), meet)
+            && double_ended(|| slice.big_words(), meet)
+    )
+}
+
+#[quickcheck]
+fn sentences(bytes: Vec<u8>, gap: usize, meet: usize) -> TestResult {
+    let init = prose(bytes);
+    if !init.is_char_boundary(gap) {
+        return TestResult::discard();
+    }
+    let mut buf = String::from(&init[..]);
+    buf.splice(gap..gap, "");
+    let slice = buf.as_str();
+
+    let spans: Vec<_> = slice.sentences().collect();
+    let ordered = spans.windows(2).all(|pair| pair[0].end <= pair[1].start);
+    let trimmed = spans.iter().all(|span| init[span.clone()].trim() == &init[span.clone()]);
+    TestResult::from_bool(ordered && trimmed && double_ended(|| slice.sentences(), meet))
+}
+
+#[test]
+fn words_vi() {
+    let buf = String::from("foo.bar(baz)  x\n\n  qux-quux\n");
+    let spans = |words: &mut Iterator<Item = Range<usize>>| -> Vec<StdString> {
+        words.map(|span| buf.slice(span).to_string()).collect()
+    };
+    let keyword = Keyword::default();
+    assert_eq!(
+        spans(&mut buf.as_str().words(&keyword)),
+        ["foo", ".", "bar", "(", "baz", ")", "x", "", "qux", "-", "quux"]
+    );
+    assert_eq!(spans(&mut buf.as_str().big_words()), ["foo.bar(baz)", "x", "", "qux-quux"]);
+
+    let keyword = "@,48-57,_,-".parse().unwrap();
+    assert_eq!(
+        spans(&mut buf.as_str().words(&keyword)),
+        ["foo", ".", "bar", "(", "baz", ")", "x", "", "qux-quux"]
+    );
+}
+
+#[test]
+fn sentences_vi() {
+    let buf = String::from("Hello there.  (Is it?) Yes.Really\nok.\n\nNext para");
+    let spans: Vec<_> = buf.as_str().sentences().map(|span| buf.slice(span).to_string()).collect();
+    assert_eq!(spans, ["Hello there.", "(Is it?)", "Yes.Really\nok.", "", "Next para"]);
+}
+
+#[test]
+fn keyword_parse() {
+    let keyword: Keyword = "a-c,^b,48,@-@".parse().unwrap();
+    assert!(keyword.contains('a') && !keyword.contains('b') && keyword.contains('c'));
+    assert!(keyword.contains('0') && keyword.contains('@') && !keyword.contains('1'));
+    assert!("".parse::<Keyword>().is_err());
+    assert!("z-a".parse::<Keyword>().is_err());
+    assert!("300".parse::<Keyword>().is_err());
+    assert!(!Keyword::empty().contains('a') && Keyword::empty().contains('字'));
+    assert!(Keyword::default().contains('a') && Keyword::default().contains('é'));
+}
+
+// Maps bytes to text over a small alphabet, for frequent and periodic matches.
+fn binary(bytes: Vec<u8>) -> StdString {
+    let alphabet = ['a', 'b', 'é'];
+    fixture::from_alphabet(bytes, &alphabet)
+}
+
+#[quickcheck]
+fn search(haystack: Vec<u8>, needle: Vec<u8>, gap: usize) -> TestResult {
+    let init = binary(haystack);
+    let needle = binary(needle);
+    if !init.is_char_boundary(gap) {
+        return TestResult::discard();
+    }
+    let mut buf = String::from(&init[..]);
+    buf.splice(gap..gap, "");
+    let slice = buf.as_str();
+
+    let ranges = |matches: Vec<(usize, &str)>| -> Vec<Range<usize>> {
+        matches.into_iter().map(|(i, s)| i..(i + s.len())).collect()
+    };
+    TestResult::from_bool(
+        slice.find(&needle) == init.find(&needle[..])
+            && slice.rfind(&needle) == init.rfind(&needle[..])
+            && slice.match_indices(&needle).collect::<Vec<_>>()
+                == ranges(init.match_indices(&needle[..]).collect())
+            && slice.rmatch_indices(&needle).collect::<Vec<_>>()
+                == ranges(init.rmatch_indices(&needle[..]).collect())
+    )
+}
+
+#[quickcheck]
+fn search_range(haystack: Vec<u8>, needle: Vec<u8>, gap: usize, range: SliceRange) -> TestResult {
+    let init = binary(haystack);
+    let needle = binary(needle);
+    discard!(range, &init);
+    if !init.is_char_boundary(gap) {
+        return TestResult::discard();
+    }
+    let mut buf = String::from(&init[..]);
+    buf.splice(gap..gap, "");
+
+    let start = range.into_range(init.len()).start;
+    let ranges = |matches: Vec<(usize, &str)>| -> Vec<Range<usize>> {
+        matches.into_iter().map(|(i, s)| (start + i)..(start + i + s.len())).collect()
+    };
+    TestResult::from_bool(
+        buf.find(range, &needle) == init[range].find(&needle[..]).map(|i| start + i)
+            && buf.rfind(range, &needle) == init[range].rfind(&needle[..]).map(|i| start + i)
+            && buf.match_indices(range, &needle).collect::<Vec<_>>()
+                == ranges(init[range].match_indices(&needle[..]).collect())
+            && buf.rmatch_indices(range, &needle).collect::<Vec<_>>()
+                == ranges(init[range].rmatch_indices(&needle[..]).collect())
+    )
+}
+
+#[test]
+fn search_straddle_gap() {
+    let mut buf = String::from("needle in a haystack");
+    for gap in 0..buf.len() {
+        buf.splice(gap..gap, "");
+        assert_eq!(buf.as_str().find("needle"), Some(0));
+        assert_eq!(buf.as_str().rfind("a"), Some(17));
+        assert_eq!(buf.as_str().match_indices("a").collect::<Vec<_>>(), [10..11, 13..14, 17..18]);
+        assert_eq!(buf.as_str().find("hay stack"), None);
+        assert_eq!(buf.find(11.., "a"), Some(13));
+        assert_eq!(buf.rfind(..17, "a"), Some(13));
+        assert_eq!(buf.match_indices(11..18, "a").collect::<Vec<_>>(), [13..14, 17..18]);
+    }
+}
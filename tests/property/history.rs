@@ -0,0 +1,319 @@
+use std::string::String as StdString;
+use std::time::{Duration, SystemTime};
+
+use quickcheck::TestResult;
+use viola::gap::String;
+use viola::history::{History, Tree};
+use viola::history::file::Error;
+use viola::operation::{Operate, Operation};
+use viola::range::IntoRange;
+
+use gap::{SliceRange, Splice};
+
+// Applies transactions of splices to a history and a standard string, skipping invalid splices.
+// Returns the states of the standard string after each recorded transaction.
+fn record(
+    history: &mut History<String>,
+    init: StdString,
+    transactions: Vec<Vec<(SliceRange, StdString)>>,
+) -> Vec<StdString> {
+    let mut states = vec![init];
+    for transaction in transactions {
+        let mut std = states.last().unwrap().clone();
+        history.begin(0);
+        let mut recorded = false;
+        for (dest, src) in transaction {
+            if !dest.valid_str(&std) {
+                continue;
+            }
+            Splice::splice(&mut std, dest, &src);
+            history.apply(&Operation { dest: dest.into_range(0), src: src });
+            recorded = true;
+        }
+        history.commit(0);
+        if recorded {
+            states.push(std);
+        }
+    }
+    states
+}
+
+#[quickcheck]
+fn undo(init: StdString, transactions: Vec<Vec<(SliceRange, StdString)>>) -> bool {
+    let mut history = History::new(String::from(&init[..]));
+    let mut states = record(&mut history, init, transactions);
+
+    states.pop();
+    while let Some(state) = states.pop() {
+        if history.undo().is_none() || *history.get_ref() != state {
+            return false;
+        }
+    }
+    history.undo().is_none()
+}
+
+#[quickcheck]
+fn undo_redo(init: StdString, transactions: Vec<Vec<(SliceRange, StdString)>>) -> bool {
+    let mut history = History::new(String::from(&init[..]));
+    let states = record(&mut history, init, transactions);
+
+    while history.undo().is_some() {}
+    for state in &states[1..] {
+        if history.redo().is_none() || history.get_ref() != state {
+            return false;
+        }
+    }
+    history.redo().is_none()
+}
+
+#[quickcheck]
+fn undo_cursor(init: StdString, dest: SliceRange, src: StdString) -> bool {
+    if !dest.valid_str(&init) {
+        return true;
+    }
+    let dest = dest.into_range(0);
+    let mut history = History::new(String::from(&init[..]));
+    history.begin(dest.end);
+    let revert = history.apply(&Operation { dest: dest.clone(), src: src });
+    history.commit(revert.dest.end);
+
+    history.undo() == Some(dest.end) && history.redo() == Some(revert.dest.end)
+}
+
+// Records transactions of splices in a tree, undoing some number of times before each, skipping
+// invalid splices. Returns the states of the standard string indexed by sequence number.
+fn record_tree(
+    tree: &mut Tree<String>,
+    init: StdString,
+    transactions: Vec<(usize, Vec<(SliceRange, StdString)>)>,
+) -> Vec<StdString> {
+    let mut states = vec![init];
+    for (undos, transaction) in transactions {
+        for _ in 0..(undos % 3) {
+            tree.undo();
+        }
+        let mut std = states[tree.seq()].clone();
+        tree.begin(0);
+        let mut recorded = false;
+        for (dest, src) in transaction {
+            if !dest.valid_str(&std) {
+                continue;
+            }
+            Splice::splice(&mut std, dest, &src);
+            tree.apply(&Operation { dest: dest.into_range(0), src: src });
+            recorded = true;
+        }
+        tree.commit(0);
+        if recorded {
+            states.push(std);
+        }
+    }
+    states
+}
+
+#[quickcheck]
+fn tree_goto(
+    init: StdString,
+    transactions: Vec<(usize, Vec<(SliceRange, StdString)>)>,
+    seqs: Vec<usize>,
+) -> bool {
+    let mut tree = Tree::new(String::from(&init[..]));
+    let states = record_tree(&mut tree, init, transactions);
+
+    tree.seq_last() == states.len() - 1
+        && *tree.get_ref() == states[tree.seq()]
+        && seqs.into_iter().all(|seq| {
+            let seq = seq % states.len();
+            tree.goto(seq);
+            tree.seq() == seq && *tree.get_ref() == states[seq]
+        })
+}
+
+#[quickcheck]
+fn tree_undo_redo(
+    init: StdString,
+    transactions: Vec<(usize, Vec<(SliceRange, StdString)>)>,
+) -> bool {
+    let mut tree = Tree::new(String::from(&init[..]));
+    let states = record_tree(&mut tree, init, transactions);
+
+    let seq = tree.seq();
+    let mut undos = 0;
+    while tree.undo().is_some() {
+        undos += 1;
+    }
+    if tree.seq() != 0 || *tree.get_ref() != states[0] {
+        return false;
+    }
+    for _ in 0..undos {
+        tree.redo();
+    }
+    tree.seq() == seq && *tree.get_ref() == states[seq]
+}
+
+#[test]
+fn tree_branch() {
+    let mut tree = Tree::new(String::from("a"));
+    tree.apply(&Operation { dest: 1..1, src: StdString::from("b") });
+    tree.apply(&Operation { dest: 2..2, src: StdString::from("c") });
+    tree.undo();
+    tree.undo();
+    tree.apply(&Operation { dest: 0..0, src: StdString::from("d") });
+    assert_eq!(tree.get_ref(), "da");
+    assert_eq!(tree.seq(), 3);
+
+    tree.earlier(1);
+    assert_eq!(tree.get_ref(), "abc");
+    tree.earlier(1);
+    assert_eq!(tree.get_ref(), "ab");
+    tree.later(2);
+    assert_eq!(tree.get_ref(), "da");
+    tree.undo();
+    tree.redo();
+    assert_eq!(tree.get_ref(), "da");
+}
+
+#[test]
+fn tree_time() {
+    let start = SystemTime::now();
+    let minutes = |n| start + Duration::from_secs(60 * n);
+
+    let mut tree = Tree::new(String::new());
+    for (n, src) in ["a", "b", "c"].iter().enumerate() {
+        tree.begin(n);
+        tree.apply(&Operation { dest: n..n, src: StdString::from(*src) });
+        tree.commit_at(n + 1, minutes(n as u64 * 5 + 5));
+    }
+    assert_eq!(tree.get_ref(), "abc");
+
+    assert_eq!(tree.earlier_time(Duration::from_secs(60 * 5)), Some(2));
+    assert_eq!(tree.get_ref(), "ab");
+    assert_eq!(tree.earlier_time(Duration::from_secs(60 * 7)), Some(0));
+    assert_eq!(tree.get_ref(), "");
+    assert_eq!(tree.later_time(Duration::from_secs(60 * 12)), Some(2));
+    assert_eq!(tree.get_ref(), "ab");
+    assert_eq!(tree.later_time(Duration::from_secs(60 * 60)), Some(3));
+    assert_eq!(tree.get_ref(), "abc");
+}
+
+#[quickcheck]
+fn undo_file(
+    init: StdString,
+    transactions: Vec<(usize, Vec<(SliceRange, StdString)>)>,
+) -> bool {
+    let mut tree = Tree::new(String::from(&init[..]));
+    let states = record_tree(&mut tree, init, transactions);
+    let mut file = Vec::new();
+    tree.write_undo(&mut file).unwrap();
+
+    let seq = tree.seq();
+    let mut read = Tree::new(String::from(&states[seq][..]));
+    if read.read_undo(&file[..]).is_err() {
+        return false;
+    }
+    if read.seq() != seq || read.seq_last() != tree.seq_last() {
+        return false;
+    }
+    (0..states.len()).all(|seq| {
+        read.goto(seq);
+        *read.get_ref() == states[seq]
+    })
+}
+
+#[quickcheck]
+fn undo_file_hash(init: StdString, other: StdString, src: StdString) -> TestResult {
+    if init == other {
+        return TestResult::discard();
+    }
+    let mut tree = Tree::new(String::from(&init[..]));
+    tree.apply(&Operation { dest: 0..0, src: src.clone() });
+    let mut file = Vec::new();
+    tree.write_undo(&mut file).unwrap();
+
+    let mut read = Tree::new(String::from(other + &src));
+    match read.read_undo(&file[..]) {
+        Err(Error::Hash) => TestResult::from_bool(read.seq_last() == 0),
+        _ => TestResult::failed(),
+    }
+}
+
+#[test]
+fn undo_file_invalid() {
+    let mut tree = Tree::new(String::from("a"));
+    tree.apply(&Operation { dest: 0..1, src: StdString::from("b") });
+    let mut file = Vec::new();
+    tree.write_undo(&mut file).unwrap();
+
+    let mut read = Tree::new(String::from("b"));
+    let mut secs = file.clone();
+    secs[52..60].copy_from_slice(&u64::max_value().to_le_bytes());
+    match read.read_undo(&secs[..]) {
+        Err(Error::Corrupt) => {},
+        result => panic!("{:?}", result),
+    }
+    match read.read_undo(&b"VIMUNDO\0"[..]) {
+        Err(Error::Magic) => {},
+        result => panic!("{:?}", result),
+    }
+    match read.read_undo(&file[..(file.len() - 1)]) {
+        Err(Error::Corrupt) => {},
+        result => panic!("{:?}", result),
+    }
+    file[8] = 2;
+    match read.read_undo(&file[..]) {
+        Err(Error::Version(2)) => {},
+        result => panic!("{:?}", result),
+    }
+    assert_eq!(read.seq_last(), 0);
+}
+
+#[test]
+fn undo_file_branch() {
+    let mut tree = Tree::new(String::from("a"));
+    tree.apply(&Operation { dest: 0..1, src: StdString::from("b") });
+    tree.apply(&Operation { dest: 0..1, src: StdString::from("c") });
+    let mut file = Vec::new();
+    tree.write_undo(&mut file).unwrap();
+
+    // Points the original state to the last state, which is not its child.
+    file[44] = 2;
+    let mut read = Tree::new(String::from("c"));
+    match read.read_undo(&file[..]) {
+        Err(Error::Corrupt) => {},
+        result => panic!("{:?}", result),
+    }
+    assert_eq!(read.seq_last(), 0);
+}
+
+#[test]
+fn undo_file_operation() {
+    let mut tree = Tree::new(String::from("a"));
+    tree.apply(&Operation { dest: 0..1, src: StdString::from("\u{e9}") });
+    let mut file = Vec::new();
+    tree.write_undo(&mut file).unwrap();
+
+    // Sets the end of the operation leading back to the original state, past the text and then
+    // within a character.
+    let mut read = Tree::new(String::from("\u{e9}"));
+    for &end in &[3u64, 1] {
+        let mut corrupt = file.clone();
+        corrupt[148..156].copy_from_slice(&end.to_le_bytes());
+        match read.read_undo(&corrupt[..]) {
+            Err(Error::Corrupt) => {},
+            result => panic!("{:?}", result),
+        }
+    }
+    assert_eq!(read.seq_last(), 0);
+
+    // Off the current branch, the operation applies to the original state instead.
+    tree.undo();
+    let mut file = Vec::new();
+    tree.write_undo(&mut file).unwrap();
+    file[148..156].copy_from_slice(&2u64.to_le_bytes());
+    let mut read = Tree::new(String::from("a"));
+    match read.read_undo(&file[..]) {
+        Err(Error::Corrupt) => {},
+        result => panic!("{:?}", result),
+    }
+    assert_eq!(read.seq_last(), 0);
+}
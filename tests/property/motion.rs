@@ -0,0 +1,211 @@
+use std::string::String as StdString;
+
+use quickcheck::TestResult;
+use viola::gap::String;
+use viola::motion::{self, Context, Target};
+use viola::normal::Motion;
+use viola::width::Curswant;
+use viola::word::Keyword;
+
+use fixture::{self, with_gap};
+
+// Maps bytes to text with words, sentences, brackets and lines.
+fn prose(bytes: Vec<u8>) -> StdString {
+    let alphabet = ['a', 'b', '.', ' ', '\n', '(', ')', '\t', 'é', '!'];
+    fixture::from_alphabet(bytes, &alphabet)
+}
+
+fn context() -> Context {
+    Context {
+        keyword: Keyword::default(),
+        tabstop: 8,
+        curswant: Curswant::Col(0),
+        screen: 1..4,
+        last_find: None,
+    }
+}
+
+fn charwise(offset: usize, inclusive: bool) -> Target {
+    Target { offset: offset, inclusive: inclusive, linewise: false }
+}
+
+fn offsets(text: &String, cursor: usize, motion: Motion, count: Option<usize>) -> Option<usize> {
+    motion::target(text, cursor, motion, count, &context()).map(|target| target.offset)
+}
+
+// Returns the offsets reached by repeating a motion from the start of the text.
+fn walk(init: &str, motion: Motion) -> Vec<usize> {
+    let text = String::from(init);
+    let mut cursor = 0;
+    let mut walked = Vec::new();
+    while let Some(offset) = offsets(&text, cursor, motion, None) {
+        if offset == cursor || walked.len() > init.len() {
+            break;
+        }
+        walked.push(offset);
+        cursor = offset;
+    }
+    walked
+}
+
+#[test]
+fn chars_and_lines() {
+    let text = String::from("ab\u{301}c\n\tdef\n\nx");
+    assert_eq!(motion::right(&text, 0, 2), Some(charwise(4, false)));
+    assert_eq!(motion::right(&text, 4, 9).map(|target| target.offset), Some(5));
+    assert_eq!(motion::right(&text, 5, 1), None);
+    assert_eq!(motion::left(&text, 4, 1).map(|target| target.offset), Some(1));
+    assert_eq!(motion::left(&text, 6, 1), None);
+
+    let down = motion::down(&text, 3, 1, Curswant::Col(9), 8).unwrap();
+    assert_eq!(down, Target { offset: 8, inclusive: false, linewise: true });
+    assert_eq!(motion::down(&text, 3, 5, Curswant::End, 8).unwrap().offset, 12);
+    assert_eq!(motion::up(&text, 12, 1, Curswant::End, 8).unwrap().offset, 11);
+    assert_eq!(motion::up(&text, 3, 1, Curswant::End, 8), None);
+    assert_eq!(motion::next_line(&text, 0, 1).unwrap().offset, 7);
+    assert_eq!(motion::prev_line(&text, 12, 3).unwrap().offset, 0);
+
+    assert_eq!(motion::first_non_blank(&text, 9).offset, 7);
+    assert_eq!(motion::line_start(&text, 9).offset, 6);
+    assert_eq!(motion::line_end(&text, 0, 1), Some(charwise(4, true)));
+    assert_eq!(motion::line_end(&text, 0, 3), Some(charwise(11, false)));
+    assert_eq!(motion::line_end(&text, 0, 5), None);
+    assert_eq!(motion::column(&text, 1, 3, 8), charwise(4, false));
+    assert_eq!(motion::column(&text, 9, 1, 8).offset, 6);
+    assert_eq!(motion::column(&text, 9, 5, 8).offset, 6);
+    assert_eq!(motion::column(&text, 6, 9, 8).offset, 7);
+    assert_eq!(motion::column(&text, 6, 99, 8).offset, 10);
+    assert_eq!(motion::column(&text, 6, 5, 4).offset, 7);
+    let wide = String::from("a字b");
+    assert_eq!(motion::column(&wide, 0, 3, 8).offset, 1);
+    assert_eq!(motion::column(&wide, 0, 4, 8).offset, 4);
+    assert_eq!(offsets(&wide, 4, Motion::Column, None), Some(0));
+    assert_eq!(motion::first_line(&text, Some(2)).offset, 7);
+    assert_eq!(motion::last_line(&text, None).offset, 12);
+    assert_eq!(motion::last_line(&text, Some(99)).offset, 12);
+    assert_eq!(motion::percent(&text, 50).unwrap().offset, 7);
+    assert_eq!(motion::percent(&text, 101), None);
+}
+
+#[test]
+fn words() {
+    let init = "foo.bar  baz\n\n  qux-";
+    assert_eq!(walk(init, Motion::WordStart { big: false }), [3, 4, 9, 13, 16, 19, 20]);
+    assert_eq!(walk(init, Motion::WordStart { big: true }), [9, 13, 16, 20]);
+    assert_eq!(walk(init, Motion::WordEnd { big: false }), [2, 3, 6, 11, 18, 19]);
+    assert_eq!(walk(init, Motion::WordEnd { big: true }), [6, 11, 19]);
+
+    let text = String::from(init);
+    let back = |motion, cursor| offsets(&text, cursor, motion, None);
+    assert_eq!(back(Motion::WordBack { big: false }, 17), Some(16));
+    assert_eq!(back(Motion::WordBack { big: false }, 16), Some(13));
+    assert_eq!(back(Motion::WordBack { big: true }, 11), Some(9));
+    assert_eq!(back(Motion::WordBack { big: true }, 9), Some(0));
+    assert_eq!(back(Motion::WordBack { big: true }, 0), None);
+    assert_eq!(back(Motion::WordEndBack { big: false }, 16), Some(13));
+    assert_eq!(back(Motion::WordEndBack { big: false }, 13), Some(11));
+    assert_eq!(back(Motion::WordEndBack { big: true }, 8), Some(6));
+    assert_eq!(back(Motion::WordEndBack { big: true }, 2), Some(0));
+    assert_eq!(offsets(&text, 0, Motion::WordStart { big: false }, Some(3)), Some(9));
+    assert_eq!(offsets(&text, 20, Motion::WordEnd { big: false }, None), None);
+}
+
+#[test]
+fn find_and_repeat() {
+    let text = String::from("a,b,c,d\n,");
+    assert_eq!(motion::find(&text, 0, 2, ','), Some(charwise(3, true)));
+    assert_eq!(motion::find(&text, 0, 4, ','), None);
+    assert_eq!(motion::till(&text, 0, 1, ','), Some(charwise(0, true)));
+    assert_eq!(motion::find_back(&text, 6, 1, ','), Some(charwise(5, false)));
+    assert_eq!(motion::till_back(&text, 6, 2, ','), Some(charwise(4, false)));
+    assert_eq!(motion::repeat_find(&text, 0, 1, Motion::Till(','), false).unwrap().offset, 2);
+    assert_eq!(motion::repeat_find(&text, 2, 1, Motion::Till(','), true), None);
+    assert_eq!(motion::repeat_find(&text, 4, 1, Motion::Till(','), true).unwrap().offset, 2);
+    assert_eq!(motion::repeat_find(&text, 4, 1, Motion::TillBack(','), false).unwrap().offset, 2);
+    assert_eq!(motion::repeat_find(&text, 4, 1, Motion::Find(','), true).unwrap().offset, 3);
+    assert_eq!(motion::repeat_find(&text, 4, 1, Motion::Left, false), None);
+
+    let mut context = context();
+    assert_eq!(motion::target(&text, 0, Motion::RepeatFind, None, &context), None);
+    context.last_find = Some(Motion::FindBack('a'));
+    assert_eq!(motion::target(&text, 2, Motion::RepeatFindReverse, None, &context), None);
+    assert_eq!(motion::target(&text, 2, Motion::RepeatFind, None, &context).unwrap().offset, 0);
+}
+
+#[test]
+fn pairs() {
+    let text = String::from("x (a [b] {\n(c)}) )");
+    assert_eq!(motion::match_pair(&text, 0), Some(charwise(15, true)));
+    assert_eq!(motion::match_pair(&text, 15).unwrap().offset, 2);
+    assert_eq!(motion::match_pair(&text, 6).unwrap().offset, 5);
+    assert_eq!(motion::match_pair(&text, 9).unwrap().offset, 14);
+    assert_eq!(motion::match_pair(&text, 17), None);
+    assert_eq!(offsets(&text, 0, Motion::MatchPair, Some(100)), Some(11));
+}
+
+#[test]
+fn sentences_and_paragraphs() {
+    let init = "One. Two!  Three\nfour.\n\n\nFive";
+    assert_eq!(walk(init, Motion::SentenceForward), [5, 11, 23, 24, 25, 29]);
+    assert_eq!(walk(init, Motion::ParagraphForward), [23, 29]);
+    let text = String::from(init);
+    assert_eq!(motion::sentence_backward(&text, 13, 1).unwrap().offset, 11);
+    assert_eq!(motion::sentence_backward(&text, 11, 2).unwrap().offset, 0);
+    assert_eq!(motion::sentence_backward(&text, 27, 1).unwrap().offset, 25);
+    assert_eq!(motion::paragraph_backward(&text, 27, 1).unwrap().offset, 24);
+    assert_eq!(motion::paragraph_backward(&text, 24, 1).unwrap().offset, 0);
+    assert_eq!(motion::paragraph_backward(&text, 0, 1), None);
+    assert_eq!(motion::paragraph_forward(&text, 0, 2).unwrap().offset, 29);
+}
+
+#[test]
+fn screen() {
+    let text = String::from("a\n b\nc\nd\ne");
+    assert_eq!(motion::screen_top(&text, 1, 1..4).offset, 3);
+    assert_eq!(motion::screen_top(&text, 9, 1..4).offset, 7);
+    assert_eq!(motion::screen_middle(&text, 1..4).offset, 5);
+    assert_eq!(motion::screen_bottom(&text, 2, 1..4).offset, 5);
+    assert_eq!(motion::screen_bottom(&text, 1, 3..9).offset, 9);
+    assert!(motion::screen_middle(&text, 0..1).linewise);
+}
+
+#[quickcheck]
+fn gap_invariant(bytes: Vec<u8>, gap: usize, count: Option<u8>) -> bool {
+    let init = prose(bytes);
+    let boundaries: Vec<_> = (0..(init.len() + 1)).filter(|&i| init.is_char_boundary(i)).collect();
+    let gap = boundaries[gap % boundaries.len()];
+    let (contiguous, fragmented) = (String::from(&init[..]), with_gap(&init, gap));
+    let count = count.map(|count| count as usize % 4 + 1);
+    let motions = [
+        Motion::Left, Motion::Right, Motion::Up, Motion::Down, Motion::WordStart { big: false },
+        Motion::WordBack { big: true }, Motion::WordEnd { big: false },
+        Motion::WordEndBack { big: false }, Motion::LineEnd, Motion::Column, Motion::Find('a'),
+        Motion::TillBack('b'), Motion::MatchPair, Motion::SentenceForward,
+        Motion::SentenceBackward, Motion::ParagraphForward, Motion::ParagraphBackward,
+    ];
+    boundaries.into_iter().all(|cursor| {
+        motions.iter().all(|&motion| {
+            let targets = (
+                motion::target(&contiguous, cursor, motion, count, &context()),
+                motion::target(&fragmented, cursor, motion, count, &context()),
+            );
+            targets.0 == targets.1
+                && targets.0.map_or(true, |target| init.is_char_boundary(target.offset))
+        })
+    })
+}
+
+#[quickcheck]
+fn find_like_std(bytes: Vec<u8>, cursor: usize, count: u8) -> TestResult {
+    let init = prose(bytes);
+    let count = count as usize % 3 + 1;
+    if cursor >= init.len() || !init.is_char_boundary(cursor) {
+        return TestResult::discard();
+    }
+    let text = String::from(&init[..]);
+    let end = init[cursor..].find('\n').map_or(init.len(), |i| cursor + i);
+    let after = cursor + init[cursor..].chars().next().unwrap().len_utf8();
+    let expected = init[after.min(end)..end].match_indices('a').nth(count - 1)
+        .map(|(i, _)| after + i);
+    TestResult::from_bool(motion::find(&text, cursor, count, 'a').map(|t| t.offset) == expected)
+}
@@ -0,0 +1,160 @@
+use quickcheck::TestResult;
+use viola::normal::{Action, Command, ESCAPE, Insert, Motion, Object, ObjectKind, Operator};
+use viola::normal::{Parser, Scroll, Status, Target, Visual};
+use viola::search::Direction;
+
+// Returns the status after each key.
+fn statuses(keys: &str) -> Vec<Status> {
+    let mut parser = Parser::new();
+    keys.chars().map(|key| parser.push(key)).collect()
+}
+
+// Returns the command of keys which must only be complete after the last key.
+fn command(keys: &str) -> Command {
+    let mut statuses = statuses(keys);
+    match statuses.pop() {
+        Some(Status::Complete(command)) => {
+            assert!(statuses.iter().all(|&status| status == Status::Incomplete), "{}", keys);
+            command
+        },
+        status => panic!("{:?} after {:?}", status, keys),
+    }
+}
+
+fn action(keys: &str) -> Action {
+    command(keys).action
+}
+
+fn invalid(keys: &str) -> bool {
+    statuses(keys).contains(&Status::Invalid)
+}
+
+#[test]
+fn motions() {
+    assert_eq!(action("j"), Action::Move(Motion::Down));
+    assert_eq!(action("0"), Action::Move(Motion::LineStart));
+    assert_eq!(action("|"), Action::Move(Motion::Column));
+    assert_eq!(action("W"), Action::Move(Motion::WordStart { big: true }));
+    assert_eq!(action("gE"), Action::Move(Motion::WordEndBack { big: true }));
+    assert_eq!(action("gg"), Action::Move(Motion::FirstLine));
+    assert_eq!(action("fx"), Action::Move(Motion::Find('x')));
+    assert_eq!(action("T;"), Action::Move(Motion::TillBack(';')));
+    assert_eq!(action("'a"), Action::Move(Motion::Mark { name: 'a', linewise: true }));
+    assert_eq!(action("`["), Action::Move(Motion::Mark { name: '[', linewise: false }));
+    assert_eq!(action("?"), Action::Move(Motion::Search(Direction::Backward)));
+    assert_eq!(command("10G"), Command {
+        register: None,
+        count: Some(10),
+        action: Action::Move(Motion::LastLine),
+    });
+    assert_eq!(command("5|").count, Some(5));
+    assert!(invalid("'!"));
+    assert!(invalid("gx"));
+}
+
+#[test]
+fn operators() {
+    let delete = |target| Action::Operate(Operator::Delete, target);
+    assert_eq!(action("dw"), delete(Target::Motion(Motion::WordStart { big: false })));
+    assert_eq!(action("dd"), delete(Target::Line));
+    assert_eq!(action("dgg"), delete(Target::Motion(Motion::FirstLine)));
+    assert_eq!(action("dt)"), delete(Target::Motion(Motion::Till(')'))));
+    assert_eq!(action("d0"), delete(Target::Motion(Motion::LineStart)));
+    assert_eq!(action("x"), delete(Target::Motion(Motion::Right)));
+    assert_eq!(action("D"), delete(Target::Motion(Motion::LineEnd)));
+    assert_eq!(action(">>"), Action::Operate(Operator::ShiftRight, Target::Line));
+    assert_eq!(action("cc"), Action::Operate(Operator::Change, Target::Line));
+    assert_eq!(action("Y"), Action::Operate(Operator::Yank, Target::Line));
+    assert_eq!(action("guu"), Action::Operate(Operator::Lowercase, Target::Line));
+    assert_eq!(action("gUgU"), Action::Operate(Operator::Uppercase, Target::Line));
+    assert_eq!(action("g~~"), Action::Operate(Operator::ToggleCase, Target::Line));
+    assert_eq!(action("gqap"), Action::Operate(Operator::Format, Target::Object(Object {
+        kind: ObjectKind::Paragraph,
+        inner: false,
+    })));
+    assert_eq!(action("ci\""), Action::Operate(Operator::Change, Target::Object(Object {
+        kind: ObjectKind::Quote('"'),
+        inner: true,
+    })));
+    assert_eq!(action("yab"), Action::Operate(Operator::Yank, Target::Object(Object {
+        kind: ObjectKind::Bracket('('),
+        inner: false,
+    })));
+    let tag = Object { kind: ObjectKind::Tag, inner: true };
+    assert_eq!(action("dit"), delete(Target::Object(tag)));
+    assert!(invalid("dy"));
+    assert!(invalid("dgu"));
+    assert!(invalid("diq"));
+    assert!(invalid("d\"a"));
+}
+
+#[test]
+fn registers_and_counts() {
+    assert_eq!(command("\"a2d3w"), Command {
+        register: Some('a'),
+        count: Some(6),
+        action: Action::Operate(Operator::Delete, Target::Motion(Motion::WordStart { big: false })),
+    });
+    assert_eq!(command("2\"_dd").register, Some('_'));
+    assert_eq!(command("2\"_dd").count, Some(2));
+    assert_eq!(command("d10j").count, Some(10));
+    assert_eq!(command("\"Ap"), Command {
+        register: Some('A'),
+        count: None,
+        action: Action::Put { before: false },
+    });
+    assert!(invalid("\"!"));
+    assert!(invalid("99999999999999999999999x"));
+    assert!(invalid("999999999999d999999999999w"));
+}
+
+#[test]
+fn other_commands() {
+    assert_eq!(action("A"), Action::Insert(Insert::LineEnd));
+    assert_eq!(action("gJ"), Action::Join { spaces: false });
+    assert_eq!(action("r\r"), Action::Replace('\r'));
+    assert_eq!(action("mz"), Action::SetMark('z'));
+    assert_eq!(action("\x12"), Action::Redo);
+    assert_eq!(action("\x16"), Action::Visual(Visual::Block));
+    assert_eq!(action("zt"), Action::Scroll(Scroll::Top));
+    assert_eq!(action("z."), Action::Scroll(Scroll::Middle));
+    assert!(invalid("dz"));
+    assert!(invalid("zq"));
+}
+
+#[test]
+fn pending_keys() {
+    let mut parser = Parser::new();
+    assert_eq!(parser.push('"'), Status::Incomplete);
+    assert_eq!(parser.push('a'), Status::Incomplete);
+    assert_eq!(parser.push('2'), Status::Incomplete);
+    assert_eq!(parser.push('d'), Status::Incomplete);
+    assert_eq!(parser.keys(), "\"a2d");
+    assert_eq!(parser.push(ESCAPE), Status::Invalid);
+    assert_eq!(parser.keys(), "");
+    assert_eq!(parser.push('w'), Status::Complete(Command {
+        register: None,
+        count: None,
+        action: Action::Move(Motion::WordStart { big: false }),
+    }));
+}
+
+#[quickcheck]
+fn counts_multiply(count: u16, motion_count: u16) -> TestResult {
+    if count == 0 || motion_count == 0 {
+        return TestResult::discard();
+    }
+    let command = command(&format!("{}y{}e", count, motion_count));
+    TestResult::from_bool(command.count == Some(count as usize * motion_count as usize))
+}
+
+#[quickcheck]
+fn resets_after_command(keys: Vec<u8>) -> bool {
+    let mut parser = Parser::new();
+    let mut pending = 0;
+    keys.into_iter().all(|key| {
+        let status = parser.push(key as char);
+        pending = if status == Status::Incomplete { pending + 1 } else { 0 };
+        parser.keys().chars().count() == pending
+    })
+}
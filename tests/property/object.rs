@@ -0,0 +1,154 @@
+use std::ops::Range;
+use std::string::String as StdString;
+
+use viola::gap::String;
+use viola::normal::{Object, ObjectKind};
+use viola::object::{self, Selection};
+use viola::word::Keyword;
+
+use fixture::{self, with_gap};
+
+// Maps bytes to text with words, sentences, quotes, brackets and tags.
+fn markup(bytes: Vec<u8>) -> StdString {
+    let alphabet = ["a", " ", ".", "\n", "\"", "\\", "(", ")", "{", "}", "<b>", "</b>", "é"];
+    fixture::from_alphabet(bytes, &alphabet)
+}
+
+// Returns the text selected by an object given by its keys, such as `aw`.
+fn select(init: &str, cursor: usize, count: usize, keys: &str) -> Option<StdString> {
+    let text = String::from(init);
+    let mut keys = keys.chars();
+    let inner = keys.next() == Some('i');
+    let kind = match keys.next().unwrap() {
+        'w' => ObjectKind::Word,
+        'W' => ObjectKind::BigWord,
+        's' => ObjectKind::Sentence,
+        'p' => ObjectKind::Paragraph,
+        't' => ObjectKind::Tag,
+        '(' => ObjectKind::Bracket('('),
+        '{' => ObjectKind::Bracket('{'),
+        quote => ObjectKind::Quote(quote),
+    };
+    let object = Object { kind: kind, inner: inner };
+    object::select(&text, cursor, object, count, &Keyword::default())
+        .map(|selection| init[selection.range].to_owned())
+}
+
+fn range(selection: Option<Selection>) -> Option<Range<usize>> {
+    selection.map(|selection| selection.range)
+}
+
+#[test]
+fn words() {
+    let init = "foo.bar  baz qux\n\nend";
+    assert_eq!(select(init, 1, 1, "iw").unwrap(), "foo");
+    assert_eq!(select(init, 1, 3, "iw").unwrap(), "foo.bar");
+    assert_eq!(select(init, 1, 1, "iW").unwrap(), "foo.bar");
+    assert_eq!(select(init, 8, 1, "iw").unwrap(), "  ");
+    assert_eq!(select(init, 8, 1, "aw").unwrap(), "  baz");
+    assert_eq!(select(init, 9, 1, "aw").unwrap(), "baz ");
+    assert_eq!(select(init, 9, 2, "aw").unwrap(), "  baz qux");
+    assert_eq!(select(init, 14, 1, "aw").unwrap(), " qux");
+    assert_eq!(select(init, 1, 1, "aw").unwrap(), "foo");
+    assert_eq!(select(init, 1, 9, "iW").unwrap(), "foo.bar  baz qux");
+    assert_eq!(select(init, 17, 1, "aw").unwrap(), "");
+}
+
+#[test]
+fn sentences() {
+    let init = "One. Two!  Three\nfour.\n\nFive.";
+    assert_eq!(select(init, 6, 1, "is").unwrap(), "Two!");
+    assert_eq!(select(init, 6, 1, "as").unwrap(), "Two!  ");
+    assert_eq!(select(init, 9, 1, "is").unwrap(), "  ");
+    assert_eq!(select(init, 9, 1, "as").unwrap(), "  Three\nfour.");
+    assert_eq!(select(init, 13, 1, "as").unwrap(), "  Three\nfour.");
+    assert_eq!(select(init, 0, 2, "as").unwrap(), "One. Two!  ");
+    assert_eq!(select(init, 0, 3, "is").unwrap(), "One. Two!");
+    assert_eq!(select(init, 25, 1, "as").unwrap(), "Five.");
+    assert_eq!(select(init, 23, 1, "is"), None);
+}
+
+#[test]
+fn paragraphs() {
+    let init = "a\nb\n\n  \nc\n\nd";
+    let text = String::from(init);
+    let selection = object::paragraph(&text, 2, 1, true).unwrap();
+    assert_eq!(selection, Selection { range: 0..4, linewise: true });
+    assert_eq!(select(init, 0, 1, "ap").unwrap(), "a\nb\n\n  \n");
+    assert_eq!(select(init, 4, 1, "ip").unwrap(), "\n  \n");
+    assert_eq!(select(init, 4, 1, "ap").unwrap(), "\n  \nc\n");
+    assert_eq!(select(init, 8, 1, "ap").unwrap(), "c\n\n");
+    assert_eq!(select(init, 11, 1, "ap").unwrap(), "\nd");
+    assert_eq!(select(init, 0, 3, "ip").unwrap(), "a\nb\n\n  \nc\n");
+    assert_eq!(select(init, 0, 9, "ap").unwrap(), init);
+    assert_eq!(range(object::paragraph(&String::from("a\n"), 2, 1, true)), Some(2..2));
+}
+
+#[test]
+fn quotes() {
+    let init = r#"x = "a \"b\"" + 'c'  "d""#;
+    assert_eq!(select(init, 6, 1, "i\"").unwrap(), r#"a \"b\""#);
+    assert_eq!(select(init, 4, 1, "a\"").unwrap(), r#""a \"b\"" "#);
+    assert_eq!(select(init, 0, 1, "i\"").unwrap(), r#"a \"b\""#);
+    assert_eq!(select(init, 6, 2, "i\"").unwrap(), r#""a \"b\"""#);
+    assert_eq!(select(init, 15, 1, "i'").unwrap(), "c");
+    assert_eq!(select(init, 17, 1, "a'").unwrap(), "'c'  ");
+    assert_eq!(select(init, 22, 1, "a\"").unwrap(), "  \"d\"");
+    assert_eq!(select(init, 18, 1, "i`"), None);
+    assert_eq!(select("\"\"", 0, 1, "i\"").unwrap(), "");
+}
+
+#[test]
+fn brackets() {
+    let init = "f(a, (b), c) {\n    x;\n}";
+    assert_eq!(select(init, 3, 1, "i(").unwrap(), "a, (b), c");
+    assert_eq!(select(init, 6, 1, "a(").unwrap(), "(b)");
+    assert_eq!(select(init, 6, 2, "a(").unwrap(), "(a, (b), c)");
+    assert_eq!(select(init, 5, 1, "i(").unwrap(), "b");
+    assert_eq!(select(init, 7, 1, "i(").unwrap(), "b");
+    assert_eq!(select(init, 1, 1, "i(").unwrap(), "a, (b), c");
+    assert_eq!(select(init, 6, 3, "a("), None);
+    assert_eq!(select(init, 17, 1, "i{").unwrap(), "    x;\n");
+    assert_eq!(select(init, 17, 1, "a{").unwrap(), "{\n    x;\n}");
+    assert_eq!(select("{\n}", 0, 1, "i{").unwrap(), "");
+    assert_eq!(select("{ x }", 0, 1, "i{").unwrap(), " x ");
+}
+
+#[test]
+fn tags() {
+    let init = "<div id=\"a\"><p>one<br/> <i>two</i><br></p></div>";
+    assert_eq!(select(init, 16, 1, "it").unwrap(), "one<br/> <i>two</i><br>");
+    assert_eq!(select(init, 28, 1, "it").unwrap(), "two");
+    assert_eq!(select(init, 28, 2, "at").unwrap(), "<p>one<br/> <i>two</i><br></p>");
+    assert_eq!(select(init, 2, 1, "at").unwrap(), init);
+    assert_eq!(select(init, 44, 1, "it").unwrap(), "<p>one<br/> <i>two</i><br></p>");
+    assert_eq!(select(init, 28, 4, "it"), None);
+    assert_eq!(select("<a>x</b>", 3, 1, "it"), None);
+}
+
+#[quickcheck]
+fn gap_invariant(bytes: Vec<u8>, gap: usize, count: u8) -> bool {
+    let init = markup(bytes);
+    let boundaries: Vec<_> = (0..(init.len() + 1)).filter(|&i| init.is_char_boundary(i)).collect();
+    let gap = boundaries[gap % boundaries.len()];
+    let (contiguous, fragmented) = (String::from(&init[..]), with_gap(&init, gap));
+    let count = count as usize % 3 + 1;
+    let kinds = [
+        ObjectKind::Word, ObjectKind::BigWord, ObjectKind::Sentence, ObjectKind::Paragraph,
+        ObjectKind::Quote('"'), ObjectKind::Bracket('('), ObjectKind::Bracket('{'),
+        ObjectKind::Tag,
+    ];
+    boundaries.into_iter().all(|cursor| {
+        kinds.iter().all(|&kind| [true, false].iter().all(|&inner| {
+            let object = Object { kind: kind, inner: inner };
+            let keyword = Keyword::default();
+            let selection = object::select(&contiguous, cursor, object, count, &keyword);
+            selection == object::select(&fragmented, cursor, object, count, &keyword)
+                && selection.map_or(true, |selection| {
+                    selection.range.start <= selection.range.end
+                        && init.is_char_boundary(selection.range.start)
+                        && init.is_char_boundary(selection.range.end)
+                })
+        }))
+    })
+}
@@ -0,0 +1,202 @@
+use std::string::String as StdString;
+
+use quickcheck::TestResult;
+use viola::gap::String;
+use viola::history::History;
+use viola::piece;
+use viola::operation::{Operate, Operation, compose, squash, transform, transform_all};
+use viola::range::IntoRange;
+
+use gap::SliceRange;
+use gap::string::disjoint;
+
+// Converts splices to operations applying in sequence to `init`, skipping invalid splices.
+fn operations(init: &str, splices: Vec<(SliceRange, StdString)>) -> Vec<Operation<StdString>> {
+    let mut buf = String::from(init);
+    let mut operations = Vec::new();
+    for (dest, src) in splices {
+        if !dest.valid_str(&buf.to_string()) {
+            continue;
+        }
+        let operation = Operation { dest: dest.into_range(0), src: src };
+        buf.apply(&operation);
+        operations.push(operation);
+    }
+    operations
+}
+
+fn apply(init: &str, operations: &[Operation<StdString>]) -> String {
+    let mut buf = String::from(init);
+    for operation in operations {
+        buf.apply(operation);
+    }
+    buf
+}
+
+#[quickcheck]
+fn transform_converge(
+    init: StdString,
+    a: (SliceRange, StdString),
+    b: (SliceRange, StdString),
+) -> TestResult {
+    if !a.0.valid_str(&init) || !b.0.valid_str(&init) {
+        return TestResult::discard();
+    }
+    let a = Operation { dest: a.0.into_range(0), src: a.1 };
+    let b = Operation { dest: b.0.into_range(0), src: b.1 };
+    let (a_prime, b_prime) = transform(&a, &b);
+
+    let ab = apply(&init, &[a, b_prime]);
+    let ba = apply(&init, &[b, a_prime]);
+    TestResult::from_bool(ab.to_string() == ba.to_string())
+}
+
+#[quickcheck]
+fn transform_insert_order(init: StdString, at: usize, a: StdString, b: StdString) -> TestResult {
+    if !init.is_char_boundary(at) {
+        return TestResult::discard();
+    }
+    let a = Operation { dest: at..at, src: a };
+    let b = Operation { dest: at..at, src: b };
+    let (a_prime, b_prime) = transform(&a, &b);
+
+    let mut expected = init.clone();
+    expected.insert_str(at, &b.src);
+    expected.insert_str(at, &a.src);
+    let ab = apply(&init, &[a, b_prime]);
+    let ba = apply(&init, &[b, a_prime]);
+    TestResult::from_bool(ab == expected && ba == expected)
+}
+
+#[quickcheck]
+fn transform_all_converge(
+    init: StdString,
+    a: Vec<(SliceRange, StdString)>,
+    b: Vec<(SliceRange, StdString)>,
+) -> bool {
+    let a = operations(&init, a);
+    let b = operations(&init, b);
+    let (a_prime, b_prime) = transform_all(&a, &b);
+
+    let ab = apply(&init, &[&a[..], &b_prime[..]].concat());
+    let ba = apply(&init, &[&b[..], &a_prime[..]].concat());
+    ab.to_string() == ba.to_string()
+}
+
+#[quickcheck]
+fn compose_apply(init: StdString, splices: Vec<(SliceRange, StdString)>) -> TestResult {
+    let operations = operations(&init, splices);
+    if operations.len() < 2 {
+        return TestResult::discard();
+    }
+    match compose(&operations[0], &operations[1]) {
+        Some(composed) => {
+            let expected = apply(&init, &operations[..2]).to_string();
+            TestResult::from_bool(apply(&init, &[composed]) == expected)
+        },
+        None => TestResult::discard(),
+    }
+}
+
+#[quickcheck]
+fn squash_apply(init: StdString, splices: Vec<(SliceRange, StdString)>) -> bool {
+    let operations = operations(&init, splices);
+    let squashed = squash(&operations);
+    squashed.len() <= operations.len()
+        && apply(&init, &squashed).to_string() == apply(&init, &operations).to_string()
+}
+
+#[quickcheck]
+fn squash_revert(init: StdString, splices: Vec<(SliceRange, StdString)>) -> bool {
+    let operations = operations(&init, splices);
+    let mut buf = String::from(&init[..]);
+    let mut reverts: Vec<_> = squash(&operations).iter().map(|op| buf.apply(op)).collect();
+    reverts.reverse();
+    for revert in squash(&reverts) {
+        buf.apply(&revert);
+    }
+    buf == init
+}
+
+#[test]
+fn squash_typing() {
+    let mut buf = String::from("()");
+    let mut operations = Vec::new();
+    let mut reverts = Vec::new();
+    for (i, ch) in "hello".char_indices() {
+        let operation = Operation { dest: (1 + i)..(1 + i), src: ch.to_string() };
+        reverts.push(buf.apply(&operation));
+        operations.push(operation);
+    }
+    let backspace = Operation { dest: 5..6, src: StdString::new() };
+    reverts.push(buf.apply(&backspace));
+    operations.push(backspace);
+    assert_eq!(&buf, "(hell)");
+
+    let squashed = squash(&operations);
+    assert_eq!(squashed, vec![Operation { dest: 1..1, src: StdString::from("hell") }]);
+
+    reverts.reverse();
+    let squashed = squash(&reverts);
+    assert_eq!(squashed, vec![Operation { dest: 1..5, src: StdString::new() }]);
+}
+
+#[quickcheck]
+fn apply_many_revert(init: StdString, splices: Vec<(SliceRange, StdString)>) -> bool {
+    let batch: Vec<_> = disjoint(&init, splices)
+        .into_iter()
+        .map(|(dest, src)| Operation { dest: dest, src: src })
+        .collect();
+
+    let mut buf = String::from(&init[..]);
+    let reverts = buf.apply_many(&batch);
+    let result = buf.to_string();
+
+    let mut seq = String::from(&result[..]);
+    for revert in reverts.iter().rev() {
+        seq.apply(revert);
+    }
+
+    let redo = buf.apply_many(&reverts);
+    buf == init && seq == init && { buf.apply_many(&redo); buf == result }
+}
+
+#[quickcheck]
+fn history_apply_many(init: StdString, splices: Vec<(SliceRange, StdString)>) -> bool {
+    let batch: Vec<_> = disjoint(&init, splices)
+        .into_iter()
+        .map(|(dest, src)| Operation { dest: dest, src: src })
+        .collect();
+
+    let mut history = History::new(String::from(&init[..]));
+    history.apply_many(&batch);
+    let result = history.get_ref().to_string();
+    history.undo();
+    let undone = *history.get_ref() == init;
+    history.redo();
+    undone && *history.get_ref() == result
+}
+
+#[quickcheck]
+fn apply_many_text_buffers(init: StdString, splices: Vec<(SliceRange, StdString)>) -> bool {
+    let batch: Vec<_> = disjoint(&init, splices)
+        .into_iter()
+        .map(|(dest, src)| Operation { dest: dest, src: src })
+        .collect();
+    let bytes: Vec<_> = batch.iter()
+        .map(|op| Operation { dest: op.dest.clone(), src: op.src.clone().into_bytes() })
+        .collect();
+
+    let mut buf = String::from(&init[..]);
+    let mut std = init.clone();
+    let mut piece = piece::String::from(&init[..]);
+    let mut vec = init.clone().into_bytes();
+    let reverts = buf.apply_many(&batch);
+
+    std.apply_many(&batch) == reverts
+        && piece.apply_many(&batch) == reverts
+        && vec.apply_many(&bytes).len() == reverts.len()
+        && buf == std
+        && piece == &std[..]
+        && vec == std.as_bytes()
+}
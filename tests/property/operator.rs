@@ -0,0 +1,263 @@
+use std::string::String as StdString;
+use std::usize;
+
+use viola::block::Block;
+use viola::gap::String;
+use viola::motion::Target;
+use viola::normal::Operator;
+use viola::object::Selection;
+use viola::operator::{self, Case, Edit, Options, Region};
+use viola::register::{Kind, Value};
+
+use fixture::{self, apply, with_gap};
+
+// Maps bytes to text with words, indents, cased chars and wide chars.
+fn indented(bytes: Vec<u8>) -> StdString {
+    let alphabet = ['a', 'B', ' ', '\t', '\n', 'é', 'ß', '.', '字'];
+    fixture::from_alphabet(bytes, &alphabet)
+}
+
+fn run(init: &str, operator: Operator, region: Region, options: &Options) -> (StdString, Edit) {
+    let edit = operator::operate(&String::from(init), 0, operator, &region, options);
+    (apply(init, &edit), edit)
+}
+
+fn changed(init: &str, operator: Operator, region: Region, options: &Options) -> StdString {
+    run(init, operator, region, options).0
+}
+
+fn value(text: &str, kind: Kind) -> Option<Value> {
+    Some(Value { text: text.to_owned(), kind: kind })
+}
+
+fn block(lines: ::std::ops::Range<usize>, cols: ::std::ops::Range<usize>) -> Region {
+    Region::Block(Block { lines: lines, cols: cols })
+}
+
+#[test]
+fn regions() {
+    let text = String::from("foo bar\n  baz\nqux\n\nend");
+    let target = |offset, inclusive, linewise| {
+        Target { offset: offset, inclusive: inclusive, linewise: linewise }
+    };
+    assert_eq!(Region::from_motion(&text, 0, target(4, false, false)), Region::Chars(0..4));
+    assert_eq!(Region::from_motion(&text, 2, target(0, true, false)), Region::Chars(0..3));
+    assert_eq!(Region::from_motion(&text, 2, target(8, false, false)), Region::Chars(2..7));
+    assert_eq!(Region::from_motion(&text, 0, target(8, false, false)), Region::Lines(0..1));
+    assert_eq!(Region::from_motion(&text, 10, target(0, false, true)), Region::Lines(0..2));
+    assert_eq!(Region::from_motion(&text, 18, target(18, true, false)), Region::Chars(18..18));
+
+    let selection = Selection { range: 0..14, linewise: true };
+    assert_eq!(Region::from_selection(&text, &selection), Region::Lines(0..2));
+    let selection = Selection { range: 18..18, linewise: true };
+    assert_eq!(Region::from_selection(&text, &selection), Region::Lines(3..4));
+    assert_eq!(Region::Chars(2..8).lines(&text), 0..1);
+    assert_eq!(Region::Chars(2..9).lines(&text), 0..2);
+}
+
+#[test]
+fn delete() {
+    let init = "one two\n  three\nfour";
+    let options = Options::default();
+    let (result, edit) = run(init, Operator::Delete, Region::Chars(0..4), &options);
+    assert_eq!(result, "two\n  three\nfour");
+    assert_eq!(edit.cursor, 0);
+    assert_eq!(edit.yanked, value("one ", Kind::Charwise));
+
+    let (result, edit) = run(init, Operator::Delete, Region::Lines(1..2), &options);
+    assert_eq!(result, "one two\nfour");
+    assert_eq!(edit.cursor, 8);
+    assert_eq!(edit.yanked, value("  three\n", Kind::Linewise));
+    assert_eq!(changed(init, Operator::Delete, Region::Lines(1..3), &options), "one two");
+    assert_eq!(changed(init, Operator::Delete, Region::Lines(0..3), &options), "");
+
+    let (result, edit) = run(init, Operator::Delete, Region::Chars(10..20), &options);
+    assert_eq!(result, "one two");
+    assert_eq!(edit.yanked, value("  three\nfour\n", Kind::Linewise));
+    let (result, edit) = run(init, Operator::Delete, Region::Chars(10..16), &options);
+    assert_eq!(result, "one two\n  four");
+    assert_eq!(edit.yanked, value("three\n", Kind::Charwise));
+    let (result, edit) = run(init, Operator::Delete, Region::Chars(10..17), &options);
+    assert_eq!(result, "one two\n  our");
+    assert_eq!(edit.cursor, 10);
+}
+
+#[test]
+fn blocks() {
+    let init = "abcdef\n\tx\nab\n";
+    let options = Options::default();
+    let (result, edit) = run(init, Operator::Delete, block(0..3, 2..4), &options);
+    assert_eq!(result, "abef\n      x\nab\n");
+    assert_eq!(edit.cursor, 2);
+    assert_eq!(edit.yanked, value("cd\n  \n", Kind::Blockwise));
+
+    let (result, edit) = run(init, Operator::Delete, block(0..3, 2..usize::MAX), &options);
+    assert_eq!(result, "ab\n  \nab\n");
+    assert_eq!(edit.yanked, value("cdef\n      x\n", Kind::Blockwise));
+
+    let init = "abcdef\nab字ef";
+    let (result, edit) = run(init, Operator::Uppercase, block(0..2, 1..3), &options);
+    assert_eq!(result, "aBCdef\naB字ef");
+    assert_eq!(edit.cursor, 1);
+    assert_eq!(changed(init, Operator::Change, block(0..2, 3..4), &options), "abcef\nab ef");
+}
+
+#[test]
+fn yank_and_change() {
+    let init = "foo\nbar baz";
+    let text = String::from(init);
+    let options = Options::default();
+    let edit = operator::yank(&text, 0, &Region::Lines(1..2), &options);
+    assert_eq!(edit.operations, []);
+    assert_eq!(edit.cursor, 4);
+    assert_eq!(edit.yanked, value("bar baz\n", Kind::Linewise));
+    assert_eq!(operator::yank(&text, 9, &Region::Chars(4..7), &options).cursor, 4);
+    assert_eq!(operator::yank(&text, 2, &Region::Lines(0..2), &options).cursor, 2);
+
+    let init = "  foo\n  bar\nbaz";
+    let (result, edit) = run(init, Operator::Change, Region::Lines(0..2), &options);
+    assert_eq!(result, "\nbaz");
+    assert_eq!(edit.cursor, 0);
+    assert_eq!(edit.yanked, value("  foo\n  bar\n", Kind::Linewise));
+    let autoindent = Options { autoindent: true, ..options };
+    let (result, edit) = run(init, Operator::Change, Region::Lines(0..2), &autoindent);
+    assert_eq!(result, "  \nbaz");
+    assert_eq!(edit.cursor, 2);
+    assert_eq!(changed(init, Operator::Change, Region::Chars(2..6), &options), "    bar\nbaz");
+}
+
+#[test]
+fn shifts() {
+    let init = "foo\n\n    bar\n\tbaz";
+    let options = Options { shiftwidth: 4, ..Options::default() };
+    let (result, edit) = run(init, Operator::ShiftRight, Region::Lines(0..4), &options);
+    assert_eq!(result, "    foo\n\n\tbar\n\t    baz");
+    assert_eq!(edit.cursor, 4);
+    let expandtab = Options { expandtab: true, ..options };
+    let result = changed(init, Operator::ShiftRight, Region::Lines(0..4), &expandtab);
+    assert_eq!(result, "    foo\n\n        bar\n            baz");
+    let (result, edit) = run(init, Operator::ShiftLeft, Region::Lines(0..4), &expandtab);
+    assert_eq!(result, "foo\n\nbar\n    baz");
+    assert_eq!(edit.cursor, 0);
+    let edit = operator::shift(&String::from(init), &Region::Chars(5..12), -2, &options);
+    assert_eq!(apply(init, &edit), "foo\n\nbar\n\tbaz");
+
+    let init = "ab  cd\nabcd\na";
+    let result = changed(init, Operator::ShiftRight, block(0..3, 2..3), &expandtab);
+    assert_eq!(result, "ab      cd\nab    cd\na");
+    let result = changed(init, Operator::ShiftLeft, block(0..3, 2..3), &expandtab);
+    assert_eq!(result, "abcd\nabcd\na");
+    let result = changed(init, Operator::ShiftRight, block(0..1, 2..3), &Options::default());
+    assert_eq!(result, "ab\t    cd\nabcd\na");
+}
+
+#[test]
+fn cases() {
+    let options = Options::default();
+    assert_eq!(changed("Hello World", Operator::ToggleCase, Region::Chars(0..5), &options),
+               "hELLO World");
+    assert_eq!(changed("ß x\nY", Operator::Uppercase, Region::Lines(0..1), &options), "SS X\nY");
+    assert_eq!(changed("ß x\nY", Operator::Lowercase, Region::Lines(0..2), &options), "ß x\ny");
+    let edit = operator::change_case(&String::from("abc"), 2, &Region::Chars(0..3), Case::Lower,
+                                     &options);
+    assert_eq!(edit.operations, []);
+    assert_eq!(edit.cursor, 0);
+}
+
+#[test]
+fn indents() {
+    let init = "  a\nb\n   \n\tc";
+    let options = Options::default();
+    let (result, edit) = run(init, Operator::Indent, Region::Lines(1..4), &options);
+    assert_eq!(result, "  a\n  b\n\n  c");
+    assert_eq!(edit.cursor, 6);
+    assert_eq!(changed(init, Operator::Indent, Region::Lines(0..2), &options), "a\nb\n   \n\tc");
+    let init = "\t\tx\ny";
+    let options = Options { tabstop: 4, ..options };
+    assert_eq!(changed(init, Operator::Indent, Region::Lines(1..2), &options), "\t\tx\n\t\ty");
+}
+
+#[test]
+fn formats() {
+    let init = "one two three four\n\n  five six seven eight nine";
+    let options = Options { textwidth: 10, ..Options::default() };
+    let (result, edit) = run(init, Operator::Format, Region::Lines(0..3), &options);
+    assert_eq!(result, "one two\nthree four\n\n  five six\nseven\neight nine");
+    assert_eq!(edit.cursor, 37);
+    let autoindent = Options { autoindent: true, ..options };
+    let (result, edit) = run(init, Operator::Format, Region::Lines(1..3), &autoindent);
+    assert_eq!(result, "one two three four\n\n  five six\n  seven\n  eight\n  nine");
+    assert_eq!(edit.cursor, 49);
+
+    let options = Options { textwidth: 5, ..options };
+    assert_eq!(changed("abcdefghijkl x", Operator::Format, Region::Lines(0..1), &options),
+               "abcdefghijkl\nx");
+    assert_eq!(changed("a\nb\nc", Operator::Format, Region::Lines(0..3), &Options::default()),
+               "a b c");
+}
+
+#[test]
+fn puts() {
+    let options = Options::default();
+    let put = |init: &str, cursor, value: Value, count, before| {
+        let edit = operator::put(&String::from(init), cursor, &value, count, before, &options);
+        (apply(init, &edit), edit.cursor)
+    };
+    let chars = |text: &str| Value { text: text.to_owned(), kind: Kind::Charwise };
+    let lines = |text: &str| Value { text: text.to_owned(), kind: Kind::Linewise };
+    let block = |text: &str| Value { text: text.to_owned(), kind: Kind::Blockwise };
+
+    assert_eq!(put("abc", 0, chars("XY"), 2, false), ("aXYXYbc".to_owned(), 4));
+    assert_eq!(put("abc", 1, chars("XY"), 1, true), ("aXYbc".to_owned(), 2));
+    assert_eq!(put("a\n\nb", 2, chars("XY"), 1, false), ("a\nXY\nb".to_owned(), 3));
+    assert_eq!(put("abc", 0, chars("1\n2"), 1, false), ("a1\n2bc".to_owned(), 1));
+    assert_eq!(put("ab\u{301}c", 0, chars("xe\u{301}"), 1, true).1, 1);
+
+    assert_eq!(put("one\ntwo", 0, lines("  x\n"), 1, false), ("one\n  x\ntwo".to_owned(), 6));
+    assert_eq!(put("one\ntwo", 5, lines("  x\n"), 1, true), ("one\n  x\ntwo".to_owned(), 6));
+    assert_eq!(put("one\ntwo", 5, lines("  x\n"), 2, false),
+               ("one\ntwo\n  x\n  x".to_owned(), 10));
+    assert_eq!(put("a\n", 0, lines("x\n"), 1, false), ("a\nx\n".to_owned(), 2));
+
+    let init = "abcd\nab\n\tx";
+    assert_eq!(put(init, 0, block("12\n3\n45\n6"), 1, false),
+               ("a12bcd\na3 b\n 45       x\n 6".to_owned(), 1));
+    assert_eq!(put(init, 0, block("12\n3\n45\n6"), 1, true),
+               ("12abcd\n3 ab\n45\tx\n6".to_owned(), 0));
+    assert_eq!(put("abc\n", 2, block("x\ny"), 2, false), ("abcxx\n   yy".to_owned(), 3));
+    assert_eq!(put("ab\ncd", 0, block("x\nyz"), 2, true), ("x x ab\nyzyzcd".to_owned(), 0));
+}
+
+#[quickcheck]
+fn revertible(bytes: Vec<u8>, gap: usize, ends: (usize, usize), kind: u8) -> bool {
+    let init = indented(bytes);
+    let boundaries: Vec<_> = (0..(init.len() + 1)).filter(|&i| init.is_char_boundary(i)).collect();
+    let gap = boundaries[gap % boundaries.len()];
+    let (contiguous, fragmented) = (String::from(&init[..]), with_gap(&init, gap));
+    let lines = contiguous.line_count();
+    let region = match kind % 4 {
+        0 => {
+            let n = boundaries.len();
+            let (a, b) = (boundaries[ends.0 % n], boundaries[ends.1 % n]);
+            Region::Chars(a.min(b)..a.max(b))
+        },
+        1 => {
+            let (a, b) = (ends.0 % lines, ends.1 % lines);
+            Region::Lines(a.min(b)..(a.max(b) + 1))
+        },
+        2 => block(0..lines, (ends.0 % 12)..(ends.0 % 12 + ends.1 % 5 + 1)),
+        _ => block((ends.0 % lines)..lines, (ends.1 % 12)..usize::MAX),
+    };
+    let options = Options { textwidth: 6, shiftwidth: 3, ..Options::default() };
+    let operators = [
+        Operator::Delete, Operator::Change, Operator::Yank, Operator::ShiftLeft,
+        Operator::ShiftRight, Operator::Lowercase, Operator::Uppercase, Operator::ToggleCase,
+        Operator::Indent, Operator::Format,
+    ];
+    operators.iter().all(|&operator| {
+        let edit = operator::operate(&contiguous, 0, operator, &region, &options);
+        let result = apply(&init, &edit);
+        edit == operator::operate(&fragmented, 0, operator, &region, &options)
+            && edit.cursor <= result.len()
+    })
+}
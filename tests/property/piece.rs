@@ -0,0 +1,115 @@
+use std::string::String as StdString;
+
+use quickcheck::TestResult;
+use viola::gap;
+use viola::piece::String;
+use viola::text::TextBuffer;
+
+use gap::{SliceRange, Splice};
+
+macro_rules! discard {
+    ($range:expr, $slice:expr) => {
+        if !$range.valid_str($slice) {
+            return TestResult::discard();
+        }
+    }
+}
+
+impl Splice<str> for String {
+    fn splice(&mut self, dest: SliceRange, src: &str) {
+        self.splice(dest, src);
+    }
+}
+
+#[quickcheck]
+fn from_std(init: StdString) -> bool {
+    let buf = String::from(init.clone());
+    buf == &init[..] && buf.into(): StdString == init
+}
+
+#[quickcheck]
+fn slice(init: StdString, range: SliceRange) -> TestResult {
+    discard!(range, &init);
+    let buf = String::from(&init[..]);
+    TestResult::from_bool(buf.slice(range) == &init[range])
+}
+
+#[quickcheck]
+fn splice_splice(
+    init: StdString,
+    one: (SliceRange, StdString),
+    two: (SliceRange, StdString),
+) -> TestResult {
+    discard!(one.0, &init);
+    let mut std = init.clone();
+    let mut buf = String::from(&init[..]);
+
+    Splice::splice(&mut std, one.0, &one.1);
+    Splice::splice(&mut buf, one.0, &one.1);
+
+    discard!(two.0, &std);
+    Splice::splice(&mut std, two.0, &two.1);
+    Splice::splice(&mut buf, two.0, &two.1);
+
+    TestResult::from_bool(buf == &std[..] && buf.len() == std.len())
+}
+
+#[quickcheck]
+fn splice_slice_chars(
+    init: StdString,
+    dest: SliceRange,
+    src: StdString,
+    range: SliceRange,
+) -> TestResult {
+    discard!(dest, &init);
+    let mut std = init.clone();
+    let mut buf = String::from(&init[..]);
+    Splice::splice(&mut std, dest, &src);
+    Splice::splice(&mut buf, dest, &src);
+
+    discard!(range, &std);
+    let slice = buf.slice(range);
+    TestResult::from_bool(
+        slice.char_indices().eq(std[range].char_indices())
+            && slice.char_indices().rev().eq(std[range].char_indices().rev())
+    )
+}
+
+#[test]
+fn splice_typing() {
+    let mut buf = String::from("hello world");
+    for (i, ch) in ", there".char_indices() {
+        buf.splice(5 + i..5 + i, &ch.to_string());
+    }
+    assert_eq!(buf.to_string(), "hello, there world");
+    assert_eq!(buf.pieces(), 3);
+}
+
+// Applies the same splices to both backends through the common trait.
+fn splice_all<T: TextBuffer<Unit = str>>(buf: &mut T, splices: &[(SliceRange, StdString)]) {
+    for &(dest, ref src) in splices {
+        buf.splice(dest, src);
+    }
+}
+
+#[quickcheck]
+fn backends_agree(init: StdString, splices: Vec<(SliceRange, StdString)>) -> bool {
+    // Keep only splices which are valid in sequence.
+    let mut std = init.clone();
+    let splices: Vec<_> = splices.into_iter().filter(|&(dest, ref src)| {
+        let valid = dest.valid_str(&std);
+        if valid {
+            Splice::splice(&mut std, dest, src);
+        }
+        valid
+    }).collect();
+
+    let mut gap = gap::String::from(&init[..]);
+    let mut piece = String::from(&init[..]);
+    splice_all(&mut gap, &splices);
+    splice_all(&mut piece, &splices);
+
+    let gap = TextBuffer::chunks(&gap, ..);
+    let piece = TextBuffer::chunks(&piece, ..);
+    gap == &std[..] && piece == &std[..] && gap.chars().eq(piece.chars())
+}
@@ -0,0 +1,234 @@
+use std::ops::Range;
+use std::string::String as StdString;
+
+use quickcheck::TestResult;
+use viola::gap::Str;
+use viola::regex::{Error, Magic, Options, Regex};
+
+use fixture::{self, with_gap};
+
+// Maps bytes to a pattern built from a small set of items.
+fn pattern(bytes: Vec<u8>) -> StdString {
+    let items = [
+        "a", "b", " ", "\\n", ".", "*", "\\+", "\\=", "\\|", "\\(", "\\)", "\\%(", "^", "$",
+        "\\<", "\\>", "[ab]", "[^a]", "\\{1,2}", "\\{-}", "\\zs", "\\ze", "\\s", "\\_.",
+    ];
+    fixture::from_alphabet(bytes, &items)
+}
+
+// Maps bytes to text in the alphabet of the patterns.
+fn text(bytes: Vec<u8>) -> StdString {
+    let alphabet = ['a', 'b', 'A', ' ', '\n'];
+    fixture::from_alphabet(bytes, &alphabet)
+}
+
+// Returns the text with the gap at `gap`.
+fn find(pattern: &str, haystack: &str) -> Option<Range<usize>> {
+    Regex::new(pattern).unwrap().find_at(Str::Contiguous(haystack), 0)
+}
+
+#[quickcheck]
+fn fragments_agree(pattern: Vec<u8>, init: Vec<u8>, gap: usize) -> TestResult {
+    let regex = match Regex::new(&self::pattern(pattern)) {
+        Ok(regex) => regex,
+        Err(_) => return TestResult::discard(),
+    };
+    let init = text(init);
+    let buf = with_gap(&init, gap % (init.len() + 1));
+
+    let contiguous = Str::Contiguous(&init);
+    let expected: Vec<_> = regex.find_iter(contiguous).collect();
+    let actual: Vec<_> = regex.find_iter(buf.as_str()).collect();
+    let offsets = (0..(init.len() + 1)).all(|offset| {
+        regex.captures_at(contiguous, offset) == regex.captures_at(buf.as_str(), offset)
+            && regex.rcaptures_at(contiguous, offset) == regex.rcaptures_at(buf.as_str(), offset)
+    });
+    TestResult::from_bool(expected == actual && offsets)
+}
+
+#[quickcheck]
+fn literal(init: Vec<u8>, needle: Vec<u8>, gap: usize) -> TestResult {
+    let (init, needle) = (text(init), text(needle));
+    if needle.is_empty() {
+        return TestResult::discard();
+    }
+    let buf = with_gap(&init, gap % (init.len() + 1));
+    let regex = Regex::new(&format!("\\V{}", needle.replace('\n', "\\n"))).unwrap();
+
+    let expected: Vec<_> = buf.as_str().match_indices(&needle).collect();
+    let actual: Vec<_> = regex.find_iter(buf.as_str()).collect();
+    TestResult::from_bool(expected == actual)
+}
+
+#[quickcheck]
+fn rfind_literal(init: Vec<u8>, needle: Vec<u8>, gap: usize) -> TestResult {
+    let (init, needle) = (text(init), text(needle));
+    if needle.is_empty() || needle.contains('\n') {
+        return TestResult::discard();
+    }
+    let buf = with_gap(&init, gap % (init.len() + 1));
+    let regex = Regex::new(&format!("\\V{}", needle)).unwrap();
+
+    let matches: Vec<_> = buf.as_str().match_indices(&needle).collect();
+    TestResult::from_bool((0..(init.len() + 1)).all(|offset| {
+        let expected = matches.iter().filter(|m| m.start < offset).last().cloned();
+        regex.rfind_at(buf.as_str(), offset) == expected
+    }))
+}
+
+#[test]
+fn magic() {
+    assert_eq!(find("a.c", "xabc"), Some(1..4));
+    assert_eq!(find("a\\.c", "abc a.c"), Some(4..7));
+    assert_eq!(find("\\Ma.c", "abc a.c"), Some(4..7));
+    assert_eq!(find("\\Ma\\.c", "abc"), Some(0..3));
+    assert_eq!(find("\\Va*", "aa a*"), Some(3..5));
+    assert_eq!(find("\\V\\^a\\$", "a"), Some(0..1));
+    assert_eq!(find("\\v(ab)+", "xababc"), Some(1..5));
+    assert_eq!(find("\\v\\(", "a(b"), Some(1..2));
+    assert_eq!(find("(ab)\\+", "(ab)))"), Some(0..6));
+    assert_eq!(find("a\\vb+\\mc+", "abbc+"), Some(0..5));
+    assert_eq!(find("[a", "x[a"), Some(1..3));
+    assert_eq!(find("*a", "b*a"), Some(1..3));
+}
+
+#[test]
+fn anchors() {
+    assert_eq!(find("^b", "ab\nb"), Some(3..4));
+    assert_eq!(find("a$", "ab\nba"), Some(4..5));
+    assert_eq!(find("a^b", "a^b"), Some(0..3));
+    assert_eq!(find("a$b", "a$b"), Some(0..3));
+    assert_eq!(find("a\\_$\\n\\_^b", "a\nb"), Some(0..3));
+    assert_eq!(find("\\%^a", "aa"), Some(0..1));
+    assert_eq!(find("a\\%$", "aa"), Some(1..2));
+    assert_eq!(find("\\<is\\>", "this is"), Some(5..7));
+    assert_eq!(find("\\<a", "ba a"), Some(3..4));
+    assert_eq!(find("\\vfoo>", "foobar foo"), Some(7..10));
+    assert_eq!(find("foo\\zsbar", "foobar"), Some(3..6));
+    assert_eq!(find("foo\\zebar", "foo foobar"), Some(4..7));
+}
+
+#[test]
+fn repeats() {
+    assert_eq!(find("a\\{2,3}", "aaaa"), Some(0..3));
+    assert_eq!(find("a\\{-2,3}", "aaaa"), Some(0..2));
+    assert_eq!(find("a\\{2}", "a aa"), Some(2..4));
+    assert_eq!(find("a\\{2,}", "aaaaa"), Some(0..5));
+    assert_eq!(find("ba\\{,2}", "baaa"), Some(0..3));
+    assert_eq!(find("ba\\{3,1\\}", "baaaa"), Some(0..4));
+    assert_eq!(find("ba\\{}", "baaa"), Some(0..4));
+    assert_eq!(find("ba\\{-}", "baaa"), Some(0..1));
+    assert_eq!(find("\\va{1,2}b", "aaab"), Some(1..4));
+    assert_eq!(find("a\\=b", "b"), Some(0..1));
+    assert_eq!(find("a\\?b", "ab"), Some(0..2));
+    assert_eq!(find("\\(a*\\)*b", "aab"), Some(0..3));
+}
+
+#[test]
+fn classes() {
+    assert_eq!(find("\\d\\+", "ab 123"), Some(3..6));
+    assert_eq!(find("\\S\\+", "  ab "), Some(2..4));
+    assert_eq!(find("\\u\\l", "aBc"), Some(1..3));
+    assert_eq!(find("\\x\\+", "xyz ff0"), Some(4..7));
+    assert_eq!(find("\\k\\+", "..é_1.."), Some(2..6));
+    assert_eq!(find("[[:digit:]x-z]\\+", "ab1xz2w"), Some(2..6));
+    assert_eq!(find("[^a-c]", "abcd"), Some(3..4));
+    assert_eq!(find("[]a]\\+", "b]a]"), Some(1..4));
+    assert_eq!(find("[\\]\\\\]", "a\\"), Some(1..2));
+    assert_eq!(find("[^a]", "a\nb"), Some(2..3));
+    assert_eq!(find("\\_[^a]", "a\nb"), Some(1..2));
+    assert_eq!(find("a.b", "a\nb"), None);
+    assert_eq!(find("a\\_.b", "a\nb"), Some(0..3));
+    assert_eq!(find("a\\_s*b", "a \n b"), Some(0..5));
+}
+
+#[test]
+fn alternation() {
+    assert_eq!(find("ab\\|a", "ab"), Some(0..2));
+    assert_eq!(find("a\\|ab", "ab"), Some(0..1));
+    assert_eq!(find("x\\|\\(b\\|c\\)", "abc"), Some(1..2));
+    assert_eq!(find("\\v<(if|in)>", "int in"), Some(4..6));
+}
+
+#[test]
+fn case() {
+    let ignore = Options { ignore_case: true, ..Options::default() };
+    let smart = Options { smart_case: true, ..ignore };
+    let find_with = |pattern: &str, options: &Options, haystack: &str| {
+        Regex::with_options(pattern, options).unwrap().find_at(Str::Contiguous(haystack), 0)
+    };
+    assert_eq!(find_with("abc", &ignore, "xAbC"), Some(1..4));
+    assert_eq!(find_with("[a-c]\\+", &ignore, "xAbC"), Some(1..4));
+    assert_eq!(find_with("é", &ignore, "É"), Some(0..2));
+    assert_eq!(find_with("abc", &smart, "xAbC"), Some(1..4));
+    assert_eq!(find_with("Abc", &smart, "xAbC abc Abc"), Some(9..12));
+    assert_eq!(find_with("\\Sbc", &smart, "xAbC"), Some(1..4));
+    assert_eq!(find_with("abc\\C", &ignore, "ABC abc"), Some(4..7));
+    assert_eq!(find("\\cabc", "ABC"), Some(0..3));
+    assert_eq!(find_with("\\vab+", &Options { magic: Magic::VeryNoMagic, ..ignore },
+        "ABB"), Some(0..3));
+}
+
+#[test]
+fn captures() {
+    let regex = Regex::new("\\(\\w\\+\\) \\(x\\)\\=\\%(\\(\\d\\)\\)").unwrap();
+    assert_eq!(regex.captures_len(), 4);
+    let captures = regex.captures_at(Str::Contiguous("-- foo 1"), 0).unwrap();
+    assert_eq!(captures.get(0), Some(3..8));
+    assert_eq!(captures.get(1), Some(3..6));
+    assert_eq!(captures.get(2), None);
+    assert_eq!(captures.get(3), Some(7..8));
+    assert_eq!(captures.get(4), None);
+
+    // The last iteration of a repeated group is captured.
+    let regex = Regex::new("\\(\\a\\)*").unwrap();
+    let captures = regex.captures_at(Str::Contiguous("abc"), 0).unwrap();
+    assert_eq!(captures.get(1), Some(2..3));
+}
+
+#[test]
+fn search_from_offset() {
+    let buf = with_gap("one two\nthree two\n", 10);
+    let regex = Regex::new("t\\w*").unwrap();
+    assert_eq!(regex.find_at(buf.as_str(), 0), Some(4..7));
+    assert_eq!(regex.find_at(buf.as_str(), 5), Some(8..13));
+    assert_eq!(regex.find_at(buf.as_str(), 7), Some(8..13));
+    assert_eq!(regex.find_at(buf.as_str(), 15), None);
+    assert_eq!(regex.rfind_at(buf.as_str(), 18), Some(14..17));
+    assert_eq!(regex.rfind_at(buf.as_str(), 14), Some(8..13));
+    assert_eq!(regex.rfind_at(buf.as_str(), 8), Some(4..7));
+    assert_eq!(regex.rfind_at(buf.as_str(), 4), None);
+
+    // Context before the offset is visible.
+    let regex = Regex::new("^t").unwrap();
+    assert_eq!(regex.find_at(buf.as_str(), 5), Some(8..9));
+    let regex = Regex::new("\\<wo").unwrap();
+    assert_eq!(regex.find_at(buf.as_str(), 5), None);
+    let matches: Vec<_> = Regex::new("x*").unwrap().find_iter(Str::Contiguous("axxé")).collect();
+    assert_eq!(matches, [0..0, 1..3, 3..3, 5..5]);
+}
+
+#[test]
+fn backward_across_lines() {
+    // Each line has a match start, but searching on from each would be quadratic.
+    let init = "ax\n".repeat(20_000);
+    let buf = with_gap(&init, init.len() / 2);
+    let end = init.len();
+    assert_eq!(Regex::new("x\\_.*z").unwrap().rfind_at(buf.as_str(), end), None);
+    let regex = Regex::new("a\\_.*x").unwrap();
+    assert_eq!(regex.rfind_at(buf.as_str(), end), Some((end - 3)..(end - 1)));
+    assert_eq!(regex.rfind_at(buf.as_str(), 4), Some(3..(end - 1)));
+}
+
+#[test]
+fn errors() {
+    assert_eq!(Regex::new("\\(a").unwrap_err(), Error::UnmatchedParen);
+    assert_eq!(Regex::new("a\\)").unwrap_err(), Error::UnmatchedParen);
+    assert_eq!(Regex::new("a\\{1,x}").unwrap_err(), Error::InvalidCount);
+    assert_eq!(Regex::new("\\+").unwrap_err(), Error::NothingToRepeat);
+    assert_eq!(Regex::new("a\\").unwrap_err(), Error::TrailingBackslash);
+    assert_eq!(Regex::new("\\_[a").unwrap_err(), Error::UnmatchedBracket);
+    assert_eq!(Regex::new("a\\{1000}\\{1000}").unwrap_err(), Error::TooLarge);
+    assert_eq!(Regex::new("\\(a\\)\\1").unwrap_err(), Error::Unsupported("\\1".to_owned()));
+    assert_eq!(Regex::new("a\\@=").unwrap_err(), Error::Unsupported("\\@".to_owned()));
+}
@@ -0,0 +1,133 @@
+use std::string::String as StdString;
+
+use viola::normal::Motion;
+use viola::register::{Error, Kind, Registers, Value};
+
+fn chars(text: &str) -> Value {
+    Value { text: text.to_owned(), kind: Kind::Charwise }
+}
+
+fn lines(text: &str) -> Value {
+    Value { text: text.to_owned(), kind: Kind::Linewise }
+}
+
+fn block(text: &str) -> Value {
+    Value { text: text.to_owned(), kind: Kind::Blockwise }
+}
+
+// Returns the content of `a` after yanking two values into `a` and `A`.
+fn appended(first: Value, second: Value) -> Value {
+    let mut registers = Registers::new();
+    registers.yank(Some('a'), first).unwrap();
+    registers.yank(Some('A'), second).unwrap();
+    registers.get('a').unwrap()
+}
+
+#[test]
+fn yanks() {
+    let mut registers = Registers::new();
+    assert_eq!(registers.get('"'), None);
+    registers.yank(None, chars("foo")).unwrap();
+    assert_eq!(registers.get('0'), Some(chars("foo")));
+    assert_eq!(registers.get('"'), Some(chars("foo")));
+
+    registers.yank(Some('a'), lines("bar\n")).unwrap();
+    assert_eq!(registers.get('a'), Some(lines("bar\n")));
+    assert_eq!(registers.get('A'), Some(lines("bar\n")));
+    assert_eq!(registers.get('"'), Some(lines("bar\n")));
+    assert_eq!(registers.get('0'), Some(chars("foo")));
+    registers.yank(Some('B'), chars("x")).unwrap();
+    assert_eq!(registers.get('b'), Some(chars("x")));
+
+    registers.yank(Some('_'), chars("gone")).unwrap();
+    assert_eq!(registers.get('_'), None);
+    assert_eq!(registers.get('"'), Some(chars("x")));
+    registers.yank(Some('"'), chars("q")).unwrap();
+    assert_eq!(registers.get('0'), Some(chars("q")));
+    registers.yank(Some('5'), chars("five")).unwrap();
+    assert_eq!(registers.get('"'), Some(chars("five")));
+
+    assert_eq!(registers.yank(Some('%'), chars("x")), Err(Error::ReadOnly('%')));
+    assert_eq!(registers.yank(Some('!'), chars("x")), Err(Error::Invalid('!')));
+    assert_eq!(registers.get('"'), Some(chars("five")));
+}
+
+#[test]
+fn deletes() {
+    let mut registers = Registers::new();
+    let word = Some(Motion::WordStart { big: false });
+    registers.delete(None, chars("w"), word).unwrap();
+    assert_eq!(registers.get('-'), Some(chars("w")));
+    assert_eq!(registers.get('1'), None);
+    assert_eq!(registers.get('"'), Some(chars("w")));
+
+    registers.delete(None, lines("one\n"), None).unwrap();
+    registers.delete(None, lines("two\n"), None).unwrap();
+    assert_eq!(registers.get('1'), Some(lines("two\n")));
+    assert_eq!(registers.get('2'), Some(lines("one\n")));
+    registers.delete(Some('"'), chars("a\nb"), word).unwrap();
+    assert_eq!(registers.get('1'), Some(chars("a\nb")));
+    assert_eq!(registers.get('3'), Some(lines("one\n")));
+    assert_eq!(registers.get('-'), Some(chars("w")));
+
+    registers.delete(Some('a'), chars("x"), word).unwrap();
+    assert_eq!(registers.get('a'), Some(chars("x")));
+    assert_eq!(registers.get('-'), Some(chars("w")));
+    assert_eq!(registers.get('"'), Some(chars("x")));
+    registers.delete(Some('a'), lines("l\n"), None).unwrap();
+    assert_eq!(registers.get('a'), Some(lines("l\n")));
+    assert_eq!(registers.get('1'), Some(lines("l\n")));
+    assert_eq!(registers.get('4'), Some(lines("one\n")));
+
+    registers.delete(None, chars("(x)"), Some(Motion::MatchPair)).unwrap();
+    assert_eq!(registers.get('1'), Some(chars("(x)")));
+    assert_eq!(registers.get('-'), Some(chars("(x)")));
+    let mark = Some(Motion::Mark { name: 'a', linewise: true });
+    registers.delete(None, chars("y"), mark).unwrap();
+    assert_eq!(registers.get('1'), Some(chars("(x)")));
+
+    let before = registers.clone();
+    registers.delete(Some('_'), lines("gone\n"), None).unwrap();
+    assert_eq!(registers.delete(Some(':'), lines("x\n"), None), Err(Error::ReadOnly(':')));
+    assert_eq!(registers, before);
+}
+
+#[test]
+fn appends() {
+    assert_eq!(appended(chars("ab"), chars("cd")), chars("abcd"));
+    assert_eq!(appended(chars("ab"), lines("cd\n")), lines("ab\ncd\n"));
+    assert_eq!(appended(lines("ab\n"), chars("cd")), lines("ab\ncd\n"));
+    assert_eq!(appended(lines("ab\n"), lines("cd\n")), lines("ab\ncd\n"));
+    assert_eq!(appended(block("a\nb"), chars("c")), block("a\nb\nc"));
+    assert_eq!(appended(chars("a"), block("b\nc")), chars("ab\nc"));
+    assert_eq!(appended(block("a\nb"), lines("c\n")), lines("a\nb\nc\n"));
+}
+
+#[test]
+fn read_only() {
+    let mut registers = Registers::new();
+    assert_eq!(registers.get('.'), None);
+    registers.set_last_insert("inserted");
+    registers.set_file_name("src/lib.rs");
+    registers.set_last_command("s/a/b/g");
+    registers.set_last_search("fo\\+");
+    assert_eq!(registers.get('.'), Some(chars("inserted")));
+    assert_eq!(registers.get('%'), Some(chars("src/lib.rs")));
+    assert_eq!(registers.get(':'), Some(chars("s/a/b/g")));
+    assert_eq!(registers.get('/'), Some(chars("fo\\+")));
+    assert_eq!(registers.yank(Some('/'), chars("x")), Err(Error::ReadOnly('/')));
+    assert_eq!(registers.get('"'), None);
+}
+
+#[quickcheck]
+fn numbered_shift(deletes: Vec<StdString>) -> bool {
+    let mut registers = Registers::new();
+    for text in &deletes {
+        registers.delete(None, lines(&format!("{}\n", text)), None).unwrap();
+    }
+    (1..10).all(|index| {
+        let name = (b'0' + index as u8) as char;
+        let expected = deletes.iter().rev().nth(index - 1);
+        registers.get(name) == expected.map(|text| lines(&format!("{}\n", text)))
+    })
+}
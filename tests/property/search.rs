@@ -0,0 +1,131 @@
+use std::string::String as StdString;
+
+use quickcheck::TestResult;
+use viola::gap::String;
+use viola::regex::{self, Regex};
+use viola::search::{Count, Direction, Options, Search};
+
+// Maps bytes to lines of text in a small alphabet.
+fn text(bytes: Vec<u8>) -> StdString {
+    let alphabet = ['a', 'b', 'A', ' ', '\n'];
+    bytes.into_iter().map(|b| alphabet[b as usize % alphabet.len()]).collect()
+}
+
+fn search(text: &String, direction: Direction, origin: usize, pattern: &str) -> Search {
+    let mut search = Search::new(direction, origin, Options::default());
+    search.set_pattern(text, pattern).unwrap();
+    search
+}
+
+#[test]
+fn incremental() {
+    let text = String::from("foo bar\nfob baz\nfoo");
+    let mut search = Search::new(Direction::Forward, 4, Options::default());
+    assert_eq!(search.current(), None);
+    assert_eq!(search.count(&text), None);
+
+    search.set_pattern(&text, "f").unwrap();
+    assert_eq!(search.current(), Some(8..9));
+    search.set_pattern(&text, "foo").unwrap();
+    assert_eq!(search.current(), Some(16..19));
+    assert_eq!(search.count(&text), Some(Count { index: 2, total: 2 }));
+    assert!(!search.wrapped());
+
+    assert!(search.set_pattern(&text, "foo\\(").is_err());
+    assert_eq!(search.current(), None);
+    search.set_pattern(&text, "fo\\+b").unwrap();
+    assert_eq!(search.current(), Some(8..11));
+    search.set_pattern(&text, "").unwrap();
+    assert_eq!(search.current(), None);
+}
+
+#[test]
+fn next_and_prev() {
+    let text = String::from("foo bar\nfob baz\nfoo");
+    let mut search = search(&text, Direction::Backward, 9, "fo");
+    assert_eq!(search.current(), Some(8..10));
+    assert_eq!(search.find_next(&text), Some(0..2));
+    assert!(!search.wrapped());
+    assert_eq!(search.find_next(&text), Some(16..18));
+    assert!(search.wrapped());
+    assert_eq!(search.count(&text), Some(Count { index: 3, total: 3 }));
+    assert_eq!(search.find_prev(&text), Some(0..2));
+    assert!(search.wrapped());
+    assert_eq!(search.count(&text), Some(Count { index: 1, total: 3 }));
+
+    let options = Options { wrapscan: false, ..Options::default() };
+    let mut search = Search::new(Direction::Forward, 0, options);
+    search.set_pattern(&text, "ba").unwrap();
+    assert_eq!(search.find_next(&text), Some(12..14));
+    assert_eq!(search.find_next(&text), None);
+    assert_eq!(search.current(), Some(12..14));
+    search.set_pattern(&text, "foo").unwrap();
+    assert_eq!(search.current(), Some(16..19));
+    assert_eq!(search.find_next(&text), None);
+}
+
+#[test]
+fn case() {
+    let text = String::from("Foo foo FOO");
+    let options = Options {
+        regex: regex::Options { ignore_case: true, smart_case: true, ..Default::default() },
+        ..Options::default()
+    };
+    let mut search = Search::new(Direction::Forward, 0, options);
+    search.set_pattern(&text, "foo").unwrap();
+    assert_eq!(search.current(), Some(4..7));
+    assert_eq!(search.count(&text), Some(Count { index: 2, total: 3 }));
+    search.set_pattern(&text, "FOO").unwrap();
+    assert_eq!(search.current(), Some(8..11));
+    assert_eq!(search.count(&text), Some(Count { index: 1, total: 1 }));
+    search.set_pattern(&text, "FOO\\c").unwrap();
+    assert_eq!(search.current(), Some(4..7));
+}
+
+#[test]
+fn visible() {
+    let text = String::from("ab ab\nab\nab ab");
+    let search = search(&text, Direction::Forward, 0, "b \\|a");
+    assert_eq!(search.visible(&text, 4..10), [6..7, 9..10]);
+    assert_eq!(search.visible(&text, 2..3), [1..3]);
+    assert!(search.visible(&text, 3..3).is_empty());
+}
+
+#[quickcheck]
+fn count(init: Vec<u8>, pattern: Vec<u8>, origin: usize, forward: bool) -> TestResult {
+    let (init, pattern) = (text(init), text(pattern));
+    if pattern.is_empty() || !init.is_char_boundary(origin) {
+        return TestResult::discard();
+    }
+    let text = String::from(&init[..]);
+    let direction = if forward { Direction::Forward } else { Direction::Backward };
+    let search = search(&text, direction, origin, &format!("\\V{}", pattern));
+
+    let matches: Vec<_> = init.match_indices(&pattern[..]).map(|(i, _)| i).collect();
+    let expected = match direction {
+        Direction::Forward => matches.iter().find(|&&i| i > origin).or(matches.first()),
+        Direction::Backward => matches.iter().rev().find(|&&i| i < origin).or(matches.last()),
+    };
+    let current = search.current().map(|current| current.start);
+    let count = search.count(&text);
+    let index = expected.map(|&start| matches.iter().position(|&i| i == start).unwrap() + 1);
+    TestResult::from_bool(current == expected.cloned() && count.map(|count| count.index) == index
+        && count.map_or(0, |count| count.total) == if index.is_some() { matches.len() } else { 0 })
+}
+
+#[quickcheck]
+fn visible_matches(init: Vec<u8>, pattern: Vec<u8>, start: usize, end: usize) -> TestResult {
+    let (init, pattern) = (text(init), text(pattern).replace('\n', ""));
+    let (start, end) = (start % (init.len() + 1), end % (init.len() + 1));
+    if pattern.is_empty() || start > end {
+        return TestResult::discard();
+    }
+    let text = String::from(&init[..]);
+    let search = search(&text, Direction::Forward, 0, &format!("\\V{}", pattern));
+
+    let regex = Regex::new(&format!("\\V{}", pattern)).unwrap();
+    let expected: Vec<_> = regex.find_iter(text.as_str())
+        .filter(|found| found.start < end && found.end > start)
+        .collect();
+    TestResult::from_bool(search.visible(&text, start..end) == expected)
+}
@@ -10,4 +10,18 @@
 extern crate quickcheck;
 extern crate viola;
 
+mod block;
+mod ex;
+mod fixture;
 mod gap;
+mod history;
+mod motion;
+mod normal;
+mod object;
+mod operation;
+mod operator;
+mod piece;
+mod regex;
+mod register;
+mod search;
+mod width;
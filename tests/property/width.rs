@@ -0,0 +1,77 @@
+use std::string::String as StdString;
+
+use quickcheck::TestResult;
+use viola::gap::{Str, String};
+use viola::width::{Curswant, char_width, offset_at, width};
+
+#[quickcheck]
+fn width_sum(a: StdString, b: StdString) -> TestResult {
+    if a.contains('\t') || b.contains('\t') {
+        return TestResult::discard();
+    }
+    let ab = a.clone() + &b;
+    let sum = width(Str::Contiguous(&a), 8) + width(Str::Contiguous(&b), 8);
+    TestResult::from_bool(width(Str::Contiguous(&ab), 8) == sum)
+}
+
+#[quickcheck]
+fn offset_at_width(init: StdString, tabstop: usize) -> bool {
+    let tabstop = tabstop % 16 + 1;
+    let slice = Str::Contiguous(&init);
+    init.char_indices().all(|(offset, ch)| {
+        let col = width(slice.slice(..offset), tabstop);
+        let cells = char_width(ch, col, tabstop);
+        cells == 0 || (col..(col + cells)).all(|col| offset_at(slice, col, tabstop) == offset)
+    }) && offset_at(slice, width(slice, tabstop), tabstop) == init.len()
+}
+
+#[quickcheck]
+fn virtcol_offset(init: StdString, offset: usize) -> TestResult {
+    if !init.is_char_boundary(offset) || init[offset..].chars().next().map_or(true, |ch| {
+        ch == '\n' || char_width(ch, 0, 1) == 0
+    }) {
+        return TestResult::discard();
+    }
+    let buf = String::from(&init[..]);
+    let col = buf.virtcol(offset, 4);
+    TestResult::from_bool(buf.virtcol_offset(buf.line_of(offset), col, 4) == offset)
+}
+
+#[test]
+fn widths() {
+    let cases = [
+        ("a\tb", 9),
+        ("\t\t", 16),
+        ("abc\t", 8),
+        ("日本語", 6),
+        ("e\u{301}", 1),
+        ("\u{1f600}", 2),
+        ("\u{31350}\u{4dc0}", 4),
+        ("\u{378}", 1),
+        ("\x01\x7f", 4),
+        ("\u{85}", 4),
+        ("\u{ad}", 1),
+        ("\u{200b}", 0),
+    ];
+    for &(s, expected) in &cases {
+        assert_eq!(width(Str::Contiguous(s), 8), expected, "{:?}", s);
+    }
+}
+
+#[test]
+fn curswant() {
+    let buf = String::from("a\tbc\n日本語x\nxy\n\nwxyz");
+    let line = |n| buf.slice(buf.line_range(n));
+
+    let want = Curswant::at(line(0), 2, 8);
+    assert_eq!(want, Curswant::Col(8));
+    assert_eq!(want.offset(line(1), 8), 9);
+    assert_eq!(want.offset(line(2), 8), 1);
+    assert_eq!(want.offset(line(3), 8), 0);
+    assert_eq!(want.offset(line(4), 8), 3);
+
+    let want = Curswant::Col(3);
+    assert_eq!(want.offset(line(0), 8), 1);
+    assert_eq!(want.offset(line(1), 8), 3);
+    assert_eq!(Curswant::End.offset(line(1), 8), 9);
+}